@@ -1,27 +1,14 @@
-use sxd_xpath::{nodeset::Node, Context, Error, Factory, Value};
-
 fn main() -> anyhow::Result<()> {
     let contents = include_str!("github_trending.html");
     let package = sxd_html::parse_html(contents);
     let document = package.as_document();
 
     let mut trending_repos: Vec<String> = Default::default();
-    let repo_as = match evaluate_xpath_node(document.root(), "//article/h1/a")? {
-        Value::Nodeset(set) => set,
-        _ => panic!("Expected node set"),
-    }
-    .document_order();
+    let repo_as = sxd_html::select(document.root(), "//article/h1/a")?.document_order();
 
     for repo_a in repo_as {
-        let user = evaluate_xpath_node(repo_a, "./span/text()")?.into_string();
-        #[allow(clippy::unwrap_used)]
-        let name = repo_a
-            .children()
-            .last()
-            .unwrap()
-            .text()
-            .map(|t| t.text())
-            .unwrap_or_default();
+        let user = sxd_html::select_text(repo_a, "./span/text()")?;
+        let name = sxd_html::select_text(repo_a, "./text()[last()]")?;
         trending_repos.push(format!("{}{}", user.trim(), name.trim()));
     }
 
@@ -32,15 +19,3 @@ fn main() -> anyhow::Result<()> {
 
     Ok(())
 }
-
-fn evaluate_xpath_node<'d>(node: impl Into<Node<'d>>, expr: &str) -> Result<Value<'d>, Error> {
-    let factory = Factory::new();
-    let expression = factory.build(expr)?;
-    let expression = expression.ok_or(Error::NoXPath)?;
-
-    let context = Context::new();
-
-    expression
-        .evaluate(&context, node.into())
-        .map_err(Into::into)
-}
@@ -0,0 +1,11 @@
+fn main() -> anyhow::Result<()> {
+    let contents = include_str!("github_trending.html");
+    let package = sxd_html::parse_html(contents);
+
+    let spec = sxd_html::ExtractionSpec::from_json(include_str!("github_trending.json"))?;
+    let value = spec.extract(&package)?;
+
+    println!("{}", serde_json::to_string_pretty(&value)?);
+
+    Ok(())
+}
@@ -0,0 +1,569 @@
+use std::collections::HashMap;
+
+use sxd_document::{
+    dom::{ChildOfElement, Element, ParentOfChild},
+    Package,
+};
+use sxd_xpath::nodeset::Node;
+
+use crate::{util, PageMetadata};
+
+/// Class and id fragments of navigation, comments, ads and other page
+/// furniture.
+const UNLIKELY_CANDIDATES: &[&str] = &[
+    "ad-break",
+    "agegate",
+    "banner",
+    "breadcrumb",
+    "combx",
+    "comment",
+    "community",
+    "cover-wrap",
+    "disqus",
+    "extra",
+    "footer",
+    "gdpr",
+    "header",
+    "legends",
+    "menu",
+    "modal",
+    "pager",
+    "pagination",
+    "popup",
+    "related",
+    "remark",
+    "replies",
+    "rss",
+    "share",
+    "shoutbox",
+    "sidebar",
+    "skyscraper",
+    "social",
+    "sponsor",
+    "supplemental",
+    "yom-remote",
+];
+
+/// Class and id fragments that keep an unlikely candidate in the running.
+const MAYBE_CANDIDATES: &[&str] = &[
+    "and", "article", "body", "column", "content", "main", "shadow",
+];
+
+/// Class and id fragments that make an element more likely to hold the
+/// article.
+const POSITIVE_CLASSES: &[&str] = &[
+    "article", "blog", "body", "content", "entry", "h-entry", "hentry", "main", "page", "post",
+    "story", "text",
+];
+
+/// Class and id fragments that make an element less likely to hold the
+/// article.
+const NEGATIVE_CLASSES: &[&str] = &[
+    "banner",
+    "com-",
+    "combx",
+    "comment",
+    "contact",
+    "foot",
+    "footnote",
+    "hidden",
+    "masthead",
+    "media",
+    "meta",
+    "outbrain",
+    "promo",
+    "related",
+    "scroll",
+    "share",
+    "shoutbox",
+    "shopping",
+    "sidebar",
+    "skyscraper",
+    "sponsor",
+    "tags",
+    "tool",
+    "widget",
+];
+
+/// Elements that never belong to the article text.
+const REMOVED_ELEMENTS: &[&str] = &[
+    "aside", "button", "embed", "footer", "form", "iframe", "input", "nav", "noscript", "object",
+    "script", "select", "style", "template", "textarea",
+];
+
+/// Elements whose text is scored as a paragraph.
+const PARAGRAPH_ELEMENTS: &[&str] = &["p", "pre", "td", "blockquote"];
+
+/// The `meta` names that give the publication date.
+const DATE_META_NAMES: &[&str] = &[
+    "date",
+    "dc.date",
+    "dc.date.issued",
+    "dcterms.created",
+    "pubdate",
+    "publishdate",
+];
+
+/// The main content of a page and its metadata, returned by
+/// [`extract_article`].
+#[derive(Debug)]
+pub struct Article {
+    /// A copy of the element holding the article, as the document element
+    /// of its own package.
+    package: Package,
+    /// The `og:title`, else the `title` of the document without the site
+    /// name, else the text of the first `h1`.
+    pub title: Option<String>,
+    /// The author, from `<meta name="author">` or a byline in the page.
+    pub byline: Option<String>,
+    /// The publication date as written, from `article:published_time`,
+    /// date `meta` elements, `itemprop="datePublished"` or the first
+    /// `<time datetime>` of the content.
+    pub published: Option<String>,
+}
+
+impl Article {
+    /// Returns a copy of the element holding the article, cleaned of
+    /// navigation, forms, scripts and link-heavy blocks.
+    pub fn content(&self) -> Element<'_> {
+        #[allow(clippy::expect_used)]
+        self.package
+            .as_document()
+            .root()
+            .children()
+            .into_iter()
+            .find_map(|child| child.element())
+            .expect("the article package has a document element")
+    }
+
+    /// Returns the [`Package`] holding the copy of the content.
+    pub fn package(&self) -> &Package {
+        &self.package
+    }
+
+    /// Returns the rendered text of the content.
+    pub fn text(&self) -> String {
+        crate::rendered_text(self.content())
+    }
+
+    /// Returns the content serialized as HTML.
+    pub fn html(&self) -> String {
+        crate::to_html(self.content())
+    }
+}
+
+/// Finds the main content of the document in `package`, such as the body
+/// of a news article or blog post, in the manner of Readability.
+///
+/// Paragraphs with enough text score their three nearest ancestors by
+/// their length and number of commas, the farther ancestors less.
+/// Candidates start from a score given by their element name and by their
+/// class and id, which favour names such as `article` or `content` and
+/// penalize names such as `sidebar` or `comment`, and the candidate with
+/// the best score weighted by its link density wins. Hidden elements and
+/// elements whose class or id suggests page furniture are not scored.
+///
+/// The winning element is copied into a new package and the copy is
+/// cleaned: navigation, forms, scripts, hidden and unlikely elements and
+/// link-heavy blocks are removed from it, while `package` is left as it
+/// was. Returns `None` if no paragraph has enough text.
+///
+/// ```rust
+/// let package = sxd_html::parse_html(r#"
+///     <nav><a href="/">Home</a> <a href="/about">About</a></nav>
+///     <div class="post">
+///         <p>The first paragraph of the post, long enough to count, tells the story.</p>
+///         <p>The second paragraph of the post, also long enough, ends the story.</p>
+///     </div>
+/// "#);
+/// let article = sxd_html::extract_article(&package).unwrap();
+/// assert_eq!(article.content().attribute_value("class"), Some("post"));
+/// assert!(!article.text().contains("About"));
+/// ```
+pub fn extract_article(package: &Package) -> Option<Article> {
+    let root = package.as_document().root();
+    let elements = util::descendant_elements(ParentOfChild::Root(root));
+
+    let mut scores = HashMap::new();
+    let mut candidates = vec![];
+    for &element in &elements {
+        let name = util::html_local_name(&element).unwrap_or("");
+        if !PARAGRAPH_ELEMENTS.contains(&name) || is_excluded(element) {
+            continue;
+        }
+        let text = collapse_whitespace(&Node::Element(element).string_value());
+        let length = text.chars().count();
+        if length < 25 {
+            continue;
+        }
+
+        let score = 1.0 + text.matches(',').count() as f64 + (length as f64 / 100.0).min(3.0);
+        let parent = element.parent().and_then(|parent| parent.element());
+        let ancestors =
+            std::iter::successors(parent, |ancestor| ancestor.parent()?.element()).take(3);
+        for (level, ancestor) in ancestors.enumerate() {
+            let divider = match level {
+                0 => 1.0,
+                1 => 2.0,
+                level => level as f64 * 3.0,
+            };
+            let candidate_score = scores.entry(ancestor).or_insert_with(|| {
+                candidates.push(ancestor);
+                initial_score(ancestor)
+            });
+            *candidate_score += score / divider;
+        }
+    }
+
+    let mut best: Option<(Element<'_>, f64)> = None;
+    for candidate in candidates {
+        let score = scores[&candidate] * (1.0 - link_density(candidate));
+        if best.is_none_or(|(_, best_score)| score > best_score) {
+            best = Some((candidate, score));
+        }
+    }
+    let content = best?.0;
+
+    let copy = Package::new();
+    let document = copy.as_document();
+    if let ChildOfElement::Element(element) = crate::import_node(&document, content) {
+        document.root().append_child(element);
+        clean(element);
+    }
+
+    let metadata = PageMetadata::extract(package);
+    Some(Article {
+        title: title(&metadata, &elements),
+        byline: byline(&metadata, &elements),
+        published: published(&metadata, &elements, content),
+        package: copy,
+    })
+}
+
+fn initial_score(element: Element<'_>) -> f64 {
+    let score = match util::html_local_name(&element) {
+        Some("div" | "article") => 5.0,
+        Some("pre" | "td" | "blockquote") => 3.0,
+        Some("address" | "ol" | "ul" | "dl" | "dd" | "dt" | "li" | "form") => -3.0,
+        Some("h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "th") => -5.0,
+        _ => 0.0,
+    };
+    score + class_weight(element)
+}
+
+/// Returns the lowercase class and id of `element`.
+fn class_and_id(element: Element<'_>) -> String {
+    let class = element.attribute_value("class").unwrap_or("");
+    let id = element.attribute_value("id").unwrap_or("");
+    format!("{} {}", class, id).to_ascii_lowercase()
+}
+
+fn class_weight(element: Element<'_>) -> f64 {
+    let class_and_id = class_and_id(element);
+    let matches = |fragments: &[&str]| {
+        fragments
+            .iter()
+            .any(|fragment| class_and_id.contains(fragment))
+    };
+    let mut weight = 0.0;
+    if matches(POSITIVE_CLASSES) {
+        weight += 25.0;
+    }
+    if matches(NEGATIVE_CLASSES) {
+        weight -= 25.0;
+    }
+    weight
+}
+
+fn is_hidden(element: Element<'_>) -> bool {
+    let style: String = element
+        .attribute_value("style")
+        .unwrap_or("")
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    element.attribute("hidden").is_some()
+        || element.attribute_value("aria-hidden") == Some("true")
+        || style.contains("display:none")
+        || style.contains("visibility:hidden")
+}
+
+fn is_unlikely(element: Element<'_>) -> bool {
+    if matches!(
+        util::html_local_name(&element),
+        Some("body" | "html" | "article" | "main")
+    ) {
+        return false;
+    }
+    let class_and_id = class_and_id(element);
+    UNLIKELY_CANDIDATES
+        .iter()
+        .any(|fragment| class_and_id.contains(fragment))
+        && !MAYBE_CANDIDATES
+            .iter()
+            .any(|fragment| class_and_id.contains(fragment))
+}
+
+/// Returns whether `element` or one of its ancestors is hidden, unlikely
+/// to hold the article, or never part of it.
+fn is_excluded(element: Element<'_>) -> bool {
+    std::iter::successors(Some(element), |element| element.parent()?.element()).any(|element| {
+        let name = util::html_local_name(&element).unwrap_or("");
+        REMOVED_ELEMENTS.contains(&name) || is_hidden(element) || is_unlikely(element)
+    })
+}
+
+fn text_length(element: Element<'_>) -> usize {
+    collapse_whitespace(&Node::Element(element).string_value())
+        .chars()
+        .count()
+}
+
+/// Returns the share of the text of `element` that is in links.
+fn link_density(element: Element<'_>) -> f64 {
+    let length = text_length(element);
+    if length == 0 {
+        return 0.0;
+    }
+    let mut link_length = 0;
+    let mut pending = vec![element];
+    while let Some(current) = pending.pop() {
+        for child in current
+            .children()
+            .into_iter()
+            .filter_map(|child| child.element())
+        {
+            match util::html_local_name(&child) {
+                Some("a") => link_length += text_length(child),
+                _ => pending.push(child),
+            }
+        }
+    }
+    link_length as f64 / length as f64
+}
+
+/// Removes the elements of `content` that are not part of the article.
+fn clean(content: Element<'_>) {
+    for element in util::descendant_elements(ParentOfChild::Element(content)) {
+        let name = util::html_local_name(&element).unwrap_or("");
+        let removed = REMOVED_ELEMENTS.contains(&name)
+            || is_hidden(element)
+            || is_unlikely(element)
+            || is_link_heavy(element)
+            || (name == "p" && text_length(element) == 0 && !has_media(element));
+        if removed {
+            element.remove_from_parent();
+        }
+    }
+}
+
+/// Returns whether `element` is a block that looks like a list of links or
+/// other furniture rather than article text.
+fn is_link_heavy(element: Element<'_>) -> bool {
+    if !matches!(
+        util::html_local_name(&element),
+        Some("div" | "section" | "ul" | "ol" | "dl" | "table")
+    ) {
+        return false;
+    }
+    let weight = class_weight(element);
+    if weight < 0.0 {
+        return true;
+    }
+    let text = Node::Element(element).string_value();
+    if text.matches(',').count() >= 10 {
+        return false;
+    }
+    let link_density = link_density(element);
+    (weight < 25.0 && link_density > 0.2 && text_length(element) < 25 * 10)
+        || (weight >= 25.0 && link_density > 0.5)
+}
+
+fn has_media(element: Element<'_>) -> bool {
+    util::descendant_elements(ParentOfChild::Element(element))
+        .iter()
+        .any(|descendant| {
+            matches!(
+                util::html_local_name(descendant),
+                Some("img" | "picture" | "video" | "audio")
+            ) || descendant.name().local_part() == "svg"
+        })
+}
+
+fn title(metadata: &PageMetadata, elements: &[Element<'_>]) -> Option<String> {
+    if let Some(title) = metadata.property("og:title") {
+        return Some(collapse_whitespace(title));
+    }
+    if let Some(title) = &metadata.title {
+        // Drop the site name from titles such as `Post title | Site`.
+        let separator = [" | ", " - ", " \u{2013} ", " \u{2014} ", " :: ", " / "]
+            .iter()
+            .filter_map(|separator| title.rfind(separator))
+            .max();
+        return match separator.map(|index| title[..index].trim()) {
+            Some(head) if head.split_whitespace().count() >= 3 => Some(head.to_string()),
+            _ => Some(title.clone()),
+        };
+    }
+    elements
+        .iter()
+        .find(|element| util::html_local_name(element) == Some("h1"))
+        .map(|h1| collapse_whitespace(&Node::Element(*h1).string_value()))
+}
+
+fn byline(metadata: &PageMetadata, elements: &[Element<'_>]) -> Option<String> {
+    let meta_author = metadata.meta.iter().find_map(|tag| match &tag.name {
+        Some(name) if name.eq_ignore_ascii_case("author") => tag.content.as_deref(),
+        _ => None,
+    });
+    if let Some(author) = meta_author.filter(|author| !author.trim().is_empty()) {
+        return Some(collapse_whitespace(author));
+    }
+
+    elements.iter().find_map(|&element| {
+        let tokens = |name| {
+            element
+                .attribute_value(name)
+                .unwrap_or("")
+                .split_ascii_whitespace()
+                .any(|token| token.eq_ignore_ascii_case("author"))
+        };
+        let class_and_id = class_and_id(element);
+        let is_byline = tokens("rel")
+            || tokens("itemprop")
+            || class_and_id.contains("byline")
+            || class_and_id.contains("author");
+        if !is_byline || is_hidden(element) || util::html_local_name(&element) == Some("meta") {
+            return None;
+        }
+        let text = collapse_whitespace(&Node::Element(element).string_value());
+        let length = text.chars().count();
+        (length > 0 && length < 100).then_some(text)
+    })
+}
+
+fn published(
+    metadata: &PageMetadata,
+    elements: &[Element<'_>],
+    content: Element<'_>,
+) -> Option<String> {
+    if let Some(published) = metadata.property("article:published_time") {
+        return Some(published.trim().to_string());
+    }
+    let meta_date = metadata.meta.iter().find_map(|tag| match &tag.name {
+        Some(name) if DATE_META_NAMES.contains(&name.to_ascii_lowercase().as_str()) => {
+            tag.content.as_deref()
+        }
+        _ => None,
+    });
+    if let Some(date) = meta_date {
+        return Some(date.trim().to_string());
+    }
+
+    let item_date = elements.iter().find(|element| {
+        element
+            .attribute_value("itemprop")
+            .unwrap_or("")
+            .split_ascii_whitespace()
+            .any(|token| token == "datePublished")
+    });
+    if let Some(&element) = item_date {
+        let date = ["content", "datetime"]
+            .iter()
+            .find_map(|&name| element.attribute_value(name))
+            .map(str::to_string)
+            .unwrap_or_else(|| collapse_whitespace(&Node::Element(element).string_value()));
+        return Some(date.trim().to_string());
+    }
+
+    util::descendant_elements(ParentOfChild::Element(content))
+        .into_iter()
+        .filter(|element| util::html_local_name(element) == Some("time"))
+        .find_map(|time| time.attribute_value("datetime"))
+        .map(|datetime| datetime.trim().to_string())
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_ascii_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"
+        <html><head>
+            <title>Rivers rise after a week of rain | Daily News</title>
+            <meta name="author" content="Jo Reporter">
+            <meta property="article:published_time" content="2025-03-04T05:06:07Z">
+        </head><body>
+            <header class="masthead"><a href="/">Daily News</a></header>
+            <nav><a href="/world">World</a> <a href="/sport">Sport</a></nav>
+            <div id="main">
+                <div class="sidebar"><p>Subscribe to our newsletter, it has many great stories.</p></div>
+                <article class="story">
+                    <h1>Rivers rise after a week of rain</h1>
+                    <p>Rivers across the region rose sharply on Monday, after a week of heavy rain.</p>
+                    <p>Officials said that the water, which reached record levels, should recede soon.</p>
+                    <div class="share"><a href="/share">Share</a></div>
+                    <ul><li><a href="/a">Other story</a></li><li><a href="/b">Another</a></li></ul>
+                    <p style="display: none">A hidden paragraph that should never be shown.</p>
+                    <script>track();</script>
+                    <p>Residents were advised to stay away from the banks, and to follow the news.</p>
+                </article>
+                <div class="comments">
+                    <p>Great article, thanks for writing it, I enjoyed it very much, really.</p>
+                </div>
+            </div>
+        </body></html>
+    "#;
+
+    #[test]
+    fn test_extract_article() {
+        let package = crate::parse_html(PAGE);
+        let article = extract_article(&package).unwrap();
+
+        assert_eq!(article.content().attribute_value("class"), Some("story"));
+        assert_eq!(
+            article.title.as_deref(),
+            Some("Rivers rise after a week of rain")
+        );
+        assert_eq!(article.byline.as_deref(), Some("Jo Reporter"));
+        assert_eq!(article.published.as_deref(), Some("2025-03-04T05:06:07Z"));
+
+        let text = article.text();
+        assert!(text.starts_with("Rivers rise after a week of rain\n\nRivers across"));
+        assert!(text.ends_with("follow the news."));
+        for removed in [
+            "Share",
+            "Other story",
+            "hidden",
+            "track",
+            "newsletter",
+            "Great",
+        ] {
+            assert!(!text.contains(removed), "{}", removed);
+        }
+
+        let original = crate::to_html(package.as_document().root());
+        assert!(original.contains("track();") && original.contains("Other story"));
+        assert_eq!(extract_article(&package).unwrap().text(), text);
+    }
+
+    #[test]
+    fn test_in_page_metadata() {
+        let package = crate::parse_html(
+            r#"<title>Notes</title><div><span class="byline">By Sam</span>
+            <time datetime="2024-12-01">December</time>
+            <p>Only a short note, but long enough to be read as the content.</p></div>"#,
+        );
+        let article = extract_article(&package).unwrap();
+        assert_eq!(util::html_local_name(&article.content()), Some("div"));
+        assert_eq!(article.title.as_deref(), Some("Notes"));
+        assert_eq!(article.byline.as_deref(), Some("By Sam"));
+        assert_eq!(article.published.as_deref(), Some("2024-12-01"));
+
+        assert!(extract_article(&crate::parse_html("<p>Too short.</p>")).is_none());
+    }
+}
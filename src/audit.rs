@@ -0,0 +1,450 @@
+use sxd_document::{
+    dom::{Attribute, Element, ParentOfChild},
+    Package,
+};
+use sxd_xpath::nodeset::Node;
+use url::Url;
+
+use crate::{
+    sanitize::{url_scheme, URL_ATTRIBUTES},
+    util,
+};
+
+const XLINK_NAMESPACE: &str = "http://www.w3.org/1999/xlink";
+
+/// How much an [`AuditFinding`] matters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Worth a look, but commonly harmless.
+    Low,
+    /// Weakens the page's defences or may leak data.
+    Medium,
+    /// Runs script or hands the page to another origin.
+    High,
+}
+
+/// The kind of risky markup an [`AuditFinding`] reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AuditKind {
+    /// An inline event handler attribute such as `onclick`.
+    EventHandler,
+    /// A `javascript:` or `vbscript:` URL.
+    ScriptUrl,
+    /// A `data:` URL.
+    DataUrl,
+    /// A link or form with `target="_blank"` but without
+    /// `rel="noopener"` or `rel="noreferrer"`.
+    TargetBlank,
+    /// A form submitting to another origin than the document's.
+    CrossOriginForm,
+    /// A resource or form loaded over HTTP by an HTTPS document.
+    MixedContent,
+    /// An inline script without a `nonce`.
+    InlineScript,
+    /// A `<base>` element that changes where relative URLs point in a
+    /// suspicious way.
+    BaseHijacking,
+}
+
+/// Risky markup found by [`audit`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditFinding<'d> {
+    /// What was found.
+    pub kind: AuditKind,
+    /// How much it matters.
+    pub severity: Severity,
+    /// The offending element, or attribute for attribute findings.
+    pub node: Node<'d>,
+    /// The [`absolute_xpath`](crate::absolute_xpath) of `node`.
+    pub xpath: String,
+    /// A description of the problem.
+    pub message: String,
+}
+
+/// Audits the document in `package` for risky markup.
+///
+/// Findings are reported in document order for inline event handlers,
+/// `javascript:` and `data:` URLs, `target="_blank"` without
+/// `rel="noopener"`, inline scripts without a `nonce` and misplaced
+/// `<base>` elements. Given the `document_url`, forms submitting to other
+/// origins, `<base>` elements pointing to other origins and, for HTTPS
+/// documents, resources and forms loaded over HTTP are reported too.
+///
+/// ```rust
+/// use sxd_html::{AuditKind, Severity};
+///
+/// let package = sxd_html::parse_html(r#"<a href="javascript:go()" onclick="go()">Go</a>"#);
+/// let findings = sxd_html::audit(&package, None);
+/// assert_eq!(findings[0].kind, AuditKind::ScriptUrl);
+/// assert_eq!(findings[0].severity, Severity::High);
+/// assert_eq!(findings[0].xpath, "/html[1]/body[1]/a[1]/@href");
+/// assert_eq!(findings[1].kind, AuditKind::EventHandler);
+/// ```
+pub fn audit<'d>(package: &'d Package, document_url: Option<&Url>) -> Vec<AuditFinding<'d>> {
+    let base_url = document_url.map(|document_url| crate::base_url(package, document_url));
+    let mut auditor = Auditor {
+        document_url,
+        base_url: base_url.as_ref(),
+        findings: vec![],
+        seen_base: false,
+        seen_url: false,
+    };
+    let root = package.as_document().root();
+    for element in util::descendant_elements(ParentOfChild::Root(root)) {
+        auditor.element(element);
+    }
+    auditor.findings
+}
+
+struct Auditor<'a, 'd> {
+    document_url: Option<&'a Url>,
+    base_url: Option<&'a Url>,
+    findings: Vec<AuditFinding<'d>>,
+    /// Whether a `<base href>` was seen.
+    seen_base: bool,
+    /// Whether an element with a URL attribute was seen.
+    seen_url: bool,
+}
+
+impl<'d> Auditor<'_, 'd> {
+    fn push(&mut self, kind: AuditKind, severity: Severity, node: Node<'d>, message: String) {
+        self.findings.push(AuditFinding {
+            kind,
+            severity,
+            node,
+            xpath: crate::absolute_xpath(node),
+            message,
+        });
+    }
+
+    fn element(&mut self, element: Element<'d>) {
+        let name = util::html_local_name(&element);
+        match name {
+            Some("base") => self.base(element),
+            Some("script") => self.script(element),
+            _ => {}
+        }
+        for attribute in element.attributes() {
+            self.attribute(element, name, attribute);
+        }
+        if let Some("a" | "area" | "form") = name {
+            self.target(element);
+        }
+        if name == Some("form") || element.attribute("formaction").is_some() {
+            self.form(element);
+        }
+    }
+
+    fn attribute(&mut self, element: Element<'d>, name: Option<&str>, attribute: Attribute<'d>) {
+        let attribute_name = attribute.name();
+        let local_name = attribute_name.local_part();
+        let node = Node::Attribute(attribute);
+        let value = attribute.value();
+
+        let urls = match attribute_name.namespace_uri() {
+            None if local_name == "srcset" => crate::links::srcset_urls(value),
+            None if URL_ATTRIBUTES.contains(&local_name) => vec![value],
+            Some(XLINK_NAMESPACE) if local_name == "href" => vec![value],
+            None if local_name.len() > 2 && local_name.to_ascii_lowercase().starts_with("on") => {
+                self.push(
+                    AuditKind::EventHandler,
+                    Severity::Medium,
+                    node,
+                    format!("inline event handler `{}`", local_name),
+                );
+                return;
+            }
+            _ => return,
+        };
+        self.seen_url = true;
+
+        for url in &urls {
+            match url_scheme(url).as_deref() {
+                Some(scheme @ ("javascript" | "vbscript")) => {
+                    self.push(
+                        AuditKind::ScriptUrl,
+                        Severity::High,
+                        node,
+                        format!("`{}:` URL in `{}`", scheme, local_name),
+                    );
+                    return;
+                }
+                Some("data") => {
+                    let severity = match resource(element, name, local_name) {
+                        Some(Resource::Passive) => Severity::Low,
+                        _ => Severity::Medium,
+                    };
+                    self.push(
+                        AuditKind::DataUrl,
+                        severity,
+                        node,
+                        format!("`data:` URL in `{}`", local_name),
+                    );
+                    return;
+                }
+                _ => {}
+            }
+        }
+
+        let (base_url, resource) = match (self.base_url, resource(element, name, local_name)) {
+            (Some(base_url), Some(resource)) if self.is_https() => (base_url, resource),
+            _ => return,
+        };
+        let insecure = urls.iter().any(|url| {
+            base_url
+                .join(url.trim())
+                .is_ok_and(|url| url.scheme() == "http")
+        });
+        if insecure {
+            let (severity, what) = match resource {
+                Resource::Active => (Severity::High, "active content"),
+                Resource::Passive => (Severity::Low, "passive content"),
+                Resource::Form => (Severity::Medium, "form submission"),
+            };
+            self.push(
+                AuditKind::MixedContent,
+                severity,
+                node,
+                format!("{} over HTTP in an HTTPS document", what),
+            );
+        }
+    }
+
+    fn target(&mut self, element: Element<'d>) {
+        let blank = element
+            .attribute_value("target")
+            .is_some_and(|target| target.trim().eq_ignore_ascii_case("_blank"));
+        let protected = element
+            .attribute_value("rel")
+            .unwrap_or("")
+            .split_ascii_whitespace()
+            .any(|rel| {
+                rel.eq_ignore_ascii_case("noopener") || rel.eq_ignore_ascii_case("noreferrer")
+            });
+        if blank && !protected {
+            self.push(
+                AuditKind::TargetBlank,
+                Severity::Low,
+                Node::Element(element),
+                "`target=\"_blank\"` without `rel=\"noopener\"`".to_string(),
+            );
+        }
+    }
+
+    fn form(&mut self, element: Element<'d>) {
+        let attribute = match util::html_local_name(&element) {
+            Some("form") => "action",
+            _ => "formaction",
+        };
+        let action = element.attribute_value(attribute).map(str::trim);
+        let (document_url, base_url, action) = match (self.document_url, self.base_url, action) {
+            (Some(document_url), Some(base_url), Some(action)) if !action.is_empty() => {
+                (document_url, base_url, action)
+            }
+            _ => return,
+        };
+        match base_url.join(action) {
+            Ok(url) if url.origin() != document_url.origin() && is_fetched(&url) => self.push(
+                AuditKind::CrossOriginForm,
+                Severity::Medium,
+                Node::Element(element),
+                format!("form submits to another origin: {}", url),
+            ),
+            _ => {}
+        }
+    }
+
+    fn script(&mut self, element: Element<'d>) {
+        let kind = element.attribute_value("type").unwrap_or("").trim();
+        let essence = kind
+            .split(';')
+            .next()
+            .unwrap_or("")
+            .trim()
+            .to_ascii_lowercase();
+        let is_javascript = essence.is_empty()
+            || essence == "module"
+            || essence.ends_with("/javascript")
+            || essence.ends_with("/ecmascript");
+        let inline = element.attribute("src").is_none()
+            && !Node::Element(element).string_value().trim().is_empty();
+        if is_javascript && inline && element.attribute("nonce").is_none() {
+            self.push(
+                AuditKind::InlineScript,
+                Severity::Medium,
+                Node::Element(element),
+                "inline script without a nonce".to_string(),
+            );
+        }
+    }
+
+    fn base(&mut self, element: Element<'d>) {
+        let href = match element.attribute_value("href") {
+            Some(href) => href.trim(),
+            None => return,
+        };
+        let node = Node::Element(element);
+
+        let in_head = std::iter::successors(Some(element), |element| element.parent()?.element())
+            .any(|element| util::html_local_name(&element) == Some("head"));
+        if !in_head {
+            self.push(
+                AuditKind::BaseHijacking,
+                Severity::High,
+                node,
+                "`<base>` outside the head".to_string(),
+            );
+        } else if self.seen_base {
+            self.push(
+                AuditKind::BaseHijacking,
+                Severity::Medium,
+                node,
+                "additional `<base>`, ignored by browsers".to_string(),
+            );
+        } else if self.seen_url {
+            self.push(
+                AuditKind::BaseHijacking,
+                Severity::Medium,
+                node,
+                "`<base>` after URLs it does not apply to".to_string(),
+            );
+        }
+
+        if let Some(document_url) = self.document_url {
+            match document_url.join(href) {
+                Ok(url) if url.origin() != document_url.origin() => self.push(
+                    AuditKind::BaseHijacking,
+                    Severity::High,
+                    node,
+                    format!("`<base>` points to another origin: {}", url),
+                ),
+                _ => {}
+            }
+        }
+        self.seen_base = true;
+    }
+
+    fn is_https(&self) -> bool {
+        self.document_url
+            .is_some_and(|document_url| document_url.scheme() == "https")
+    }
+}
+
+/// How a browser loads the URL in an attribute, for mixed content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Resource {
+    /// Scripts, styles, frames and plugins, blocked by browsers over HTTP.
+    Active,
+    /// Images and media.
+    Passive,
+    /// A form submission.
+    Form,
+}
+
+fn resource(element: Element<'_>, name: Option<&str>, attribute: &str) -> Option<Resource> {
+    let rels = || {
+        element
+            .attribute_value("rel")
+            .unwrap_or("")
+            .to_ascii_lowercase()
+            .split_ascii_whitespace()
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+    match (name?, attribute) {
+        ("script", "src") | ("iframe" | "frame", "src") | ("object", "data") | ("embed", "src") => {
+            Some(Resource::Active)
+        }
+        ("link", "href") => {
+            let rels = rels();
+            let has = |rel: &str| rels.iter().any(|candidate| candidate == rel);
+            if has("stylesheet") || has("preload") || has("modulepreload") {
+                Some(Resource::Active)
+            } else if has("icon") || has("apple-touch-icon") || has("manifest") {
+                Some(Resource::Passive)
+            } else {
+                None
+            }
+        }
+        ("img" | "source", "src" | "srcset")
+        | ("audio" | "video" | "track" | "input", "src")
+        | ("video", "poster") => Some(Resource::Passive),
+        ("form", "action") | (_, "formaction") => Some(Resource::Form),
+        _ => None,
+    }
+}
+
+/// Returns whether `url` is fetched over the network, unlike `javascript:`
+/// or `mailto:` URLs.
+fn is_fetched(url: &Url) -> bool {
+    matches!(url.scheme(), "http" | "https")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(findings: &[AuditFinding<'_>]) -> Vec<(AuditKind, Severity, String)> {
+        findings
+            .iter()
+            .map(|finding| (finding.kind, finding.severity, finding.xpath.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_audit() {
+        let package = crate::parse_html(concat!(
+            r#"<head><script nonce="r4nd">ok()</script><script>track()</script>"#,
+            r#"<script type="application/ld+json">{}</script><script src="http://cdn.example/a.js"></script>"#,
+            r#"<link rel="stylesheet" href="/site.css"><link rel="icon" href="http://cdn.example/i.png">"#,
+            r#"<base href="https://evil.example/"></head>"#,
+            r#"<body onload="init()"><a href=" java&#10;script:x()" target="_blank">a</a>"#,
+            r#"<a href="/b" target="_BLANK" rel="noopener">b</a><iframe src="data:text/html,x"></iframe>"#,
+            r#"<img src="data:image/png;base64,AA"><img srcset="a.png 1x, http://cdn.example/b.png 2x">"#,
+            r#"<form action="https://collect.example/post"><button formaction="mailto:a@example.com">b</button></form>"#,
+            r#"<svg><a xlink:href="javascript:x()"></a></svg><base href="/other/"></body>"#,
+        ));
+        let document_url = Url::parse("https://example.com/").unwrap();
+        let findings = audit(&package, Some(&document_url));
+
+        use AuditKind::*;
+        use Severity::*;
+        let head = "/html[1]/head[1]";
+        let body = "/html[1]/body[1]";
+        assert_eq!(
+            summary(&findings),
+            vec![
+                (InlineScript, Medium, format!("{}/script[2]", head)),
+                (MixedContent, High, format!("{}/script[4]/@src", head)),
+                (MixedContent, Low, format!("{}/link[2]/@href", head)),
+                (BaseHijacking, Medium, format!("{}/base[1]", head)),
+                (BaseHijacking, High, format!("{}/base[1]", head)),
+                (EventHandler, Medium, format!("{}/@onload", body)),
+                (ScriptUrl, High, format!("{}/a[1]/@href", body)),
+                (TargetBlank, Low, format!("{}/a[1]", body)),
+                (DataUrl, Medium, format!("{}/iframe[1]/@src", body)),
+                (DataUrl, Low, format!("{}/img[1]/@src", body)),
+                (MixedContent, Low, format!("{}/img[2]/@srcset", body)),
+                (CrossOriginForm, Medium, format!("{}/form[1]", body)),
+                (
+                    ScriptUrl,
+                    High,
+                    format!("{}/svg:svg[1]/svg:a[1]/@xlink:href", body)
+                ),
+                (BaseHijacking, High, format!("{}/base[1]", body)),
+            ]
+        );
+        assert_eq!(
+            findings[6].node.attribute().unwrap().value(),
+            " java\nscript:x()"
+        );
+    }
+
+    #[test]
+    fn test_audit_without_url() {
+        let package = crate::parse_html(
+            r#"<form action="https://other.example/" target="_blank" rel="noreferrer"></form><img src="http://x.example/i.png">"#,
+        );
+        assert!(audit(&package, None).is_empty());
+    }
+}
@@ -0,0 +1,221 @@
+use std::fmt;
+
+use sxd_xpath::{
+    nodeset::{Node, Nodeset},
+    Value,
+};
+
+/// Errors returned when an XPath [`Value`] cannot be converted by
+/// [`FromXPathValue`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConversionError {
+    /// The value is an empty node set, but a value was required.
+    NoMatch,
+    /// The value is a boolean, number or string where a node set was
+    /// expected.
+    NotANodeset,
+    /// The value could not be converted to a number.
+    NotANumber,
+    /// The value is a number, but not an integer in the range of the
+    /// requested type.
+    NotAnInteger,
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoMatch => write!(f, "no node matched"),
+            Self::NotANodeset => write!(f, "value is not a node set"),
+            Self::NotANumber => write!(f, "value is not a number"),
+            Self::NotAnInteger => write!(f, "value is not an integer in range"),
+        }
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
+/// Conversion from the result of an XPath expression.
+///
+/// Node sets are converted using their first node in document order. The
+/// implementations for `String`, the number types and [`Node`] are strict:
+/// an empty node set is a [`ConversionError::NoMatch`] rather than an empty
+/// string or `NaN`. Use `Option<T>` when a match is optional and `Vec<T>`
+/// to convert every node of a node set.
+///
+/// `bool` follows the XPath `boolean()` function, so an empty node set is
+/// `false`.
+pub trait FromXPathValue<'d>: Sized {
+    /// Converts `value`.
+    fn from_xpath_value(value: Value<'d>) -> Result<Self, ConversionError>;
+}
+
+impl<'d> FromXPathValue<'d> for Value<'d> {
+    fn from_xpath_value(value: Value<'d>) -> Result<Self, ConversionError> {
+        Ok(value)
+    }
+}
+
+impl<'d> FromXPathValue<'d> for Nodeset<'d> {
+    fn from_xpath_value(value: Value<'d>) -> Result<Self, ConversionError> {
+        match value {
+            Value::Nodeset(nodes) => Ok(nodes),
+            _ => Err(ConversionError::NotANodeset),
+        }
+    }
+}
+
+impl<'d> FromXPathValue<'d> for Node<'d> {
+    fn from_xpath_value(value: Value<'d>) -> Result<Self, ConversionError> {
+        Nodeset::from_xpath_value(value)?
+            .document_order_first()
+            .ok_or(ConversionError::NoMatch)
+    }
+}
+
+impl<'d> FromXPathValue<'d> for String {
+    fn from_xpath_value(value: Value<'d>) -> Result<Self, ConversionError> {
+        match value {
+            Value::Nodeset(nodes) => nodes
+                .document_order_first()
+                .map(|node| node.string_value())
+                .ok_or(ConversionError::NoMatch),
+            value => Ok(value.into_string()),
+        }
+    }
+}
+
+impl<'d> FromXPathValue<'d> for bool {
+    fn from_xpath_value(value: Value<'d>) -> Result<Self, ConversionError> {
+        Ok(value.into_boolean())
+    }
+}
+
+impl<'d> FromXPathValue<'d> for f64 {
+    fn from_xpath_value(value: Value<'d>) -> Result<Self, ConversionError> {
+        if matches!(&value, Value::Nodeset(nodes) if nodes.size() == 0) {
+            return Err(ConversionError::NoMatch);
+        }
+
+        let number = value.into_number();
+        if number.is_nan() {
+            return Err(ConversionError::NotANumber);
+        }
+        Ok(number)
+    }
+}
+
+macro_rules! impl_from_xpath_value_for_integer {
+    ($($t:ty),*) => {
+        $(
+            impl<'d> FromXPathValue<'d> for $t {
+                fn from_xpath_value(value: Value<'d>) -> Result<Self, ConversionError> {
+                    let number = f64::from_xpath_value(value)?;
+                    if number.fract() != 0.0
+                        || number < <$t>::MIN as f64
+                        || number >= <$t>::MAX as f64 + 1.0
+                    {
+                        return Err(ConversionError::NotAnInteger);
+                    }
+                    Ok(number as $t)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_xpath_value_for_integer!(i32, i64, u32, u64, usize);
+
+impl<'d, T: FromXPathValue<'d>> FromXPathValue<'d> for Option<T> {
+    fn from_xpath_value(value: Value<'d>) -> Result<Self, ConversionError> {
+        match value {
+            Value::Nodeset(nodes) if nodes.size() == 0 => Ok(None),
+            value => T::from_xpath_value(value).map(Some),
+        }
+    }
+}
+
+impl<'d, T: FromXPathValue<'d>> FromXPathValue<'d> for Vec<T> {
+    fn from_xpath_value(value: Value<'d>) -> Result<Self, ConversionError> {
+        Nodeset::from_xpath_value(value)?
+            .document_order()
+            .into_iter()
+            .map(|node| {
+                let mut nodes = Nodeset::new();
+                nodes.add(node);
+                T::from_xpath_value(Value::Nodeset(nodes))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HTML: &str = r#"<ul><li>1</li><li>2.5</li><li>x</li></ul>"#;
+
+    fn evaluate<'d, T: FromXPathValue<'d>>(
+        node: impl Into<Node<'d>>,
+        xpath: &str,
+    ) -> Result<T, ConversionError> {
+        T::from_xpath_value(crate::evaluate(node, xpath).unwrap())
+    }
+
+    #[test]
+    fn test_strict_conversions() {
+        let package = crate::parse_html(HTML);
+        let root = package.as_document().root();
+
+        assert_eq!(evaluate::<String>(root, "//li").unwrap(), "1");
+        assert_eq!(evaluate::<i64>(root, "//li[1]").unwrap(), 1);
+        assert_eq!(evaluate::<f64>(root, "//li[2]").unwrap(), 2.5);
+        assert_eq!(evaluate::<usize>(root, "count(//li)").unwrap(), 3);
+        assert!(evaluate::<bool>(root, "//li").unwrap());
+        assert!(!evaluate::<bool>(root, "//table").unwrap());
+
+        assert_eq!(
+            evaluate::<String>(root, "//table"),
+            Err(ConversionError::NoMatch)
+        );
+        assert_eq!(
+            evaluate::<f64>(root, "//li[3]"),
+            Err(ConversionError::NotANumber)
+        );
+        assert_eq!(
+            evaluate::<i64>(root, "//li[2]"),
+            Err(ConversionError::NotAnInteger)
+        );
+        assert_eq!(
+            evaluate::<u32>(root, "-1"),
+            Err(ConversionError::NotAnInteger)
+        );
+        assert_eq!(
+            evaluate::<Vec<String>>(root, "'a'"),
+            Err(ConversionError::NotANodeset)
+        );
+    }
+
+    #[test]
+    fn test_option_and_vec_conversions() {
+        let package = crate::parse_html(HTML);
+        let root = package.as_document().root();
+
+        assert_eq!(evaluate::<Option<String>>(root, "//table").unwrap(), None);
+        assert_eq!(
+            evaluate::<Option<String>>(root, "//li[3]").unwrap(),
+            Some("x".to_string())
+        );
+        assert_eq!(
+            evaluate::<Vec<String>>(root, "//li").unwrap(),
+            vec!["1", "2.5", "x"]
+        );
+        assert_eq!(
+            evaluate::<Vec<f64>>(root, "//li[position() < 3]").unwrap(),
+            vec![1.0, 2.5]
+        );
+        assert_eq!(
+            evaluate::<Vec<Option<i64>>>(root, "//li[3]"),
+            Err(ConversionError::NotANumber)
+        );
+    }
+}
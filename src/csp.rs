@@ -0,0 +1,618 @@
+use std::path::{Path, PathBuf};
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use sxd_document::{
+    dom::{Element, ParentOfChild},
+    Package,
+};
+use sxd_xpath::nodeset::Node;
+use url::Url;
+
+use crate::util;
+
+/// A hash algorithm of Content Security Policy sources and Subresource
+/// Integrity metadata.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HashAlgorithm {
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl HashAlgorithm {
+    /// Returns the name of the algorithm in hash sources, such as `sha256`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256",
+            Self::Sha384 => "sha384",
+            Self::Sha512 => "sha512",
+        }
+    }
+
+    /// Returns the algorithm named `name`, such as `sha384`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "sha256" => Some(Self::Sha256),
+            "sha384" => Some(Self::Sha384),
+            "sha512" => Some(Self::Sha512),
+            _ => None,
+        }
+    }
+
+    /// Returns the integrity metadata of `bytes`, such as
+    /// `sha384-oqVuAfXRKap7fdgcCY5uykM6+R9GqQ8K/uxy9rx7HNQlGYl1kPzQho1wx4JwY8wC`.
+    pub fn integrity(self, bytes: &[u8]) -> String {
+        let digest = match self {
+            Self::Sha256 => Sha256::digest(bytes).to_vec(),
+            Self::Sha384 => Sha384::digest(bytes).to_vec(),
+            Self::Sha512 => Sha512::digest(bytes).to_vec(),
+        };
+        format!("{}-{}", self.name(), STANDARD.encode(digest))
+    }
+}
+
+/// Where an inline hash comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InlineKind {
+    /// The body of a `<script>` without `src`.
+    Script,
+    /// The body of a `<style>`.
+    Style,
+    /// A `style` attribute.
+    StyleAttribute,
+}
+
+/// The hashes of an inline script, style or `style` attribute.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InlineHash<'d> {
+    /// The `script` or `style` element, or the element with the `style`
+    /// attribute.
+    pub element: Element<'d>,
+    /// What was hashed.
+    pub kind: InlineKind,
+    /// The SHA-256 hash, such as `sha256-bhHHL3z2…`.
+    pub sha256: String,
+    /// The SHA-384 hash.
+    pub sha384: String,
+}
+
+/// The inline content and external origins of a document, returned by
+/// [`analyze_csp`], from which a Content Security Policy is derived.
+///
+/// Origins are serialized as in CSP source lists, such as
+/// `https://cdn.example.com`, and are sorted and unique. Resources of the
+/// document's own origin are listed as `'self'` and `data:` URLs as
+/// `data:`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CspReport<'d> {
+    /// The inline scripts, styles and `style` attributes, in document order.
+    pub inline: Vec<InlineHash<'d>>,
+    /// The origins of `<script src>` and script preloads.
+    pub script_origins: Vec<String>,
+    /// The origins of stylesheets and style preloads.
+    pub style_origins: Vec<String>,
+    /// The origins of images, icons and video posters.
+    pub img_origins: Vec<String>,
+    /// The origins of font preloads and of the `@font-face` sources of
+    /// inline styles.
+    pub font_origins: Vec<String>,
+    /// The origins of `<iframe>` and `<frame>` documents.
+    pub frame_origins: Vec<String>,
+}
+
+impl CspReport<'_> {
+    /// Returns a suggested `Content-Security-Policy` header value allowing
+    /// the document's own origin, the external origins and the SHA-256
+    /// hashes of the inline content, and nothing else.
+    ///
+    /// Hashes of `style` attributes need `'unsafe-hashes'`, which is added
+    /// to `style-src` when there are any.
+    pub fn header(&self) -> String {
+        let hashes = |kinds: &[InlineKind]| {
+            self.inline
+                .iter()
+                .filter(|hash| kinds.contains(&hash.kind))
+                .map(|hash| format!("'{}'", hash.sha256))
+                .collect::<Vec<_>>()
+        };
+        let mut style_hashes = hashes(&[InlineKind::Style, InlineKind::StyleAttribute]);
+        if self
+            .inline
+            .iter()
+            .any(|hash| hash.kind == InlineKind::StyleAttribute)
+        {
+            style_hashes.insert(0, "'unsafe-hashes'".to_string());
+        }
+
+        let directive = |name: &str, origins: &[String], hashes: Vec<String>| {
+            let mut sources = vec!["'self'".to_string()];
+            for source in origins.iter().cloned().chain(hashes) {
+                if !sources.contains(&source) {
+                    sources.push(source);
+                }
+            }
+            format!("{} {}", name, sources.join(" "))
+        };
+        [
+            "default-src 'self'".to_string(),
+            directive(
+                "script-src",
+                &self.script_origins,
+                hashes(&[InlineKind::Script]),
+            ),
+            directive("style-src", &self.style_origins, style_hashes),
+            directive("img-src", &self.img_origins, vec![]),
+            directive("font-src", &self.font_origins, vec![]),
+            directive("frame-src", &self.frame_origins, vec![]),
+            "object-src 'none'".to_string(),
+            "base-uri 'self'".to_string(),
+        ]
+        .join("; ")
+    }
+}
+
+/// Collects the hashes of the inline scripts, styles and `style`
+/// attributes of the document in `package` and the origins of its
+/// external scripts, styles, images, fonts and frames.
+///
+/// URLs are resolved against the base URL of the document at
+/// `document_url` if given. Otherwise relative URLs are taken to be of the
+/// document's own origin.
+///
+/// ```rust
+/// let package = sxd_html::parse_html(r#"
+///     <script>alert(1)</script>
+///     <script src="https://cdn.example.com/lib.js"></script>
+/// "#);
+/// let report = sxd_html::analyze_csp(&package, None);
+/// assert_eq!(report.inline[0].sha256, "sha256-bhHHL3z2vDgxUt0W3dWQOrprscmda2Y5pLsLg4GF+pI=");
+/// assert_eq!(
+///     report.header(),
+///     "default-src 'self'; \
+///      script-src 'self' https://cdn.example.com 'sha256-bhHHL3z2vDgxUt0W3dWQOrprscmda2Y5pLsLg4GF+pI='; \
+///      style-src 'self'; img-src 'self'; font-src 'self'; frame-src 'self'; \
+///      object-src 'none'; base-uri 'self'",
+/// );
+/// ```
+pub fn analyze_csp<'d>(package: &'d Package, document_url: Option<&Url>) -> CspReport<'d> {
+    let base_url = document_url.map(|document_url| crate::base_url(package, document_url));
+    let origin = |value: &str| source(value, base_url.as_ref());
+    let mut report = CspReport::default();
+
+    let root = package.as_document().root();
+    for element in util::descendant_elements(ParentOfChild::Root(root)) {
+        let name = util::html_local_name(&element);
+        let attribute = |name| element.attribute_value(name);
+        let rels = attribute("rel").unwrap_or("").to_ascii_lowercase();
+        let has_rel = |rel| rels.split_ascii_whitespace().any(|token| token == rel);
+        let preload_as = attribute("as").unwrap_or("").to_ascii_lowercase();
+
+        match name {
+            Some("script") => match attribute("src") {
+                Some(src) => push(&mut report.script_origins, origin(src)),
+                None => report.inline.push(inline_hash(element, InlineKind::Script)),
+            },
+            Some("style") => report.inline.push(inline_hash(element, InlineKind::Style)),
+            Some("link") => {
+                let href = match attribute("href") {
+                    Some(href) => href,
+                    None => continue,
+                };
+                let list = if has_rel("stylesheet") || (has_rel("preload") && preload_as == "style")
+                {
+                    &mut report.style_origins
+                } else if has_rel("modulepreload") || (has_rel("preload") && preload_as == "script")
+                {
+                    &mut report.script_origins
+                } else if has_rel("preload") && preload_as == "font" {
+                    &mut report.font_origins
+                } else if has_rel("icon") || has_rel("apple-touch-icon") {
+                    &mut report.img_origins
+                } else {
+                    continue;
+                };
+                push(list, origin(href));
+            }
+            Some("img" | "source") => {
+                let srcset = attribute("srcset").map(crate::links::srcset_urls);
+                for url in attribute("src")
+                    .into_iter()
+                    .chain(srcset.into_iter().flatten())
+                {
+                    push(&mut report.img_origins, origin(url));
+                }
+            }
+            Some("video") => {
+                if let Some(poster) = attribute("poster") {
+                    push(&mut report.img_origins, origin(poster));
+                }
+            }
+            Some("iframe" | "frame") => {
+                if let Some(src) = attribute("src") {
+                    push(&mut report.frame_origins, origin(src));
+                }
+            }
+            _ => {}
+        }
+
+        if name == Some("style") {
+            let css = Node::Element(element).string_value();
+            for url in font_face_urls(&css) {
+                push(&mut report.font_origins, origin(url));
+            }
+        }
+        if element.attribute("style").is_some() {
+            report
+                .inline
+                .push(inline_hash(element, InlineKind::StyleAttribute));
+        }
+    }
+
+    for list in [
+        &mut report.script_origins,
+        &mut report.style_origins,
+        &mut report.img_origins,
+        &mut report.font_origins,
+        &mut report.frame_origins,
+    ] {
+        list.sort();
+    }
+    report
+}
+
+fn inline_hash(element: Element<'_>, kind: InlineKind) -> InlineHash<'_> {
+    let content = match kind {
+        InlineKind::StyleAttribute => element.attribute_value("style").unwrap_or("").to_string(),
+        InlineKind::Script | InlineKind::Style => Node::Element(element).string_value(),
+    };
+    InlineHash {
+        element,
+        kind,
+        sha256: HashAlgorithm::Sha256.integrity(content.as_bytes()),
+        sha384: HashAlgorithm::Sha384.integrity(content.as_bytes()),
+    }
+}
+
+fn push(list: &mut Vec<String>, source: Option<String>) {
+    if let Some(source) = source {
+        if !list.contains(&source) {
+            list.push(source);
+        }
+    }
+}
+
+/// Returns the CSP source expression allowing the URL `value`.
+fn source(value: &str, base_url: Option<&Url>) -> Option<String> {
+    let value = value.trim();
+    let url = match base_url {
+        Some(base_url) => base_url.join(value).ok()?,
+        None => match Url::parse(value) {
+            Ok(url) => url,
+            Err(url::ParseError::RelativeUrlWithoutBase) => return Some("'self'".to_string()),
+            Err(_) => return None,
+        },
+    };
+    match url.scheme() {
+        "data" | "blob" => return Some(format!("{}:", url.scheme())),
+        "http" | "https" => {}
+        _ => return None,
+    }
+    if base_url.is_some_and(|base_url| base_url.origin() == url.origin()) {
+        return Some("'self'".to_string());
+    }
+    Some(url.origin().ascii_serialization())
+}
+
+/// Returns the `url()` sources of the `@font-face` rules of `css`.
+fn font_face_urls(css: &str) -> Vec<&str> {
+    let mut urls = vec![];
+    for rule in css.split("@font-face").skip(1) {
+        let block = match rule.split_once('{') {
+            Some((_, block)) => block.split('}').next().unwrap_or(""),
+            None => continue,
+        };
+        for part in block.split("url(").skip(1) {
+            let url = part.split(')').next().unwrap_or("").trim();
+            urls.push(url.trim_matches(|c| c == '"' || c == '\''));
+        }
+    }
+    urls
+}
+
+/// The state of the `integrity` of a subresource, checked by
+/// [`check_integrity`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IntegrityStatus {
+    /// The file matches the `integrity` attribute.
+    Valid,
+    /// The file does not match the `integrity` attribute.
+    Mismatch,
+    /// The element has no `integrity` attribute.
+    Missing,
+    /// The `integrity` attribute has no hash of a supported algorithm.
+    Unsupported,
+    /// The file could not be read.
+    Unreadable,
+}
+
+/// The result of checking the `integrity` of a local subresource.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntegrityCheck<'d> {
+    /// The `<script>` or `<link>` element.
+    pub element: Element<'d>,
+    /// The `src` or `href` attribute.
+    pub url: String,
+    /// The local file the URL points to.
+    pub path: PathBuf,
+    /// The `integrity` attribute, if any.
+    pub expected: Option<String>,
+    /// The integrity metadata of the file, with the strongest algorithm of
+    /// `expected`, or SHA-384 if there is none. `None` if the file could
+    /// not be read.
+    pub computed: Option<String>,
+    /// The outcome of the check.
+    pub status: IntegrityStatus,
+}
+
+/// Computes and verifies the `integrity` of the scripts and stylesheets of
+/// the document in `package` that are files below the directory `root`.
+///
+/// The `src` of `<script>` elements and the `href` of stylesheet and
+/// preload `<link>` elements are mapped to files as if the document were
+/// at the top of `root`: `/`-rooted and relative URLs alike are looked up
+/// from `root`, and `..` segments do not leave it. URLs with a scheme,
+/// including `file:`, or a host are skipped. The `integrity` attribute is
+/// verified as browsers do, against the hashes of its strongest algorithm.
+pub fn check_integrity<'d>(package: &'d Package, root: &Path) -> Vec<IntegrityCheck<'d>> {
+    let document = package.as_document();
+    let mut checks = vec![];
+    for element in util::descendant_elements(ParentOfChild::Root(document.root())) {
+        let attribute = match util::html_local_name(&element) {
+            Some("script") => "src",
+            Some("link") => {
+                let rels = element
+                    .attribute_value("rel")
+                    .unwrap_or("")
+                    .to_ascii_lowercase();
+                let subresource = rels
+                    .split_ascii_whitespace()
+                    .any(|rel| matches!(rel, "stylesheet" | "preload" | "modulepreload"));
+                if !subresource {
+                    continue;
+                }
+                "href"
+            }
+            _ => continue,
+        };
+        let url = match element.attribute_value(attribute) {
+            Some(url) => url.trim(),
+            None => continue,
+        };
+        let path = match local_path(url, root) {
+            Some(path) => path,
+            None => continue,
+        };
+
+        let expected = element.attribute_value("integrity").map(str::to_string);
+        let (status, computed) = match std::fs::read(&path) {
+            Ok(bytes) => verify(expected.as_deref(), &bytes),
+            Err(_) => (IntegrityStatus::Unreadable, None),
+        };
+        checks.push(IntegrityCheck {
+            element,
+            url: url.to_string(),
+            path,
+            expected,
+            computed,
+            status,
+        });
+    }
+    checks
+}
+
+/// Returns the file below `root` that the local URL `url` points to.
+fn local_path(url: &str, root: &Path) -> Option<PathBuf> {
+    // Only relative URLs fail to parse on their own.
+    if Url::parse(url.trim()).is_ok() {
+        return None;
+    }
+    let root_url = Url::parse("file:///").ok()?;
+    let resolved = root_url.join(url).ok()?;
+    if resolved.scheme() != "file" || resolved.host().is_some() || url.starts_with("//") {
+        return None;
+    }
+    let mut path = root.to_path_buf();
+    for segment in resolved.path_segments()? {
+        let segment = percent_decode(segment)?;
+        if segment.is_empty() {
+            continue;
+        }
+        path.push(segment);
+    }
+    Some(path)
+}
+
+/// Decodes the percent-encoded URL path segment `segment`, rejecting
+/// segments that decode to a path separator.
+fn percent_decode(segment: &str) -> Option<String> {
+    let mut bytes = vec![];
+    let mut rest = segment.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' && tail.len() >= 2 {
+            let hex = std::str::from_utf8(&tail[..2]).ok()?;
+            if let Ok(decoded) = u8::from_str_radix(hex, 16) {
+                bytes.push(decoded);
+                rest = &tail[2..];
+                continue;
+            }
+        }
+        bytes.push(byte);
+        rest = tail;
+    }
+    let segment = String::from_utf8(bytes).ok()?;
+    match segment.contains(['/', '\\']) || segment == ".." {
+        true => None,
+        false => Some(segment),
+    }
+}
+
+fn verify(expected: Option<&str>, bytes: &[u8]) -> (IntegrityStatus, Option<String>) {
+    let expected = match expected {
+        Some(expected) => expected,
+        None => {
+            let computed = HashAlgorithm::Sha384.integrity(bytes);
+            return (IntegrityStatus::Missing, Some(computed));
+        }
+    };
+
+    // Options after `?` are ignored, and so are unknown algorithms.
+    let hashes: Vec<(HashAlgorithm, &str)> = expected
+        .split_ascii_whitespace()
+        .filter_map(|token| {
+            let token = token.split('?').next().unwrap_or(token);
+            let (name, _) = token.split_once('-')?;
+            Some((HashAlgorithm::from_name(name)?, token))
+        })
+        .collect();
+    let strongest = match hashes.iter().map(|(algorithm, _)| *algorithm).max() {
+        Some(strongest) => strongest,
+        None => {
+            let computed = HashAlgorithm::Sha384.integrity(bytes);
+            return (IntegrityStatus::Unsupported, Some(computed));
+        }
+    };
+
+    let computed = strongest.integrity(bytes);
+    let valid = hashes.iter().any(|&(algorithm, token)| {
+        algorithm == strongest
+            && token.split_once('-').map(|(_, hash)| hash)
+                == computed.split_once('-').map(|(_, hash)| hash)
+    });
+    let status = match valid {
+        true => IntegrityStatus::Valid,
+        false => IntegrityStatus::Mismatch,
+    };
+    (status, Some(computed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyze_csp() {
+        let package = crate::parse_html(concat!(
+            r#"<head><style>p{color:red}</style><link rel="stylesheet" href="https://cdn.example.com/a.css">"#,
+            r#"<link rel="preload" as="font" href="/f.woff2"><link rel="icon" href="data:,">"#,
+            r#"<style>@font-face { font-family: x; src: url("https://fonts.example.net/x.woff2") }</style></head>"#,
+            r#"<body><p style="color: red">x</p><script>alert(1)</script>"#,
+            r#"<script src="/app.js"></script><script src="https://cdn.example.com/b.js"></script>"#,
+            r#"<img srcset="https://img.example.org/1.png 1x, /2.png 2x"><iframe src="https://www.youtube.com/embed/x"></iframe>"#,
+            r#"<img src="javascript:x()"></body>"#,
+        ));
+        let document_url = Url::parse("https://example.com/page").unwrap();
+        let report = analyze_csp(&package, Some(&document_url));
+
+        let kinds: Vec<_> = report
+            .inline
+            .iter()
+            .map(|hash| (hash.kind, hash.sha256.as_str()))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (
+                    InlineKind::Style,
+                    "sha256-p0bF+un5yUb9MBO6xRb8kPHlY2BdpHVtLiFkDrZPF64="
+                ),
+                (
+                    InlineKind::Style,
+                    "sha256-QXt1MQalx2bi3/QXToWZ+Y4Enh4ChEQKGkR6YSsyxaA="
+                ),
+                (
+                    InlineKind::StyleAttribute,
+                    "sha256-NerDAUWfwD31YdZHveMrq0GLjsNFMwxLpZl0dPUeCcw="
+                ),
+                (
+                    InlineKind::Script,
+                    "sha256-bhHHL3z2vDgxUt0W3dWQOrprscmda2Y5pLsLg4GF+pI="
+                ),
+            ]
+        );
+        assert_eq!(
+            report.inline[3].sha384,
+            "sha384-HT2E9NfWiuQ/w1PRai+hTyqW16NIoCGA/m8VQDUopfAtcz6YQjtsMmQd5uRbVDpW"
+        );
+        assert_eq!(
+            report.script_origins,
+            vec!["'self'", "https://cdn.example.com"]
+        );
+        assert_eq!(report.style_origins, vec!["https://cdn.example.com"]);
+        assert_eq!(
+            report.img_origins,
+            vec!["'self'", "data:", "https://img.example.org"]
+        );
+        assert_eq!(
+            report.font_origins,
+            vec!["'self'", "https://fonts.example.net"]
+        );
+        assert_eq!(report.frame_origins, vec!["https://www.youtube.com"]);
+
+        let header = report.header();
+        assert!(header.contains(concat!(
+            "; style-src 'self' https://cdn.example.com 'unsafe-hashes' ",
+            "'sha256-p0bF+un5yUb9MBO6xRb8kPHlY2BdpHVtLiFkDrZPF64=' ",
+            "'sha256-QXt1MQalx2bi3/QXToWZ+Y4Enh4ChEQKGkR6YSsyxaA=' ",
+            "'sha256-NerDAUWfwD31YdZHveMrq0GLjsNFMwxLpZl0dPUeCcw='; ",
+        )));
+        assert!(header.contains("; img-src 'self' data: https://img.example.org; "));
+    }
+
+    #[test]
+    fn test_check_integrity() {
+        let root = std::env::temp_dir().join(format!("sxd_html_sri_{}", std::process::id()));
+        std::fs::create_dir_all(root.join("js")).unwrap();
+        std::fs::write(root.join("js/app.js"), "console.log(\"app\");\n").unwrap();
+        std::fs::write(root.join("a b.css"), "p{color:red}").unwrap();
+
+        let package = crate::parse_html(concat!(
+            r#"<link rel="stylesheet" href="a%20b.css" integrity="sha256-p0bF+un5yUb9MBO6xRb8kPHlY2BdpHVtLiFkDrZPF64=">"#,
+            r#"<script src="/js/app.js?v=1"></script>"#,
+            r#"<script src="js/app.js" integrity="sha256-b0wRP1l0lEIqepjFcKQDB8dAOfMM9dfLe8+hte1QwXg= sha384-wrong"></script>"#,
+            r#"<script src="js/missing.js" integrity="md5-x"></script><script src="../secret.js"></script>"#,
+            r#"<script src="https://cdn.example.com/x.js"></script><link rel="icon" href="i.png">"#,
+            r#"<script src="file:///js/app.js"></script><script src=" FILE:js/app.js"></script>"#,
+        ));
+        let checks = check_integrity(&package, &root);
+        std::fs::remove_dir_all(&root).unwrap();
+
+        let summary: Vec<_> = checks
+            .iter()
+            .map(|check| (check.url.as_str(), check.status, check.computed.as_deref()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    "a%20b.css",
+                    IntegrityStatus::Valid,
+                    Some("sha256-p0bF+un5yUb9MBO6xRb8kPHlY2BdpHVtLiFkDrZPF64=")
+                ),
+                (
+                    "/js/app.js?v=1",
+                    IntegrityStatus::Missing,
+                    Some("sha384-T2hO7zKkY8LxiAArzsPXNjINCGbwMTu1In0Aopy4beZlBxSrGoWPBhGCG2rh7418")
+                ),
+                (
+                    "js/app.js",
+                    IntegrityStatus::Mismatch,
+                    Some("sha384-T2hO7zKkY8LxiAArzsPXNjINCGbwMTu1In0Aopy4beZlBxSrGoWPBhGCG2rh7418")
+                ),
+                ("js/missing.js", IntegrityStatus::Unreadable, None),
+                ("../secret.js", IntegrityStatus::Unreadable, None),
+            ]
+        );
+        assert_eq!(checks[1].path, root.join("js").join("app.js"));
+    }
+}
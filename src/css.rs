@@ -0,0 +1,880 @@
+use std::fmt;
+
+const UPPERCASE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const LOWERCASE: &str = "abcdefghijklmnopqrstuvwxyz";
+
+/// Form elements that can be disabled.
+const DISABLABLE: &str = "self::button or self::input or self::select or self::textarea \
+                          or self::optgroup or self::option or self::fieldset";
+
+/// An error in a CSS selector.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectorError {
+    position: usize,
+    message: String,
+}
+
+impl SelectorError {
+    fn new(position: usize, message: impl Into<String>) -> Self {
+        Self {
+            position,
+            message: message.into(),
+        }
+    }
+
+    /// Returns the position of the error in the selector, in characters.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns a description of the error.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for SelectorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for SelectorError {}
+
+/// Translates a CSS selector to an equivalent XPath 1.0 expression.
+///
+/// The expression selects the elements matching `selector` among the
+/// descendants of the context node. Like `querySelectorAll`, the whole
+/// selector is matched against the document, so in `div p` the `div` may
+/// be an ancestor of the context node.
+///
+/// Selectors Level 3 is supported, except for the dynamic pseudo-classes
+/// such as `:hover` and pseudo-elements, along with `:is()`, `:where()`,
+/// `:has()` and complex selectors in `:not()` from Level 4. Type selectors
+/// match HTML elements; SVG and MathML elements are selected with a
+/// namespace prefix such as `svg|circle`, which needs the prefixes
+/// registered by [`register_namespaces`](crate::register_namespaces).
+///
+/// ```rust
+/// assert_eq!(
+///     sxd_html::css_to_xpath("ul > li:first-child").unwrap(),
+///     "descendant::li[not(preceding-sibling::*)][parent::ul]"
+/// );
+/// ```
+///
+/// The result can be compiled with [`sxd_xpath::Factory`] like any other
+/// expression, or evaluated with [`select_css`](crate::select_css).
+pub fn css_to_xpath(selector: &str) -> Result<String, SelectorError> {
+    let mut parser = Parser::new(selector);
+    let selectors = parser.selector_list(false)?;
+    if let Some(c) = parser.peek() {
+        return Err(parser.error(format!("unexpected `{}`", c)));
+    }
+
+    let paths: Vec<String> = selectors
+        .iter()
+        .map(|(_, complex)| complex.matching(Axis::Descendant))
+        .collect();
+    Ok(paths.join(" | "))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Combinator {
+    Descendant,
+    Child,
+    NextSibling,
+    SubsequentSibling,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Axis {
+    Descendant,
+    Itself,
+    Ancestor,
+    Parent,
+    PrecedingSibling,
+    PreviousSibling,
+    Child,
+    FollowingSibling,
+    NextSibling,
+}
+
+/// A compound selector: a name test and the conditions on the element.
+#[derive(Debug)]
+struct Compound {
+    name: String,
+    conditions: Vec<String>,
+}
+
+impl Compound {
+    fn step(&self, axis: Axis) -> String {
+        let mut step = match axis {
+            Axis::PreviousSibling | Axis::NextSibling => {
+                let axis = if axis == Axis::PreviousSibling {
+                    "preceding-sibling"
+                } else {
+                    "following-sibling"
+                };
+                let mut step = format!("{}::*[1]", axis);
+                if self.name != "*" {
+                    step.push_str(&format!("[self::{}]", self.name));
+                }
+                step
+            }
+            axis => {
+                let axis = match axis {
+                    Axis::Descendant => "descendant",
+                    Axis::Itself => "self",
+                    Axis::Ancestor => "ancestor",
+                    Axis::Parent => "parent",
+                    Axis::PrecedingSibling => "preceding-sibling",
+                    Axis::Child => "child",
+                    _ => "following-sibling",
+                };
+                format!("{}::{}", axis, self.name)
+            }
+        };
+        for condition in &self.conditions {
+            step.push_str(&format!("[{}]", condition));
+        }
+        step
+    }
+}
+
+/// A complex selector: compound selectors separated by combinators.
+#[derive(Debug)]
+struct Complex {
+    compounds: Vec<Compound>,
+    combinators: Vec<Combinator>,
+}
+
+impl Complex {
+    /// Returns a step along `axis` selecting the elements matching the
+    /// selector, checking the compound selectors from right to left.
+    fn matching(&self, axis: Axis) -> String {
+        self.matching_at(self.compounds.len() - 1, axis)
+    }
+
+    fn matching_at(&self, index: usize, axis: Axis) -> String {
+        let mut step = self.compounds[index].step(axis);
+        if index > 0 {
+            let axis = match self.combinators[index - 1] {
+                Combinator::Descendant => Axis::Ancestor,
+                Combinator::Child => Axis::Parent,
+                Combinator::NextSibling => Axis::PreviousSibling,
+                Combinator::SubsequentSibling => Axis::PrecedingSibling,
+            };
+            step.push_str(&format!("[{}]", self.matching_at(index - 1, axis)));
+        }
+        step
+    }
+
+    /// Returns a path from an anchor element to the elements matching the
+    /// relative selector `combinator self`, as in `:has()`.
+    fn relative(&self, combinator: Combinator) -> String {
+        let steps: Vec<String> = self
+            .compounds
+            .iter()
+            .enumerate()
+            .map(|(index, compound)| {
+                let combinator = match index {
+                    0 => combinator,
+                    _ => self.combinators[index - 1],
+                };
+                compound.step(match combinator {
+                    Combinator::Descendant => Axis::Descendant,
+                    Combinator::Child => Axis::Child,
+                    Combinator::NextSibling => Axis::NextSibling,
+                    Combinator::SubsequentSibling => Axis::FollowingSibling,
+                })
+            })
+            .collect();
+        steps.join("/")
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn new(selector: &str) -> Self {
+        Self {
+            chars: selector.chars().collect(),
+            position: 0,
+        }
+    }
+
+    fn error(&self, message: impl Into<String>) -> SelectorError {
+        SelectorError::new(self.position, message)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn peek_next(&self) -> Option<char> {
+        self.chars.get(self.position + 1).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), SelectorError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected `{}`", c)))
+        }
+    }
+
+    fn skip_whitespace(&mut self) -> bool {
+        let start = self.position;
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+        self.position > start
+    }
+
+    /// Parses a comma separated list of complex selectors. Relative
+    /// selectors, as in `:has(> p)`, may start with a combinator.
+    fn selector_list(
+        &mut self,
+        relative: bool,
+    ) -> Result<Vec<(Combinator, Complex)>, SelectorError> {
+        let mut selectors = vec![];
+        loop {
+            self.skip_whitespace();
+            let combinator = match relative {
+                true => self.combinator().unwrap_or(Combinator::Descendant),
+                false => Combinator::Descendant,
+            };
+            self.skip_whitespace();
+            selectors.push((combinator, self.complex()?));
+            self.skip_whitespace();
+            if !self.eat(',') {
+                return Ok(selectors);
+            }
+        }
+    }
+
+    fn combinator(&mut self) -> Option<Combinator> {
+        let combinator = match self.peek()? {
+            '>' => Combinator::Child,
+            '+' => Combinator::NextSibling,
+            '~' => Combinator::SubsequentSibling,
+            _ => return None,
+        };
+        self.position += 1;
+        Some(combinator)
+    }
+
+    fn complex(&mut self) -> Result<Complex, SelectorError> {
+        let mut compounds = vec![self.compound()?];
+        let mut combinators = vec![];
+        loop {
+            let whitespace = self.skip_whitespace();
+            let combinator = match self.combinator() {
+                Some(combinator) => combinator,
+                None => match self.peek() {
+                    None | Some(',') | Some(')') => break,
+                    Some(_) if whitespace => Combinator::Descendant,
+                    Some(c) => return Err(self.error(format!("unexpected `{}`", c))),
+                },
+            };
+            self.skip_whitespace();
+            compounds.push(self.compound()?);
+            combinators.push(combinator);
+        }
+        Ok(Complex {
+            compounds,
+            combinators,
+        })
+    }
+
+    fn compound(&mut self) -> Result<Compound, SelectorError> {
+        let start = self.position;
+        let mut compound = Compound {
+            name: "*".to_string(),
+            conditions: vec![],
+        };
+        self.type_selector(&mut compound)?;
+
+        loop {
+            match self.peek() {
+                Some('#') => {
+                    self.position += 1;
+                    let id = self.identifier()?;
+                    compound.conditions.push(format!("@id = {}", literal(&id)));
+                }
+                Some('.') => {
+                    self.position += 1;
+                    let class = self.identifier()?;
+                    compound.conditions.push(contains_word("@class", &class));
+                }
+                Some('[') => {
+                    self.position += 1;
+                    let condition = self.attribute()?;
+                    compound.conditions.push(condition);
+                }
+                Some(':') => {
+                    self.position += 1;
+                    let condition = self.pseudo_class(&compound)?;
+                    compound.conditions.push(condition);
+                }
+                _ => break,
+            }
+        }
+
+        if self.position == start {
+            return Err(self.error("expected a selector"));
+        }
+        Ok(compound)
+    }
+
+    fn type_selector(&mut self, compound: &mut Compound) -> Result<(), SelectorError> {
+        let first = match self.peek() {
+            Some('*') => {
+                self.position += 1;
+                Some("*".to_string())
+            }
+            Some('|') => None,
+            Some(c) if is_identifier_start(c) => Some(self.identifier()?),
+            _ => return Ok(()),
+        };
+
+        if self.peek() != Some('|') {
+            if let Some(name) = first {
+                compound.name = name.to_ascii_lowercase();
+            }
+            return Ok(());
+        }
+        self.position += 1;
+        let name = match self.eat('*') {
+            true => "*".to_string(),
+            false => self.identifier()?,
+        };
+
+        match (first.as_deref(), name.as_str()) {
+            (Some("*"), "*") => {}
+            (Some("*"), name) => compound
+                .conditions
+                .push(format!("local-name() = {}", literal(name))),
+            (None, name) => compound.name = name.to_ascii_lowercase(),
+            (Some(prefix), name) => compound.name = format!("{}:{}", prefix, name),
+        }
+        Ok(())
+    }
+
+    fn identifier(&mut self) -> Result<String, SelectorError> {
+        let mut identifier = String::new();
+        while let Some(c) = self.peek() {
+            if c == '\\' {
+                self.position += 1;
+                identifier.push(self.escape());
+            } else if c.is_ascii_alphanumeric() || c == '-' || c == '_' || !c.is_ascii() {
+                self.position += 1;
+                identifier.push(c);
+            } else {
+                break;
+            }
+        }
+
+        if identifier.is_empty() {
+            return Err(self.error("expected an identifier"));
+        }
+        Ok(identifier)
+    }
+
+    /// Parses the escape following a `\`.
+    fn escape(&mut self) -> char {
+        let mut hex = String::new();
+        while hex.len() < 6 && self.peek().is_some_and(|c| c.is_ascii_hexdigit()) {
+            hex.extend(self.peek());
+            self.position += 1;
+        }
+
+        if hex.is_empty() {
+            let c = self.peek().unwrap_or('\u{fffd}');
+            self.position += 1;
+            return c;
+        }
+        if self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+        u32::from_str_radix(&hex, 16)
+            .ok()
+            .filter(|&code| code != 0)
+            .and_then(char::from_u32)
+            .unwrap_or('\u{fffd}')
+    }
+
+    fn string(&mut self) -> Result<String, SelectorError> {
+        let start = self.position;
+        let quote = self.peek();
+        self.position += 1;
+
+        let mut string = String::new();
+        loop {
+            match self.peek() {
+                None => return Err(SelectorError::new(start, "unterminated string")),
+                Some(c) if Some(c) == quote => {
+                    self.position += 1;
+                    return Ok(string);
+                }
+                Some('\\') => {
+                    self.position += 1;
+                    if self.eat('\n') {
+                        continue;
+                    }
+                    string.push(self.escape());
+                }
+                Some(c) => {
+                    self.position += 1;
+                    string.push(c);
+                }
+            }
+        }
+    }
+
+    /// Parses an attribute selector following the `[`.
+    fn attribute(&mut self) -> Result<String, SelectorError> {
+        self.skip_whitespace();
+        let mut name = match self.eat('*') {
+            true => "*".to_string(),
+            false => self.identifier()?.to_ascii_lowercase(),
+        };
+        if self.peek() == Some('|') && self.peek_next() != Some('=') {
+            self.position += 1;
+            let local_name = self.identifier()?;
+            name = match name.as_str() {
+                "*" => format!("*[local-name() = {}]", literal(&local_name)),
+                prefix => format!("{}:{}", prefix, local_name),
+            };
+        } else if name == "*" {
+            return Err(self.error("expected `|`"));
+        }
+        let attribute = format!("@{}", name);
+        self.skip_whitespace();
+
+        if self.eat(']') {
+            return Ok(attribute);
+        }
+
+        let operator = match self.peek() {
+            Some('=') => '=',
+            Some(c @ ('~' | '|' | '^' | '$' | '*')) if self.peek_next() == Some('=') => {
+                self.position += 1;
+                c
+            }
+            _ => return Err(self.error("expected an attribute operator or `]`")),
+        };
+        self.position += 1;
+        self.skip_whitespace();
+
+        let mut value = match self.peek() {
+            Some('"' | '\'') => self.string()?,
+            _ => self.identifier()?,
+        };
+        self.skip_whitespace();
+
+        let mut attribute = attribute;
+        if self.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+            match self.identifier()?.to_ascii_lowercase().as_str() {
+                "i" => {
+                    attribute = lower_case(&attribute);
+                    value = value.to_ascii_lowercase();
+                }
+                "s" => {}
+                flag => return Err(self.error(format!("unknown attribute flag `{}`", flag))),
+            }
+            self.skip_whitespace();
+        }
+        self.expect(']')?;
+
+        let value_literal = literal(&value);
+        let condition = match operator {
+            '=' => format!("{} = {}", attribute, value_literal),
+            '~' => contains_word(&attribute, &value),
+            '|' => format!(
+                "{0} = {1} or starts-with({0}, {2})",
+                attribute,
+                value_literal,
+                literal(&format!("{}-", value))
+            ),
+            _ if value.is_empty() => "false()".to_string(),
+            '^' => format!("starts-with({}, {})", attribute, value_literal),
+            '$' => format!(
+                "substring({0}, string-length({0}) - {1}) = {2}",
+                attribute,
+                value.chars().count() - 1,
+                value_literal
+            ),
+            _ => format!("contains({}, {})", attribute, value_literal),
+        };
+        Ok(condition)
+    }
+
+    /// Parses a pseudo-class following the `:`.
+    fn pseudo_class(&mut self, compound: &Compound) -> Result<String, SelectorError> {
+        let start = self.position;
+        if self.peek() == Some(':') {
+            return Err(self.error("pseudo-elements are not supported"));
+        }
+        let name = self.identifier()?.to_ascii_lowercase();
+
+        if !self.eat('(') {
+            let of_type = |axes: &[&str]| -> Result<String, SelectorError> {
+                if compound.name.starts_with('*') || compound.name.ends_with('*') {
+                    return Err(SelectorError::new(
+                        start,
+                        format!("`:{}` requires a type selector", name),
+                    ));
+                }
+                let steps: Vec<String> = axes
+                    .iter()
+                    .map(|axis| format!("{}::{}", axis, compound.name))
+                    .collect();
+                Ok(format!("not({})", steps.join(" | ")))
+            };
+
+            return match name.as_str() {
+                "first-child" => Ok("not(preceding-sibling::*)".to_string()),
+                "last-child" => Ok("not(following-sibling::*)".to_string()),
+                "only-child" => Ok("not(preceding-sibling::* | following-sibling::*)".to_string()),
+                "first-of-type" => of_type(&["preceding-sibling"]),
+                "last-of-type" => of_type(&["following-sibling"]),
+                "only-of-type" => of_type(&["preceding-sibling", "following-sibling"]),
+                "root" => Ok("not(parent::*)".to_string()),
+                "empty" => Ok("not(* | text())".to_string()),
+                "link" | "any-link" => Ok("(self::a or self::area) and @href".to_string()),
+                "checked" => {
+                    Ok("(self::input and @checked) or (self::option and @selected)".to_string())
+                }
+                "disabled" => Ok(format!("@disabled and ({})", DISABLABLE)),
+                "enabled" => Ok(format!("not(@disabled) and ({})", DISABLABLE)),
+                "required" => Ok("@required".to_string()),
+                "optional" => Ok(
+                    "not(@required) and (self::input or self::select or self::textarea)"
+                        .to_string(),
+                ),
+                _ => Err(SelectorError::new(
+                    start,
+                    format!("unsupported pseudo-class `:{}`", name),
+                )),
+            };
+        }
+
+        let condition = match name.as_str() {
+            "not" | "is" | "where" | "matches" => {
+                let selectors = self.selector_list(false)?;
+                let conditions: Vec<String> = selectors
+                    .iter()
+                    .map(|(_, complex)| complex.matching(Axis::Itself))
+                    .collect();
+                match name.as_str() {
+                    "not" => format!("not({})", conditions.join(" or ")),
+                    _ => conditions.join(" or "),
+                }
+            }
+            "has" => {
+                let selectors = self.selector_list(true)?;
+                let paths: Vec<String> = selectors
+                    .iter()
+                    .map(|(combinator, complex)| complex.relative(*combinator))
+                    .collect();
+                paths.join(" or ")
+            }
+            "nth-child" | "nth-last-child" | "nth-of-type" | "nth-last-of-type" => {
+                let argument_start = self.position;
+                let mut argument = String::new();
+                while let Some(c) = self.peek().filter(|&c| c != ')') {
+                    argument.push(c);
+                    self.position += 1;
+                }
+                let invalid = || {
+                    SelectorError::new(argument_start, format!("invalid argument to `:{}`", name))
+                };
+                let (a, b) = parse_nth(&argument).ok_or_else(invalid)?;
+
+                let axis = match name.as_str() {
+                    "nth-child" | "nth-of-type" => "preceding-sibling",
+                    _ => "following-sibling",
+                };
+                let node_test = match name.ends_with("of-type") {
+                    false => "*",
+                    true if compound.name.starts_with('*') || compound.name.ends_with('*') => {
+                        return Err(SelectorError::new(
+                            start,
+                            format!("`:{}` requires a type selector", name),
+                        ))
+                    }
+                    true => compound.name.as_str(),
+                };
+                nth(&format!("count({}::{})", axis, node_test), a, b).ok_or_else(invalid)?
+            }
+            "lang" => {
+                self.skip_whitespace();
+                let language = match self.peek() {
+                    Some('"' | '\'') => self.string()?,
+                    _ => self.identifier()?,
+                }
+                .to_ascii_lowercase();
+                self.skip_whitespace();
+                let lang = lower_case("@lang");
+                format!(
+                    "ancestor-or-self::*[@lang][1][{0} = {1} or starts-with({0}, {2})]",
+                    lang,
+                    literal(&language),
+                    literal(&format!("{}-", language))
+                )
+            }
+            _ => {
+                return Err(SelectorError::new(
+                    start,
+                    format!("unsupported pseudo-class `:{}()`", name),
+                ))
+            }
+        };
+
+        self.skip_whitespace();
+        self.expect(')')?;
+        Ok(condition)
+    }
+}
+
+fn is_identifier_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '-' || c == '_' || c == '\\' || !c.is_ascii()
+}
+
+/// Returns an XPath string literal for `value`.
+pub(crate) fn literal(value: &str) -> String {
+    if !value.contains('\'') {
+        format!("'{}'", value)
+    } else if !value.contains('"') {
+        format!("\"{}\"", value)
+    } else {
+        let parts: Vec<String> = value
+            .split('\'')
+            .map(|part| format!("'{}'", part))
+            .collect();
+        format!("concat({})", parts.join(", \"'\", "))
+    }
+}
+
+/// Returns `expression` converted to lower case, for ASCII letters.
+fn lower_case(expression: &str) -> String {
+    format!(
+        "translate({}, '{}', '{}')",
+        expression, UPPERCASE, LOWERCASE
+    )
+}
+
+/// Returns a condition that is true if `word` is one of the whitespace
+/// separated words of `expression`.
+fn contains_word(expression: &str, word: &str) -> String {
+    if word.is_empty() || word.contains(char::is_whitespace) {
+        return "false()".to_string();
+    }
+    format!(
+        "contains(concat(' ', normalize-space({}), ' '), {})",
+        expression,
+        literal(&format!(" {} ", word))
+    )
+}
+
+/// Parses the `an+b` argument of the `:nth-*` pseudo-classes.
+fn parse_nth(argument: &str) -> Option<(i64, i64)> {
+    let argument: String = argument
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_ascii_lowercase();
+
+    match argument.as_str() {
+        "odd" => return Some((2, 1)),
+        "even" => return Some((2, 0)),
+        _ => {}
+    }
+
+    match argument.split_once('n') {
+        Some((a, b)) => {
+            let a = match a {
+                "" | "+" => 1,
+                "-" => -1,
+                a => a.parse().ok()?,
+            };
+            let b = match b {
+                "" => 0,
+                b if b.starts_with(['+', '-']) => b.parse().ok()?,
+                _ => return None,
+            };
+            Some((a, b))
+        }
+        None => Some((0, argument.parse().ok()?)),
+    }
+}
+
+/// Returns a condition that is true if `count + 1` is `a * n + b` for some
+/// `n >= 0`, where `count` counts the preceding or following siblings.
+///
+/// Returns `None` if `a` or `b` is too large to be negated or decremented.
+fn nth(count: &str, a: i64, b: i64) -> Option<String> {
+    // Compare `count` with `b - 1` instead of the 1-based index.
+    let k = b.checked_sub(1)?;
+    k.checked_neg()?;
+    a.checked_neg()?;
+    let mut conditions = vec![];
+    match a {
+        0 if k < 0 => return Some("false()".to_string()),
+        0 => conditions.push(format!("{} = {}", count, k)),
+        a if a > 0 => {
+            if k > 0 {
+                conditions.push(format!("{} >= {}", count, k));
+            }
+            if a != 1 {
+                let difference = match k {
+                    0 => count.to_string(),
+                    k if k > 0 => format!("({} - {})", count, k),
+                    k => format!("({} + {})", count, -k),
+                };
+                conditions.push(format!("{} mod {} = 0", difference, a));
+            }
+        }
+        _ if k < 0 => return Some("false()".to_string()),
+        a => {
+            conditions.push(format!("{} <= {}", count, k));
+            if a != -1 {
+                conditions.push(format!("({} - {}) mod {} = 0", k, count, -a));
+            }
+        }
+    }
+
+    match conditions.is_empty() {
+        true => Some("true()".to_string()),
+        false => Some(conditions.join(" and ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HTML: &str = concat!(
+        r#"<ul id="list">"#,
+        r#"<li class="item first">one</li>"#,
+        r#"<li class="item" data-kind="News">two</li>"#,
+        r#"<li class="item"><a href="/three" lang="en-GB">three</a></li>"#,
+        r#"<li>four</li>"#,
+        r#"</ul>"#,
+        r#"<p>text</p><p class="note">note</p>"#,
+        r#"<svg><circle r="1"></circle></svg>"#,
+    );
+
+    fn texts(selector: &str) -> Vec<String> {
+        let package = crate::parse_html(HTML);
+        let root = package.as_document().root();
+        let query = crate::Query::with_context(crate::html_context());
+        query
+            .select_css(root, selector)
+            .unwrap()
+            .document_order()
+            .iter()
+            .map(|node| node.string_value())
+            .collect()
+    }
+
+    #[test]
+    fn test_translation() {
+        assert_eq!(css_to_xpath("p").unwrap(), "descendant::p");
+        assert_eq!(
+            css_to_xpath("div > p, #a").unwrap(),
+            "descendant::p[parent::div] | descendant::*[@id = 'a']"
+        );
+        assert_eq!(
+            css_to_xpath("h1 + p").unwrap(),
+            "descendant::p[preceding-sibling::*[1][self::h1]]"
+        );
+        assert_eq!(
+            css_to_xpath("li:nth-child(2n+1)").unwrap(),
+            "descendant::li[count(preceding-sibling::*) mod 2 = 0]"
+        );
+        assert_eq!(
+            css_to_xpath("a[title=\"it's\"]").unwrap(),
+            "descendant::a[@title = \"it's\"]"
+        );
+        assert_eq!(
+            css_to_xpath("div:has(> p)").unwrap(),
+            "descendant::div[child::p]"
+        );
+    }
+
+    #[test]
+    fn test_select_css() {
+        assert_eq!(texts("li.item"), vec!["one", "two", "three"]);
+        assert_eq!(texts("ul > li:not(.item)"), vec!["four"]);
+        assert_eq!(texts("li:nth-child(even)"), vec!["two", "four"]);
+        assert_eq!(texts("li:nth-last-child(-n+2)"), vec!["three", "four"]);
+        assert_eq!(texts("li.first ~ li:last-child"), vec!["four"]);
+        assert_eq!(texts("li + li.item"), vec!["two", "three"]);
+        assert_eq!(texts("#list p, ul ~ p:first-of-type"), vec!["text"]);
+        assert_eq!(texts("p:last-of-type"), vec!["note"]);
+        assert_eq!(texts("li:has(a[href^='/'])"), vec!["three"]);
+        assert_eq!(texts(":is(li, p).first, p.note"), vec!["one", "note"]);
+        assert_eq!(texts("[data-kind='news' i]"), vec!["two"]);
+        assert_eq!(texts("a:lang(en)"), vec!["three"]);
+        assert_eq!(texts("svg|circle[r]").len(), 1);
+    }
+
+    #[test]
+    fn test_escapes_and_attribute_operators() {
+        assert_eq!(
+            css_to_xpath(r"#\31 23").unwrap(),
+            "descendant::*[@id = '123']"
+        );
+        assert_eq!(texts("[class~=first]"), vec!["one"]);
+        assert_eq!(texts("[href$='three']"), vec!["three"]);
+        assert_eq!(texts("[href*=hre]"), vec!["three"]);
+        assert_eq!(texts("[lang|=en]"), vec!["three"]);
+        assert_eq!(texts("[href^='']"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(css_to_xpath("").unwrap_err().position(), 0);
+        assert_eq!(css_to_xpath("p >").unwrap_err().position(), 3);
+        assert!(css_to_xpath("a[href").is_err());
+        assert!(css_to_xpath("p::before").is_err());
+        assert!(css_to_xpath("a:hover").is_err());
+        assert!(css_to_xpath("*:first-of-type").is_err());
+        assert!(css_to_xpath("li:nth-child(x)").is_err());
+        assert!(css_to_xpath("p)").is_err());
+    }
+
+    #[test]
+    fn test_nth_overflow() {
+        let package = crate::parse_html(HTML);
+        let root = package.as_document().root();
+        for selector in [
+            "li:nth-child(-9223372036854775808)",
+            "li:nth-child(9223372036854775807n-9223372036854775808)",
+            "li:nth-last-of-type(-9223372036854775808n+1)",
+        ] {
+            assert!(matches!(
+                crate::select_css(root, selector),
+                Err(crate::QueryError::Selector { .. })
+            ));
+        }
+        assert_eq!(
+            texts("li:nth-child(9223372036854775807)"),
+            Vec::<String>::new()
+        );
+    }
+}
@@ -0,0 +1,326 @@
+use std::{collections::HashMap, convert::Infallible, fmt, str::FromStr};
+
+use sxd_document::{
+    dom::{Document, Element, ParentOfChild, Root},
+    Package,
+};
+use url::Url;
+
+use crate::{
+    form::FormOwners,
+    util::{self, ElementKey},
+    Error, Form,
+};
+
+/// The rendering mode html5ever selected for a document based on its
+/// DOCTYPE.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum QuirksMode {
+    /// Standards mode.
+    #[default]
+    NoQuirks,
+    /// Almost standards mode.
+    LimitedQuirks,
+    /// Full quirks mode, used when the DOCTYPE is missing or obsolete.
+    Quirks,
+}
+
+impl QuirksMode {
+    pub(crate) fn from_html5ever(mode: html5ever::tree_builder::QuirksMode) -> Self {
+        match mode {
+            html5ever::tree_builder::QuirksMode::NoQuirks => Self::NoQuirks,
+            html5ever::tree_builder::QuirksMode::LimitedQuirks => Self::LimitedQuirks,
+            html5ever::tree_builder::QuirksMode::Quirks => Self::Quirks,
+        }
+    }
+}
+
+/// A DOCTYPE declaration.
+///
+/// `sxd_document` has no DOCTYPE node type, so the declaration is kept
+/// next to the tree by [`HtmlDocument`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Doctype {
+    name: String,
+    public_id: String,
+    system_id: String,
+}
+
+impl Doctype {
+    pub(crate) fn new(
+        name: impl Into<String>,
+        public_id: impl Into<String>,
+        system_id: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            public_id: public_id.into(),
+            system_id: system_id.into(),
+        }
+    }
+
+    /// Returns the DOCTYPE name, `html` for HTML documents.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the public identifier, empty if none was given.
+    pub fn public_id(&self) -> &str {
+        &self.public_id
+    }
+
+    /// Returns the system identifier, empty if none was given.
+    pub fn system_id(&self) -> &str {
+        &self.system_id
+    }
+}
+
+impl fmt::Display for Doctype {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<!DOCTYPE {}", self.name)?;
+        if !self.public_id.is_empty() {
+            write!(f, " PUBLIC \"{}\"", self.public_id)?;
+            if !self.system_id.is_empty() {
+                write!(f, " \"{}\"", self.system_id)?;
+            }
+        } else if !self.system_id.is_empty() {
+            write!(f, " SYSTEM \"{}\"", self.system_id)?;
+        }
+        write!(f, ">")
+    }
+}
+
+/// A parsed HTML document together with the information html5ever reports
+/// alongside the tree.
+///
+/// `HtmlDocument` owns the [`Package`] returned by [`parse_html`] along with
+/// the parse errors, DOCTYPE, quirks mode and source text, so they can be
+/// passed around as one value. It also gives the base URL and the declared
+/// encoding of the document.
+///
+/// [`parse_html`]: crate::parse_html
+#[derive(Debug)]
+pub struct HtmlDocument {
+    package: Package,
+    errors: Vec<Error>,
+    doctype: Option<Doctype>,
+    quirks_mode: QuirksMode,
+    form_associations: HashMap<ElementKey, ElementKey>,
+    source: String,
+}
+
+impl HtmlDocument {
+    /// Parses a complete HTML document.
+    pub fn parse(contents: &str) -> Self {
+        let (package, output) = crate::parse_document(contents, true);
+
+        Self {
+            package,
+            errors: output.errors,
+            doctype: output.doctype,
+            quirks_mode: output.quirks_mode,
+            form_associations: output.form_associations,
+            source: contents.to_string(),
+        }
+    }
+
+    /// Returns the parse errors reported by html5ever.
+    ///
+    /// At most [`MAX_PARSE_ERRORS`](crate::MAX_PARSE_ERRORS) errors are
+    /// recorded.
+    pub fn errors(&self) -> &[Error] {
+        &self.errors
+    }
+
+    /// Returns the quirks mode html5ever selected for the document.
+    pub fn quirks_mode(&self) -> QuirksMode {
+        self.quirks_mode
+    }
+
+    /// Returns the DOCTYPE declaration, if the document has one.
+    pub fn doctype(&self) -> Option<&Doctype> {
+        self.doctype.as_ref()
+    }
+
+    /// Returns the form that owns the form control `control`.
+    ///
+    /// Besides the `form` attribute and the `form` ancestors of the control,
+    /// this takes into account the form the parser associated the control
+    /// with, which need not be an ancestor when markup such as
+    /// `<div><form></div><input>` is misnested.
+    ///
+    /// The associations are kept when the tree is edited, as long as the
+    /// form is still in the tree. Each call walks the document, so prefer
+    /// [`forms`](Self::forms) over calling this for every control.
+    pub fn form_owner<'d>(&'d self, control: Element<'d>) -> Option<Element<'d>> {
+        FormOwners::new(self.root(), &self.form_associations).owner(control)
+    }
+
+    /// Returns the forms of the document in document order, with their
+    /// controls determined as in [`form_owner`](Self::form_owner).
+    pub fn forms(&self) -> Vec<Form<'_>> {
+        let owners = FormOwners::new(self.root(), &self.form_associations);
+        let mut controls: HashMap<_, Vec<_>> = HashMap::new();
+        for control in owners.controls() {
+            if let Some(form) = owners.owner(control) {
+                controls.entry(form).or_default().push(control);
+            }
+        }
+        owners
+            .forms()
+            .map(|form| Form::with_controls(form, controls.remove(&form).unwrap_or_default()))
+            .collect()
+    }
+
+    /// Returns the base URL of the document loaded from `document_url`, as
+    /// [`base_url`](crate::base_url) does.
+    pub fn base_url(&self, document_url: &Url) -> Url {
+        crate::base_url(&self.package, document_url)
+    }
+
+    /// Returns the character encoding the markup declares: the `charset`
+    /// attribute of a `meta` element, or the `charset` parameter of the
+    /// `content` of a `<meta http-equiv="content-type">`, whichever comes
+    /// first.
+    ///
+    /// The document is parsed from text that is already decoded, so this is
+    /// only the declaration as written, for callers that decode the bytes of
+    /// a page themselves.
+    pub fn encoding(&self) -> Option<&str> {
+        util::descendant_elements(ParentOfChild::Root(self.root()))
+            .into_iter()
+            .filter(|element| util::html_local_name(element) == Some("meta"))
+            .find_map(|meta| {
+                let charset = match meta.attribute_value("charset") {
+                    Some(charset) => charset.trim(),
+                    None => {
+                        let http_equiv = meta.attribute_value("http-equiv")?;
+                        if !http_equiv.trim().eq_ignore_ascii_case("content-type") {
+                            return None;
+                        }
+                        content_type_charset(meta.attribute_value("content")?)?
+                    }
+                };
+                Some(charset).filter(|charset| !charset.is_empty())
+            })
+    }
+
+    /// Returns the source text the document was parsed from.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns the root node of the parsed tree.
+    pub fn root(&self) -> Root<'_> {
+        self.document().root()
+    }
+
+    /// Returns the parsed tree.
+    pub fn document(&self) -> Document<'_> {
+        self.package.as_document()
+    }
+
+    /// Returns the [`Package`] holding the parsed tree.
+    pub fn package(&self) -> &Package {
+        &self.package
+    }
+
+    /// Consumes the document, returning the [`Package`] holding the parsed
+    /// tree.
+    pub fn into_package(self) -> Package {
+        self.package
+    }
+}
+
+impl FromStr for HtmlDocument {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::parse(s))
+    }
+}
+
+/// Serializes the current state of the tree as HTML, preceded by the
+/// DOCTYPE if the document has one.
+impl fmt::Display for HtmlDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(doctype) = &self.doctype {
+            write!(f, "{}", doctype)?;
+        }
+        write!(f, "{}", crate::to_html(self.root()))
+    }
+}
+
+/// Returns the `charset` parameter of a `Content-Type` value such as
+/// `text/html; charset=utf-8`.
+fn content_type_charset(content: &str) -> Option<&str> {
+    let start = content.to_ascii_lowercase().find("charset")? + "charset".len();
+    let value = content[start..]
+        .trim_start()
+        .strip_prefix('=')?
+        .trim_start();
+    let value = value.trim_start_matches(['"', '\'']);
+    let end = value
+        .find(|c: char| matches!(c, ';' | '"' | '\'') || c.is_ascii_whitespace())
+        .unwrap_or(value.len());
+    Some(&value[..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_document_metadata() {
+        let contents =
+            "<!DOCTYPE html><html><head><title>t</title></head><body><p>x</div></body></html>";
+        let document: HtmlDocument = contents.parse().unwrap();
+
+        assert_eq!(document.doctype().map(Doctype::name), Some("html"));
+        assert_eq!(document.quirks_mode(), QuirksMode::NoQuirks);
+        assert_eq!(document.source(), contents);
+        assert!(!document.errors().is_empty());
+        assert_eq!(document.root().children().len(), 1);
+        assert_eq!(
+            document.to_string(),
+            "<!DOCTYPE html><html><head><title>t</title></head><body><p>x</p></body></html>"
+        );
+    }
+
+    #[test]
+    fn test_quirks_mode_without_doctype() {
+        let document = HtmlDocument::parse("<p>no doctype</p>");
+        assert_eq!(document.doctype(), None);
+        assert_eq!(document.quirks_mode(), QuirksMode::Quirks);
+
+        let document = HtmlDocument::parse(concat!(
+            r#"<!DOCTYPE html PUBLIC "-//W3C//DTD HTML 4.01 Transitional//EN" "#,
+            r#""http://www.w3.org/TR/html4/loose.dtd"><p>x</p>"#,
+        ));
+        assert_eq!(document.quirks_mode(), QuirksMode::LimitedQuirks);
+        assert_eq!(
+            document.doctype().map(Doctype::public_id),
+            Some("-//W3C//DTD HTML 4.01 Transitional//EN")
+        );
+    }
+
+    #[test]
+    fn test_base_url_and_encoding() {
+        let document_url = Url::parse("https://example.com/a/page").unwrap();
+        let document =
+            HtmlDocument::parse(r#"<head><base href="/docs/"><meta charset=" Shift_JIS "></head>"#);
+        assert_eq!(
+            document.base_url(&document_url).as_str(),
+            "https://example.com/docs/"
+        );
+        assert_eq!(document.encoding(), Some("Shift_JIS"));
+
+        let document = HtmlDocument::parse(concat!(
+            r#"<meta http-equiv="refresh" content="5">"#,
+            r#"<meta http-equiv="Content-Type" content="text/html; Charset='euc-jp'">"#,
+        ));
+        assert_eq!(document.base_url(&document_url), document_url);
+        assert_eq!(document.encoding(), Some("euc-jp"));
+        assert_eq!(HtmlDocument::parse("<p>x</p>").encoding(), None);
+    }
+}
@@ -0,0 +1,235 @@
+use std::fmt;
+
+use sxd_document::dom::{ChildOfElement, Element, ParentOfChild};
+
+/// Where [`insert_adjacent_html`] inserts nodes relative to an element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AdjacentPosition {
+    /// Before the element, as its preceding siblings.
+    BeforeBegin,
+    /// Inside the element, before its first child.
+    AfterBegin,
+    /// Inside the element, after its last child.
+    BeforeEnd,
+    /// After the element, as its following siblings.
+    AfterEnd,
+}
+
+impl AdjacentPosition {
+    /// Returns the position as the DOM spells it, such as `beforebegin`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::BeforeBegin => "beforebegin",
+            Self::AfterBegin => "afterbegin",
+            Self::BeforeEnd => "beforeend",
+            Self::AfterEnd => "afterend",
+        }
+    }
+
+    /// Parses a DOM position name, ignoring ASCII case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        [
+            Self::BeforeBegin,
+            Self::AfterBegin,
+            Self::BeforeEnd,
+            Self::AfterEnd,
+        ]
+        .iter()
+        .copied()
+        .find(|position| position.as_str().eq_ignore_ascii_case(name))
+    }
+}
+
+/// The error returned by [`insert_adjacent_html`] when nodes would be
+/// inserted beside an element whose parent is not an element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InsertHtmlError {
+    position: AdjacentPosition,
+}
+
+impl InsertHtmlError {
+    /// Returns the position that could not be used.
+    pub fn position(&self) -> AdjacentPosition {
+        self.position
+    }
+}
+
+impl fmt::Display for InsertHtmlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cannot insert `{}` of an element without a parent element",
+            self.position.as_str()
+        )
+    }
+}
+
+impl std::error::Error for InsertHtmlError {}
+
+/// Replaces the children of `element` with the nodes parsed from `html`, like
+/// the DOM `innerHTML` setter.
+///
+/// The markup is parsed with `element` as the fragment parsing context, so
+/// `<tr>` is kept inside a `<tbody>` and elements inside an `<svg>` or
+/// `<math>` element get the SVG or MathML namespace.
+///
+/// ```rust
+/// let package = sxd_html::parse_html("<ul><li>old</li></ul>");
+/// let root = package.as_document().root();
+/// let ul = sxd_html::select_one(root, "//ul").unwrap().element().unwrap();
+/// sxd_html::set_inner_html(ul, "<li>one<li>two");
+/// assert_eq!(sxd_html::inner_html(ul), "<li>one</li><li>two</li>");
+/// ```
+pub fn set_inner_html(element: Element, html: &str) {
+    element.replace_children(crate::parse_html_fragment_in(element, html));
+}
+
+/// Parses `html` and inserts the nodes at `position` relative to `element`,
+/// like the DOM `insertAdjacentHTML` method.
+///
+/// Inside the element, the markup is parsed with `element` as the fragment
+/// parsing context. Beside it, the parent element is the context, and an
+/// error is returned if the parent is the document root or there is none.
+///
+/// ```rust
+/// use sxd_html::AdjacentPosition;
+///
+/// let package = sxd_html::parse_html("<div><p>middle</p></div>");
+/// let root = package.as_document().root();
+/// let p = sxd_html::select_one(root, "//p").unwrap().element().unwrap();
+/// sxd_html::insert_adjacent_html(p, AdjacentPosition::BeforeBegin, "<p>first</p>").unwrap();
+/// sxd_html::insert_adjacent_html(p, AdjacentPosition::AfterEnd, "<p>last</p>").unwrap();
+/// assert_eq!(
+///     sxd_html::inner_html(sxd_html::select_one(root, "//div").unwrap().element().unwrap()),
+///     "<p>first</p><p>middle</p><p>last</p>"
+/// );
+/// ```
+pub fn insert_adjacent_html(
+    element: Element,
+    position: AdjacentPosition,
+    html: &str,
+) -> Result<(), InsertHtmlError> {
+    match position {
+        AdjacentPosition::AfterBegin => {
+            let mut children = crate::parse_html_fragment_in(element, html);
+            children.extend(element.children());
+            element.replace_children(children);
+        }
+        AdjacentPosition::BeforeEnd => {
+            element.append_children(crate::parse_html_fragment_in(element, html));
+        }
+        AdjacentPosition::BeforeBegin | AdjacentPosition::AfterEnd => {
+            let parent = match element.parent() {
+                Some(ParentOfChild::Element(parent)) => parent,
+                _ => return Err(InsertHtmlError { position }),
+            };
+            let nodes = crate::parse_html_fragment_in(parent, html);
+
+            let mut children = vec![];
+            for child in parent.children() {
+                let is_element = child == ChildOfElement::Element(element);
+                if is_element && position == AdjacentPosition::AfterEnd {
+                    children.push(child);
+                    children.extend(nodes.iter().copied());
+                } else if is_element {
+                    children.extend(nodes.iter().copied());
+                    children.push(child);
+                } else {
+                    children.push(child);
+                }
+            }
+            parent.replace_children(children);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{html_context, inner_html, select_one, to_html, Query};
+
+    fn element<'d>(root: sxd_document::dom::Root<'d>, xpath: &str) -> Element<'d> {
+        select_one(root, xpath).unwrap().element().unwrap()
+    }
+
+    #[test]
+    fn test_set_inner_html_uses_context() {
+        let package = crate::parse_html("<table><tbody></tbody></table><p>x</p>");
+        let root = package.as_document().root();
+
+        let tbody = element(root, "//tbody");
+        set_inner_html(tbody, "<tr><td>1</td></tr>");
+        assert_eq!(inner_html(tbody), "<tr><td>1</td></tr>");
+
+        let p = element(root, "//p");
+        set_inner_html(p, "<tr><td>1</td></tr>");
+        assert_eq!(inner_html(p), "1");
+
+        set_inner_html(p, "");
+        assert_eq!(inner_html(p), "");
+    }
+
+    #[test]
+    fn test_foreign_context_namespaces() {
+        let package = crate::parse_html("<svg></svg><math></math>");
+        let root = package.as_document().root();
+        let query = Query::with_context(html_context());
+
+        set_inner_html(
+            query
+                .select_one(root, "//svg:svg")
+                .unwrap()
+                .element()
+                .unwrap(),
+            r#"<circle r="1"/><a xlink:href="x">y</a>"#,
+        );
+        insert_adjacent_html(
+            query
+                .select_one(root, "//math:math")
+                .unwrap()
+                .element()
+                .unwrap(),
+            AdjacentPosition::BeforeEnd,
+            "<mi>x</mi>",
+        )
+        .unwrap();
+
+        assert_eq!(
+            query.select(root, "//svg:svg/svg:circle").unwrap().size(),
+            1
+        );
+        assert_eq!(query.select_text(root, "//svg:a/@xlink:href").unwrap(), "x");
+        assert_eq!(query.select_text(root, "//math:math/math:mi").unwrap(), "x");
+        assert_eq!(query.select(root, "//circle | //mi").unwrap().size(), 0);
+    }
+
+    #[test]
+    fn test_insert_adjacent_html_positions() {
+        let package = crate::parse_html("<div><p>b</p></div>");
+        let root = package.as_document().root();
+        let p = element(root, "//p");
+
+        insert_adjacent_html(p, AdjacentPosition::BeforeBegin, "<i>1</i>").unwrap();
+        insert_adjacent_html(p, AdjacentPosition::AfterBegin, "a").unwrap();
+        insert_adjacent_html(p, AdjacentPosition::BeforeEnd, "<b>c</b>").unwrap();
+        insert_adjacent_html(p, AdjacentPosition::AfterEnd, "<!--2-->").unwrap();
+        assert_eq!(
+            to_html(element(root, "//div")),
+            "<div><i>1</i><p>ab<b>c</b></p><!--2--></div>"
+        );
+
+        let html = element(root, "/html");
+        assert_eq!(
+            insert_adjacent_html(html, AdjacentPosition::AfterEnd, "<p>x</p>")
+                .unwrap_err()
+                .position(),
+            AdjacentPosition::AfterEnd
+        );
+        assert_eq!(
+            AdjacentPosition::from_name("afterEnd"),
+            Some(AdjacentPosition::AfterEnd)
+        );
+        assert_eq!(AdjacentPosition::from_name("after"), None);
+    }
+}
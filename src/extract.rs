@@ -0,0 +1,139 @@
+use std::{fmt::Display, str::FromStr};
+
+use sxd_xpath::{nodeset::Node, Value};
+
+use crate::{ExtractError, ExtractErrorKind, FromHtmlNode};
+
+/// Something an expression selected for a field.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Selected<'d> {
+    /// A node of the node set an expression evaluated to.
+    Node(Node<'d>),
+    /// The string value of an expression that evaluated to a string, number
+    /// or boolean.
+    Value(String),
+}
+
+impl Selected<'_> {
+    /// Returns the string value of the node, or the value itself.
+    pub fn string_value(&self) -> String {
+        match self {
+            Self::Node(node) => node.string_value(),
+            Self::Value(value) => value.clone(),
+        }
+    }
+}
+
+/// Returns the nodes selected for a field in document order, or the value
+/// of an expression that does not select nodes.
+pub fn select<'d>(
+    node: Node<'d>,
+    expression: &str,
+    css: bool,
+    attribute: Option<&str>,
+) -> Result<Vec<Selected<'d>>, ExtractError> {
+    let value = match css {
+        true => crate::select_css(node, expression).map(Value::Nodeset),
+        false => crate::evaluate(node, expression),
+    }
+    .map_err(|error| ExtractError::new(ExtractErrorKind::Query(error)))?;
+
+    let nodes = match value {
+        Value::Nodeset(nodes) => nodes.document_order(),
+        value => return Ok(vec![Selected::Value(value.into_string())]),
+    };
+
+    Ok(match attribute {
+        Some(attribute) => nodes
+            .into_iter()
+            .filter_map(|node| node.element()?.attribute(attribute))
+            .map(|attribute| Selected::Node(Node::Attribute(attribute)))
+            .collect(),
+        None => nodes.into_iter().map(Selected::Node).collect(),
+    })
+}
+
+pub fn required<'d, T>(
+    selected: Vec<Selected<'d>>,
+    convert: impl Fn(Selected<'d>) -> Result<T, ExtractError>,
+) -> Result<T, ExtractError> {
+    match selected.into_iter().next() {
+        Some(selected) => convert(selected),
+        None => Err(ExtractError::new(ExtractErrorKind::Missing)),
+    }
+}
+
+pub fn optional<'d, T>(
+    selected: Vec<Selected<'d>>,
+    convert: impl Fn(Selected<'d>) -> Result<T, ExtractError>,
+) -> Result<Option<T>, ExtractError> {
+    selected.into_iter().next().map(convert).transpose()
+}
+
+pub fn repeated<'d, T>(
+    selected: Vec<Selected<'d>>,
+    convert: impl Fn(Selected<'d>) -> Result<T, ExtractError>,
+) -> Result<Vec<T>, ExtractError> {
+    selected.into_iter().map(convert).collect()
+}
+
+pub fn convert<T: FromHtmlNode>(selected: Selected<'_>) -> Result<T, ExtractError> {
+    match selected {
+        Selected::Node(node) => T::from_html_node(node),
+        Selected::Value(value) => T::from_html_text(&value),
+    }
+}
+
+pub fn text(selected: Selected<'_>, trim: bool) -> String {
+    let text = selected.string_value();
+    match trim {
+        true => text.trim().to_string(),
+        false => text,
+    }
+}
+
+pub fn parse<T>(selected: Selected<'_>, trim: bool) -> Result<T, ExtractError>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    parse_with(selected, trim, str::parse)
+}
+
+pub fn parse_with<T, E: Display>(
+    selected: Selected<'_>,
+    trim: bool,
+    parse: impl Fn(&str) -> Result<T, E>,
+) -> Result<T, ExtractError> {
+    parse_text(text(selected, trim), parse)
+}
+
+/// Parses `text`, reporting a failure as an [`ExtractErrorKind::Parse`].
+pub(crate) fn parse_text<T, E: Display>(
+    text: String,
+    parse: impl Fn(&str) -> Result<T, E>,
+) -> Result<T, ExtractError> {
+    parse(&text).map_err(|error| {
+        ExtractError::new(ExtractErrorKind::Parse {
+            message: error.to_string(),
+            text,
+        })
+    })
+}
+
+/// Records the field an error occurred in, prefixing the path of an error
+/// from a nested struct or an `[index]` of a repeated field.
+pub fn within(mut error: ExtractError, field: &str, expression: &str) -> ExtractError {
+    if error.field.is_empty() {
+        error.field = field.to_string();
+        error.expression = expression.to_string();
+    } else {
+        let separator = if error.field.starts_with('[') {
+            ""
+        } else {
+            "."
+        };
+        error.field = format!("{}{}{}", field, separator, error.field);
+    }
+    error
+}
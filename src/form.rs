@@ -0,0 +1,759 @@
+use std::collections::HashMap;
+
+use sxd_document::dom::{Element, ParentOfChild, Root};
+use sxd_xpath::nodeset::Node;
+use url::{form_urlencoded, Url};
+
+use crate::util::{self, ElementKey};
+
+/// The `type` values of `input` elements, as in the HTML standard.
+const INPUT_TYPES: &[&str] = &[
+    "hidden",
+    "text",
+    "search",
+    "tel",
+    "url",
+    "email",
+    "password",
+    "date",
+    "month",
+    "week",
+    "time",
+    "datetime-local",
+    "number",
+    "range",
+    "color",
+    "checkbox",
+    "radio",
+    "file",
+    "submit",
+    "image",
+    "reset",
+    "button",
+];
+
+/// The elements the `form` attribute and the parser associate with forms.
+const LISTED_ELEMENTS: &[&str] = &[
+    "button", "fieldset", "input", "object", "output", "select", "textarea",
+];
+
+const URLENCODED: &str = "application/x-www-form-urlencoded";
+const MULTIPART: &str = "multipart/form-data";
+const TEXT_PLAIN: &str = "text/plain";
+
+/// A control of a [`Form`]: an `input`, `button`, `select` or `textarea`
+/// element.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormControl<'d> {
+    /// The control element.
+    pub element: Element<'d>,
+    /// The value of the `name` attribute, empty if there is none.
+    pub name: String,
+    /// The type of the control: the `type` of an `input` (`text` if it is
+    /// missing or unknown) or `button` (`submit` by default),
+    /// `select-one` or `select-multiple` for `select` elements and
+    /// `textarea` for `textarea` elements.
+    pub kind: String,
+    /// The default value: the `value` attribute of an `input` or `button`
+    /// (`on` for checkboxes and radio buttons without one), the text of a
+    /// `textarea` and the value of the first selected option of a `select`.
+    pub value: String,
+    /// Whether a checkbox or radio button is checked by default. Of the
+    /// radio buttons of a group that have the `checked` attribute, only
+    /// the last one is checked.
+    pub checked: bool,
+    /// Whether the control is disabled, by its own `disabled` attribute or
+    /// by that of a `fieldset` it is in.
+    pub disabled: bool,
+    /// The options of a `select` element.
+    pub options: Vec<FormOption<'d>>,
+}
+
+/// An `option` of a `select` control.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormOption<'d> {
+    /// The `option` element.
+    pub element: Element<'d>,
+    /// The `value` attribute, or the text of the option if it has none.
+    pub value: String,
+    /// The `label` attribute, or the text of the option if it has none.
+    pub label: String,
+    /// Whether the option is selected by default.
+    pub selected: bool,
+    /// Whether the option is disabled, by its own `disabled` attribute or
+    /// by that of its `optgroup`.
+    pub disabled: bool,
+}
+
+impl FormControl<'_> {
+    fn is_submit_button(&self) -> bool {
+        matches!(self.kind.as_str(), "submit" | "image")
+    }
+
+    fn is_button(&self) -> bool {
+        matches!(self.kind.as_str(), "submit" | "image" | "reset" | "button")
+    }
+}
+
+/// A name and value of a [`FormSubmission`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormEntry {
+    /// The name of the control.
+    pub name: String,
+    /// The value of the control, or the file name for a file upload.
+    pub value: String,
+    /// Whether the entry is a file upload. The contents of files are not
+    /// modelled, so uploads are submitted empty.
+    pub file: bool,
+}
+
+impl FormEntry {
+    fn text(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+            file: false,
+        }
+    }
+}
+
+/// An HTML form with its controls.
+///
+/// The controls of a form are the `input`, `button`, `select` and
+/// `textarea` elements it owns: those whose `form` attribute names its `id`
+/// and, for controls without a `form` attribute, those inside it. Use
+/// [`HtmlDocument::forms`](crate::HtmlDocument::forms) to also include the
+/// controls the parser associated with the form.
+#[derive(Debug, Clone)]
+pub struct Form<'d> {
+    element: Element<'d>,
+    action: String,
+    method: String,
+    enctype: String,
+    controls: Vec<FormControl<'d>>,
+}
+
+impl<'d> Form<'d> {
+    /// Reads the `form` element `element`, returning `None` if it is not a
+    /// `form` element.
+    pub fn from_element(element: Element<'d>) -> Option<Self> {
+        if util::html_local_name(&element) != Some("form") {
+            return None;
+        }
+
+        let owners = FormOwners::new(element.document().root(), &HashMap::new());
+        let controls = owners
+            .controls()
+            .filter(|&control| owners.owner(control) == Some(element))
+            .collect();
+        Some(Self::with_controls(element, controls))
+    }
+
+    /// Reads the `form` element `element` with the controls it owns, given
+    /// in document order.
+    pub(crate) fn with_controls(element: Element<'d>, controls: Vec<Element<'d>>) -> Self {
+        let mut controls: Vec<_> = controls.into_iter().map(control).collect();
+
+        // Checking a radio button unchecks the others of its group.
+        let mut checked_radios = HashMap::new();
+        for index in 0..controls.len() {
+            let control = &controls[index];
+            if control.kind == "radio" && control.checked && !control.name.is_empty() {
+                if let Some(previous) = checked_radios.insert(control.name.clone(), index) {
+                    controls[previous].checked = false;
+                }
+            }
+        }
+
+        Self {
+            element,
+            action: element.attribute_value("action").unwrap_or("").to_string(),
+            method: method(element.attribute_value("method")).to_string(),
+            enctype: enctype(element.attribute_value("enctype")).to_string(),
+            controls,
+        }
+    }
+
+    /// Returns the `form` element.
+    pub fn element(&self) -> Element<'d> {
+        self.element
+    }
+
+    /// Returns the `action` attribute, empty if there is none, in which
+    /// case the form is submitted to the URL of the document.
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+
+    /// Returns the lowercase `method`: `get` (the default), `post` or
+    /// `dialog`.
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+
+    /// Returns the lowercase `enctype`:
+    /// `application/x-www-form-urlencoded` (the default),
+    /// `multipart/form-data` or `text/plain`.
+    pub fn enctype(&self) -> &str {
+        &self.enctype
+    }
+
+    /// Returns the controls of the form in document order.
+    pub fn controls(&self) -> &[FormControl<'d>] {
+        &self.controls
+    }
+
+    /// Returns the first control named `name`.
+    pub fn control(&self, name: &str) -> Option<&FormControl<'d>> {
+        self.controls.iter().find(|control| control.name == name)
+    }
+
+    /// Returns what submitting the form with the default values of its
+    /// controls sends, submitted by the button `submitter` if given.
+    ///
+    /// The entries follow the HTML standard: disabled controls, controls
+    /// without a name, unchecked checkboxes and radio buttons, and buttons
+    /// other than the submitter are left out. The `formaction`,
+    /// `formmethod` and `formenctype` attributes of the submitter take
+    /// precedence over the attributes of the form.
+    pub fn submit(&self, submitter: Option<&FormControl<'d>>) -> FormSubmission {
+        let submitter = submitter.filter(|submitter| submitter.is_submit_button());
+        let mut submission = FormSubmission {
+            action: self.action.clone(),
+            method: self.method.clone(),
+            enctype: self.enctype.clone(),
+            entries: vec![],
+        };
+        if let Some(submitter) = submitter {
+            let element = submitter.element;
+            if let Some(action) = element.attribute_value("formaction") {
+                submission.action = action.to_string();
+            }
+            if let Some(value) = element.attribute_value("formmethod") {
+                submission.method = method(Some(value)).to_string();
+            }
+            if let Some(value) = element.attribute_value("formenctype") {
+                submission.enctype = enctype(Some(value)).to_string();
+            }
+        }
+
+        for control in &self.controls {
+            let is_submitter = submitter.is_some_and(|submitter| submitter == control);
+            if control.disabled
+                || has_ancestor(control.element, "datalist")
+                || (control.is_button() && !is_submitter)
+            {
+                continue;
+            }
+            if control.kind == "image" {
+                let prefix = match control.name.as_str() {
+                    "" => String::new(),
+                    name => format!("{}.", name),
+                };
+                let entries = &mut submission.entries;
+                entries.push(FormEntry::text(format!("{}x", prefix), "0"));
+                entries.push(FormEntry::text(format!("{}y", prefix), "0"));
+                continue;
+            }
+            if control.name.is_empty() {
+                continue;
+            }
+
+            let entries = &mut submission.entries;
+            match control.kind.as_str() {
+                "checkbox" | "radio" if !control.checked => {}
+                "select-one" | "select-multiple" => entries.extend(
+                    control
+                        .options
+                        .iter()
+                        .filter(|option| option.selected && !option.disabled)
+                        .map(|option| FormEntry::text(&control.name, &option.value)),
+                ),
+                "file" => entries.push(FormEntry {
+                    name: control.name.clone(),
+                    value: String::new(),
+                    file: true,
+                }),
+                "hidden"
+                    if control.name.eq_ignore_ascii_case("_charset_")
+                        && control.element.attribute("value").is_none() =>
+                {
+                    entries.push(FormEntry::text(&control.name, "UTF-8"))
+                }
+                _ => entries.push(FormEntry::text(&control.name, &control.value)),
+            }
+        }
+        submission
+    }
+}
+
+/// The data a [`Form`] submits, returned by [`Form::submit`].
+///
+/// The entries can be changed, to fill in the form, before the request is
+/// built with [`url`](Self::url), [`content_type`](Self::content_type) and
+/// [`body`](Self::body).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormSubmission {
+    /// The `action` URL, which may be relative or empty.
+    pub action: String,
+    /// The lowercase method, `get`, `post` or `dialog`.
+    pub method: String,
+    /// The lowercase encoding type of the body.
+    pub enctype: String,
+    /// The names and values submitted, in document order.
+    pub entries: Vec<FormEntry>,
+}
+
+impl FormSubmission {
+    /// Returns the URL the form is submitted to, resolving the action
+    /// against `document_url`. For the `get` method, the query of the URL
+    /// is replaced with the URL-encoded entries.
+    pub fn url(&self, document_url: &Url) -> Result<Url, url::ParseError> {
+        let mut url = match self.action.as_str() {
+            "" => document_url.clone(),
+            action => document_url.join(action)?,
+        };
+        if self.method == "get" {
+            url.set_query(Some(&encode_urlencoded(&self.entries)));
+        }
+        Ok(url)
+    }
+
+    /// Returns the `Content-Type` of the [`body`](Self::body), which for
+    /// `multipart/form-data` includes `boundary`.
+    pub fn content_type(&self, boundary: &str) -> String {
+        match self.enctype.as_str() {
+            MULTIPART => format!("{}; boundary={}", MULTIPART, boundary),
+            TEXT_PLAIN => format!("{}; charset=UTF-8", TEXT_PLAIN),
+            enctype => enctype.to_string(),
+        }
+    }
+
+    /// Returns the request body in the encoding of the form. `boundary`
+    /// separates the parts of a `multipart/form-data` body and must not
+    /// occur in the entries; other encodings ignore it.
+    pub fn body(&self, boundary: &str) -> Vec<u8> {
+        match self.enctype.as_str() {
+            MULTIPART => encode_multipart(&self.entries, boundary),
+            TEXT_PLAIN => self
+                .entries
+                .iter()
+                .map(|entry| {
+                    format!(
+                        "{}={}\r\n",
+                        normalize_newlines(&entry.name),
+                        normalize_newlines(&entry.value)
+                    )
+                })
+                .collect::<String>()
+                .into_bytes(),
+            _ => encode_urlencoded(&self.entries).into_bytes(),
+        }
+    }
+}
+
+/// Encodes `entries` as `application/x-www-form-urlencoded`.
+pub fn encode_urlencoded(entries: &[FormEntry]) -> String {
+    let mut serializer = form_urlencoded::Serializer::new(String::new());
+    for entry in entries {
+        serializer.append_pair(
+            &normalize_newlines(&entry.name),
+            &normalize_newlines(&entry.value),
+        );
+    }
+    serializer.finish()
+}
+
+/// Encodes `entries` as `multipart/form-data` with the parts separated by
+/// `boundary`, which must not occur in the entries.
+pub fn encode_multipart(entries: &[FormEntry], boundary: &str) -> Vec<u8> {
+    let mut body = String::new();
+    for entry in entries {
+        body.push_str(&format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"{}\"",
+            boundary,
+            escape_multipart(&entry.name)
+        ));
+        if entry.file {
+            body.push_str(&format!(
+                "; filename=\"{}\"\r\nContent-Type: application/octet-stream\r\n\r\n",
+                escape_multipart(&entry.value)
+            ));
+        } else {
+            body.push_str("\r\n\r\n");
+            body.push_str(&normalize_newlines(&entry.value));
+        }
+        body.push_str("\r\n");
+    }
+    body.push_str(&format!("--{}--\r\n", boundary));
+    body.into_bytes()
+}
+
+/// The elements of a document indexed to find the forms owning its
+/// controls, collected in one walk of the tree.
+pub(crate) struct FormOwners<'d> {
+    elements: Vec<Element<'d>>,
+    ids: HashMap<&'d str, Element<'d>>,
+    associations: HashMap<Element<'d>, Element<'d>>,
+}
+
+impl<'d> FormOwners<'d> {
+    /// Indexes the elements below `root`, given the forms the parser
+    /// associated controls with. Associations with an element that is no
+    /// longer a `form` in the tree are ignored.
+    pub(crate) fn new(root: Root<'d>, associations: &HashMap<ElementKey, ElementKey>) -> Self {
+        let elements = util::descendant_elements(ParentOfChild::Root(root));
+
+        let mut ids = HashMap::new();
+        let mut keys = HashMap::new();
+        for &element in &elements {
+            if let Some(id) = element.attribute_value("id") {
+                ids.entry(id).or_insert(element);
+            }
+            if !associations.is_empty() {
+                keys.insert(ElementKey::new(element), element);
+            }
+        }
+        let associations = associations
+            .iter()
+            .filter_map(|(control, form)| Some((*keys.get(control)?, *keys.get(form)?)))
+            .filter(|(_, form)| util::html_local_name(form) == Some("form"))
+            .collect();
+
+        Self {
+            elements,
+            ids,
+            associations,
+        }
+    }
+
+    /// Returns the `form` elements in document order.
+    pub(crate) fn forms(&self) -> impl Iterator<Item = Element<'d>> + '_ {
+        self.elements
+            .iter()
+            .copied()
+            .filter(|element| util::html_local_name(element) == Some("form"))
+    }
+
+    /// Returns the `input`, `button`, `select` and `textarea` elements in
+    /// document order.
+    pub(crate) fn controls(&self) -> impl Iterator<Item = Element<'d>> + '_ {
+        self.elements.iter().copied().filter(|element| {
+            matches!(
+                util::html_local_name(element),
+                Some("input" | "button" | "select" | "textarea")
+            )
+        })
+    }
+
+    /// Returns the form owning `control`.
+    pub(crate) fn owner(&self, control: Element<'d>) -> Option<Element<'d>> {
+        let name = util::html_local_name(&control)?;
+        if LISTED_ELEMENTS.contains(&name) {
+            if let Some(id) = control.attribute_value("form") {
+                return self
+                    .ids
+                    .get(id)
+                    .copied()
+                    .filter(|element| util::html_local_name(element) == Some("form"));
+            }
+        }
+        if let Some(&form) = self.associations.get(&control) {
+            return Some(form);
+        }
+        ancestors(control).find(|element| util::html_local_name(element) == Some("form"))
+    }
+}
+
+fn control(element: Element<'_>) -> FormControl<'_> {
+    let name = util::html_local_name(&element).unwrap_or("");
+    let attribute = |name| element.attribute_value(name).unwrap_or("");
+    let mut control = FormControl {
+        element,
+        name: attribute("name").to_string(),
+        kind: String::new(),
+        value: attribute("value").to_string(),
+        checked: false,
+        disabled: is_disabled(element),
+        options: vec![],
+    };
+
+    match name {
+        "input" => {
+            let kind = attribute("type").to_ascii_lowercase();
+            control.kind = match INPUT_TYPES.contains(&kind.as_str()) {
+                true => kind,
+                false => "text".to_string(),
+            };
+            match control.kind.as_str() {
+                "checkbox" | "radio" => {
+                    control.checked = element.attribute("checked").is_some();
+                    if element.attribute("value").is_none() {
+                        control.value = "on".to_string();
+                    }
+                }
+                "text" | "search" | "tel" | "password" => {
+                    control.value.retain(|c| c != '\n' && c != '\r')
+                }
+                _ => {}
+            }
+        }
+        "button" => {
+            control.kind = match attribute("type").to_ascii_lowercase().as_str() {
+                kind @ ("reset" | "button") => kind.to_string(),
+                _ => "submit".to_string(),
+            };
+        }
+        "select" => {
+            let multiple = element.attribute("multiple").is_some();
+            control.kind = match multiple {
+                true => "select-multiple",
+                false => "select-one",
+            }
+            .to_string();
+            control.options = util::descendant_elements(ParentOfChild::Element(element))
+                .into_iter()
+                .filter(|option| util::html_local_name(option) == Some("option"))
+                .map(option)
+                .collect();
+
+            // Only one option of a drop-down list is selected: the last one
+            // with the `selected` attribute, or the first enabled one.
+            let size = attribute("size").trim().parse::<usize>().unwrap_or(1);
+            if !multiple {
+                let selected = control.options.iter().rposition(|option| option.selected);
+                let selected = match selected {
+                    None if size <= 1 => control.options.iter().position(|option| !option.disabled),
+                    selected => selected,
+                };
+                for (index, option) in control.options.iter_mut().enumerate() {
+                    option.selected = Some(index) == selected;
+                }
+            }
+            control.value = control
+                .options
+                .iter()
+                .find(|option| option.selected)
+                .map(|option| option.value.clone())
+                .unwrap_or_default();
+        }
+        _ => {
+            control.kind = "textarea".to_string();
+            control.value = Node::Element(element).string_value();
+        }
+    }
+    control
+}
+
+fn option(element: Element<'_>) -> FormOption<'_> {
+    let text = Node::Element(element)
+        .string_value()
+        .split_ascii_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let optgroup_disabled = element
+        .parent()
+        .and_then(|parent| parent.element())
+        .is_some_and(|parent| {
+            util::html_local_name(&parent) == Some("optgroup")
+                && parent.attribute("disabled").is_some()
+        });
+
+    FormOption {
+        element,
+        value: element
+            .attribute_value("value")
+            .unwrap_or(&text)
+            .to_string(),
+        label: element
+            .attribute_value("label")
+            .filter(|label| !label.is_empty())
+            .unwrap_or(&text)
+            .to_string(),
+        selected: element.attribute("selected").is_some(),
+        disabled: element.attribute("disabled").is_some() || optgroup_disabled,
+    }
+}
+
+/// Returns whether `control` is disabled by its `disabled` attribute or by a
+/// disabled `fieldset`, whose first `legend` does not disable its contents.
+fn is_disabled(control: Element<'_>) -> bool {
+    if control.attribute("disabled").is_some() {
+        return true;
+    }
+
+    let mut child = control;
+    for ancestor in ancestors(control) {
+        if util::html_local_name(&ancestor) == Some("fieldset")
+            && ancestor.attribute("disabled").is_some()
+        {
+            let first_legend = ancestor
+                .children()
+                .into_iter()
+                .filter_map(|child| child.element())
+                .find(|child| util::html_local_name(child) == Some("legend"));
+            if first_legend != Some(child) {
+                return true;
+            }
+        }
+        child = ancestor;
+    }
+    false
+}
+
+fn ancestors(element: Element<'_>) -> impl Iterator<Item = Element<'_>> {
+    std::iter::successors(
+        element.parent().and_then(|parent| parent.element()),
+        |element| element.parent().and_then(|parent| parent.element()),
+    )
+}
+
+fn has_ancestor(element: Element<'_>, name: &str) -> bool {
+    ancestors(element).any(|ancestor| util::html_local_name(&ancestor) == Some(name))
+}
+
+fn method(value: Option<&str>) -> &'static str {
+    match value.map(str::to_ascii_lowercase).as_deref() {
+        Some("post") => "post",
+        Some("dialog") => "dialog",
+        _ => "get",
+    }
+}
+
+fn enctype(value: Option<&str>) -> &'static str {
+    match value.map(str::to_ascii_lowercase).as_deref() {
+        Some(MULTIPART) => MULTIPART,
+        Some(TEXT_PLAIN) => TEXT_PLAIN,
+        _ => URLENCODED,
+    }
+}
+
+/// Converts every line break to CRLF, as forms submit them.
+fn normalize_newlines(value: &str) -> String {
+    value
+        .replace("\r\n", "\n")
+        .replace('\r', "\n")
+        .replace('\n', "\r\n")
+}
+
+fn escape_multipart(value: &str) -> String {
+    value
+        .replace('\n', "%0A")
+        .replace('\r', "%0D")
+        .replace('"', "%22")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HtmlDocument;
+
+    #[test]
+    fn test_controls() {
+        let document = HtmlDocument::parse(concat!(
+            r#"<form id="f" action="/search" method="POST">"#,
+            r#"<input name="q" value="rust"><input type="checkbox" name="c" checked>"#,
+            r#"<input type="radio" name="r" value="1" checked><input type="radio" name="r" value="2" checked>"#,
+            r#"<select name="s"><option>a</option><optgroup disabled><option value="b" selected> B </option></optgroup></select>"#,
+            r#"<textarea name="t">x</textarea><fieldset disabled><input name="d"></fieldset>"#,
+            r#"</form><input name="outside" form="f"><input name="other">"#,
+        ));
+        let forms = document.forms();
+        assert_eq!(forms.len(), 1);
+        let form = &forms[0];
+        assert_eq!(form.action(), "/search");
+        assert_eq!(form.method(), "post");
+        assert_eq!(form.enctype(), URLENCODED);
+
+        let summary: Vec<_> = form
+            .controls()
+            .iter()
+            .map(|control| {
+                (
+                    control.name.as_str(),
+                    control.kind.as_str(),
+                    control.value.as_str(),
+                    control.checked,
+                    control.disabled,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("q", "text", "rust", false, false),
+                ("c", "checkbox", "on", true, false),
+                ("r", "radio", "1", false, false),
+                ("r", "radio", "2", true, false),
+                ("s", "select-one", "b", false, false),
+                ("t", "textarea", "x", false, false),
+                ("d", "text", "", false, true),
+                ("outside", "text", "", false, false),
+            ]
+        );
+        let select = form.control("s").unwrap();
+        assert_eq!(select.options[1].label, "B");
+        assert!(select.options[1].disabled);
+    }
+
+    #[test]
+    fn test_parser_association() {
+        let document = HtmlDocument::parse(r#"<div><form action="a"></div><input name="x">"#);
+        let input = util::descendant_elements(ParentOfChild::Root(document.root()))
+            .into_iter()
+            .find(|element| element.name().local_part() == "input")
+            .unwrap();
+        let owner = document.form_owner(input).unwrap();
+        assert_eq!(owner.attribute_value("action"), Some("a"));
+        assert_eq!(document.forms()[0].controls().len(), 1);
+
+        let form = Form::from_element(owner).unwrap();
+        assert!(form.controls().is_empty());
+
+        let div = owner.parent().and_then(|parent| parent.element()).unwrap();
+        crate::insert_adjacent_html(div, crate::AdjacentPosition::BeforeBegin, "<p>x</p>").unwrap();
+        assert_eq!(document.form_owner(input), Some(owner));
+        assert_eq!(document.forms()[0].controls().len(), 1);
+    }
+
+    #[test]
+    fn test_submission() {
+        let document = HtmlDocument::parse(concat!(
+            r#"<form action="search?old=1#top"><input name="q" value="a b&c">"#,
+            r#"<select name="m" multiple><option selected>x</option><option selected>y</option></select>"#,
+            r#"<textarea name="t">1&#10;2</textarea><input type="file" name="f">"#,
+            r#"<button name="go" value="1">Go</button><button name="reset" type="reset">"#,
+            r#"<button name="post" formmethod="post" formenctype="multipart/form-data">"#,
+            r#"</form>"#,
+        ));
+        let forms = document.forms();
+        let form = &forms[0];
+
+        let submission = form.submit(form.control("go"));
+        let document_url = Url::parse("https://example.com/dir/page").unwrap();
+        assert_eq!(
+            submission.url(&document_url).unwrap().as_str(),
+            "https://example.com/dir/search?q=a+b%26c&m=x&m=y&t=1%0D%0A2&f=&go=1#top"
+        );
+
+        let submission = form.submit(form.control("post"));
+        assert_eq!(submission.method, "post");
+        assert_eq!(
+            submission.content_type("B"),
+            "multipart/form-data; boundary=B"
+        );
+        assert_eq!(
+            String::from_utf8(submission.body("B")).unwrap(),
+            concat!(
+                "--B\r\nContent-Disposition: form-data; name=\"q\"\r\n\r\na b&c\r\n",
+                "--B\r\nContent-Disposition: form-data; name=\"m\"\r\n\r\nx\r\n",
+                "--B\r\nContent-Disposition: form-data; name=\"m\"\r\n\r\ny\r\n",
+                "--B\r\nContent-Disposition: form-data; name=\"t\"\r\n\r\n1\r\n2\r\n",
+                "--B\r\nContent-Disposition: form-data; name=\"f\"; filename=\"\"\r\n",
+                "Content-Type: application/octet-stream\r\n\r\n\r\n",
+                "--B\r\nContent-Disposition: form-data; name=\"post\"\r\n\r\n\r\n",
+                "--B--\r\n",
+            )
+        );
+    }
+}
@@ -0,0 +1,244 @@
+use std::fmt;
+
+use sxd_xpath::nodeset::Node;
+
+use crate::QueryError;
+
+/// Construction of a value from an HTML node.
+///
+/// With the `derive` feature, `#[derive(FromHtml)]` implements this trait
+/// for structs whose fields are annotated with the expression that selects
+/// them:
+///
+/// ```rust
+/// # #[cfg(feature = "derive")]
+/// # fn main() -> Result<(), sxd_html::ExtractError> {
+/// use sxd_html::FromHtml;
+///
+/// #[derive(FromHtml)]
+/// struct Repo {
+///     #[xpath(".//h1/a/@href")]
+///     url: String,
+///     #[css("span.stars", trim)]
+///     stars: Option<u32>,
+///     #[css("a.topic")]
+///     topics: Vec<String>,
+/// }
+///
+/// let repo = Repo::from_html(
+///     r#"<h1><a href="/rust-lang/rust">rust</a></h1><span class="stars"> 42 </span>"#,
+/// )?;
+/// assert_eq!(repo.url, "/rust-lang/rust");
+/// assert_eq!(repo.stars, Some(42));
+/// assert!(repo.topics.is_empty());
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "derive"))]
+/// # fn main() {}
+/// ```
+///
+/// Each field has an `#[xpath("...")]` or `#[css("...")]` attribute,
+/// evaluated with the node passed to [`from_node`](FromHtml::from_node) as
+/// the context node. A field of type `Option<T>` is `None` when nothing
+/// matches, a `Vec<T>` holds a value for every match in document order, and
+/// any other field requires a match. XPath expressions that yield a string,
+/// number or boolean are converted with
+/// [`from_html_text`](FromHtmlNode::from_html_text).
+///
+/// Values are converted with [`FromHtmlNode`], which `#[derive(FromHtml)]`
+/// also implements, so fields can be nested structs. The attribute accepts
+/// these options after the expression:
+///
+/// * `attr = "name"` uses the value of an attribute of the selected
+///   elements, which CSS selectors cannot select themselves.
+/// * `trim` trims whitespace from the text and parses it with `FromStr`.
+/// * `parse` parses the text with `FromStr`, for types that do not
+///   implement [`FromHtmlNode`].
+/// * `parse_with = path` parses the text with a function taking `&str` and
+///   returning a `Result` whose error implements `Display`.
+pub trait FromHtml: Sized {
+    /// Extracts a value from `node` and its descendants.
+    fn from_node<'d>(node: impl Into<Node<'d>>) -> Result<Self, ExtractError>;
+
+    /// Parses `html` as a document and extracts a value from its root.
+    fn from_html(html: &str) -> Result<Self, ExtractError> {
+        let package = crate::parse_html(html);
+        Self::from_node(package.as_document().root())
+    }
+}
+
+/// Conversion of a node selected for a field of a `#[derive(FromHtml)]`
+/// struct.
+///
+/// `String` uses the string value of the node as is, while the number
+/// types, `bool` and `char` parse the trimmed string value.
+pub trait FromHtmlNode: Sized {
+    /// Converts `node`.
+    fn from_html_node(node: Node<'_>) -> Result<Self, ExtractError>;
+
+    /// Converts the value of an expression that yields a string, number or
+    /// boolean instead of nodes.
+    ///
+    /// The default implementation fails, as types such as nested structs
+    /// can only be built from a node.
+    fn from_html_text(text: &str) -> Result<Self, ExtractError> {
+        Err(ExtractError::new(ExtractErrorKind::Parse {
+            text: text.to_string(),
+            message: "expected a node".to_string(),
+        }))
+    }
+}
+
+impl FromHtmlNode for String {
+    fn from_html_node(node: Node<'_>) -> Result<Self, ExtractError> {
+        Ok(node.string_value())
+    }
+
+    fn from_html_text(text: &str) -> Result<Self, ExtractError> {
+        Ok(text.to_string())
+    }
+}
+
+macro_rules! impl_from_html_node_with_from_str {
+    ($($t:ty),*) => {
+        $(
+            impl FromHtmlNode for $t {
+                fn from_html_node(node: Node<'_>) -> Result<Self, ExtractError> {
+                    Self::from_html_text(&node.string_value())
+                }
+
+                fn from_html_text(text: &str) -> Result<Self, ExtractError> {
+                    crate::extract::parse_text(text.trim().to_string(), str::parse)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_html_node_with_from_str!(
+    i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64, bool, char
+);
+
+/// An error returned by [`FromHtml::from_node`].
+///
+/// The error names the field that could not be extracted, as a path such
+/// as `owner.name` for fields of nested structs, and the expression of that
+/// field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtractError {
+    pub(crate) field: String,
+    pub(crate) expression: String,
+    kind: Box<ExtractErrorKind>,
+}
+
+/// The reason a field could not be extracted.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExtractErrorKind {
+    /// The expression could not be evaluated.
+    Query(QueryError),
+    /// The expression did not match anything, but the field is required.
+    Missing,
+    /// The selected text could not be parsed.
+    Parse { text: String, message: String },
+}
+
+impl ExtractError {
+    pub(crate) fn new(kind: ExtractErrorKind) -> Self {
+        Self {
+            field: String::new(),
+            expression: String::new(),
+            kind: Box::new(kind),
+        }
+    }
+
+    /// Returns the path of the field that could not be extracted.
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+
+    /// Returns the expression of the field.
+    pub fn expression(&self) -> &str {
+        &self.expression
+    }
+
+    /// Returns the reason the field could not be extracted.
+    pub fn kind(&self) -> &ExtractErrorKind {
+        &self.kind
+    }
+}
+
+impl fmt::Display for ExtractError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unable to extract `{}` with `{}`: ",
+            self.field, self.expression
+        )?;
+        match self.kind.as_ref() {
+            ExtractErrorKind::Query(error) => write!(f, "{}", error),
+            ExtractErrorKind::Missing => write!(f, "nothing matched"),
+            ExtractErrorKind::Parse { text, message } => {
+                write!(f, "unable to parse {:?}: {}", text, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ExtractError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self.kind.as_ref() {
+            ExtractErrorKind::Query(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Functions called by the code `#[derive(FromHtml)]` generates.
+#[doc(hidden)]
+pub mod __private {
+    pub use sxd_xpath::nodeset::Node;
+
+    pub use crate::extract::{
+        convert, optional, parse, parse_with, repeated, required, select, text, within, Selected,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_html_node() {
+        let package = crate::parse_html("<p> 42 </p><p>x</p>");
+        let root = package.as_document().root();
+        let selected = __private::select(root.into(), "//p", false, None).unwrap();
+
+        assert_eq!(
+            __private::convert::<String>(selected[0].clone()).unwrap(),
+            " 42 "
+        );
+        assert_eq!(__private::convert::<u8>(selected[0].clone()).unwrap(), 42);
+        let error = __private::within(
+            __private::convert::<u8>(selected[1].clone()).unwrap_err(),
+            "n",
+            "//p",
+        );
+        assert_eq!(
+            error.to_string(),
+            "unable to extract `n` with `//p`: unable to parse \"x\": invalid digit found in string"
+        );
+
+        let selected = __private::select(root.into(), "count(//p)", false, None).unwrap();
+        assert_eq!(selected, vec![__private::Selected::Value("2".to_string())]);
+        assert_eq!(__private::convert::<u8>(selected[0].clone()).unwrap(), 2);
+        assert!(__private::convert::<Wrapper>(selected[0].clone()).is_err());
+    }
+
+    struct Wrapper;
+
+    impl FromHtmlNode for Wrapper {
+        fn from_html_node(_: Node<'_>) -> Result<Self, ExtractError> {
+            Ok(Wrapper)
+        }
+    }
+}
@@ -0,0 +1,329 @@
+use std::collections::HashSet;
+
+use sxd_document::dom::Element;
+use sxd_xpath::{
+    context::{Context, Evaluation},
+    function::{Args, Error, Function},
+    nodeset::{Node, Nodeset},
+    Value,
+};
+
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// Namespace prefixes registered by [`register_namespaces`].
+pub(crate) const NAMESPACES: &[(&str, &str)] = &[
+    ("svg", "http://www.w3.org/2000/svg"),
+    ("math", "http://www.w3.org/1998/Math/MathML"),
+    ("xlink", "http://www.w3.org/1999/xlink"),
+];
+
+/// Creates a context with every function and namespace prefix this crate
+/// provides.
+///
+/// The context has the core XPath 1.0 functions, the functions registered
+/// by [`register_html_functions`] and
+/// [`register_string_functions`](crate::register_string_functions), and the
+/// prefixes registered by [`register_namespaces`].
+///
+/// ```rust
+/// let package = sxd_html::parse_html("<p class='Price'>1,280 YEN</p>");
+/// let root = package.as_document().root();
+/// let query = sxd_html::Query::with_context(sxd_html::html_context());
+/// assert_eq!(query.select_text(root, "lower-case(//p)").unwrap(), "1,280 yen");
+/// ```
+pub fn html_context<'d>() -> Context<'d> {
+    let mut context = Context::new();
+    register_html_functions(&mut context);
+    crate::register_string_functions(&mut context);
+    register_namespaces(&mut context);
+    context
+}
+
+/// Registers the `svg`, `math` and `xlink` namespace prefixes on `context`.
+///
+/// HTML elements have no namespace, so they are selected without a prefix,
+/// but SVG and MathML elements keep their namespaces and need one, as in
+/// `//svg:svg/svg:title` or `//svg:use/@xlink:href`.
+pub fn register_namespaces(context: &mut Context) {
+    for (prefix, uri) in NAMESPACES {
+        context.set_namespace(prefix, uri);
+    }
+}
+
+/// Registers XPath functions for querying HTML documents on `context`.
+///
+/// * `has-class(name, ...)` is true if the context element's `class`
+///   attribute contains every given class name.
+/// * `id(ids)` selects the elements whose HTML `id` attribute matches any of
+///   the whitespace separated ids. Like the XPath 1.0 `id()`, a node set
+///   argument is converted using the string value of each node.
+/// * `lang(language)` is true if the language of the context node, given by
+///   the nearest `lang` or `xml:lang` attribute, is `language` or one of its
+///   sub-languages, compared case-insensitively.
+/// * `attr-ci(name, value)` is true if the context element has an attribute
+///   named `name` whose value equals `value`, ignoring case in both.
+/// * `inner-text(nodes?)` returns the [`rendered_text`](crate::rendered_text)
+///   of the first node of `nodes`, or of the context node.
+///
+/// `id()` and `lang()` replace the XPath 1.0 functions of the same name,
+/// which only consider XML identifiers and `xml:lang`.
+pub fn register_html_functions(context: &mut Context) {
+    context.set_function("has-class", HasClass);
+    context.set_function("id", Id);
+    context.set_function("lang", Lang);
+    context.set_function("attr-ci", AttrCi);
+    context.set_function("inner-text", InnerText);
+}
+
+struct HasClass;
+
+impl Function for HasClass {
+    fn evaluate<'c, 'd>(
+        &self,
+        context: &Evaluation<'c, 'd>,
+        args: Vec<Value<'d>>,
+    ) -> Result<Value<'d>, Error> {
+        let args = Args(args);
+        args.at_least(1)?;
+
+        let classes = context
+            .node
+            .element()
+            .and_then(|element| element.attribute_value("class"))
+            .unwrap_or_default();
+        let classes: HashSet<&str> = classes.split_ascii_whitespace().collect();
+
+        let has_all = args
+            .0
+            .into_iter()
+            .map(Value::into_string)
+            .all(|name| classes.contains(name.as_str()));
+        Ok(Value::Boolean(has_all))
+    }
+}
+
+struct Id;
+
+impl Function for Id {
+    fn evaluate<'c, 'd>(
+        &self,
+        context: &Evaluation<'c, 'd>,
+        args: Vec<Value<'d>>,
+    ) -> Result<Value<'d>, Error> {
+        let mut args = Args(args);
+        args.exactly(1)?;
+
+        let ids: Vec<String> = match args.0.pop() {
+            Some(Value::Nodeset(nodes)) => nodes.iter().map(|node| node.string_value()).collect(),
+            Some(value) => vec![value.into_string()],
+            None => vec![],
+        };
+        let ids: HashSet<&str> = ids
+            .iter()
+            .flat_map(|id| id.split_ascii_whitespace())
+            .collect();
+
+        let mut selected = Nodeset::new();
+        let mut stack: Vec<Node<'d>> = vec![Node::Root(context.node.document().root())];
+        while let Some(node) = stack.pop() {
+            if let Node::Element(element) = node {
+                if element
+                    .attribute_value("id")
+                    .is_some_and(|id| ids.contains(id))
+                {
+                    selected.add(element);
+                }
+            }
+            stack.extend(node.children());
+        }
+
+        Ok(Value::Nodeset(selected))
+    }
+}
+
+struct Lang;
+
+impl Function for Lang {
+    fn evaluate<'c, 'd>(
+        &self,
+        context: &Evaluation<'c, 'd>,
+        args: Vec<Value<'d>>,
+    ) -> Result<Value<'d>, Error> {
+        let mut args = Args(args);
+        args.exactly(1)?;
+        let language = args.pop_string()?.to_ascii_lowercase();
+
+        let mut node = Some(context.node);
+        while let Some(current) = node {
+            if let Some(lang) = current.element().and_then(element_language) {
+                let lang = lang.to_ascii_lowercase();
+                let matches = lang == language
+                    || lang
+                        .strip_prefix(&language)
+                        .is_some_and(|rest| rest.starts_with('-'));
+                return Ok(Value::Boolean(matches));
+            }
+            node = current.parent();
+        }
+
+        Ok(Value::Boolean(false))
+    }
+}
+
+fn element_language<'d>(element: Element<'d>) -> Option<&'d str> {
+    element
+        .attribute_value((XML_NAMESPACE, "lang"))
+        .or_else(|| element.attribute_value("xml:lang"))
+        .or_else(|| element.attribute_value("lang"))
+}
+
+struct AttrCi;
+
+impl Function for AttrCi {
+    fn evaluate<'c, 'd>(
+        &self,
+        context: &Evaluation<'c, 'd>,
+        args: Vec<Value<'d>>,
+    ) -> Result<Value<'d>, Error> {
+        let mut args = Args(args);
+        args.exactly(2)?;
+        let value = args.pop_string()?.to_lowercase();
+        let name = args.pop_string()?;
+
+        let matches = context.node.element().is_some_and(|element| {
+            element.attributes().iter().any(|attribute| {
+                attribute.name().local_part().eq_ignore_ascii_case(&name)
+                    && attribute.value().to_lowercase() == value
+            })
+        });
+        Ok(Value::Boolean(matches))
+    }
+}
+
+struct InnerText;
+
+impl Function for InnerText {
+    fn evaluate<'c, 'd>(
+        &self,
+        context: &Evaluation<'c, 'd>,
+        args: Vec<Value<'d>>,
+    ) -> Result<Value<'d>, Error> {
+        let mut args = Args(args);
+        args.at_most(1)?;
+
+        let node = if args.is_empty() {
+            Some(context.node)
+        } else {
+            args.pop_nodeset()?.document_order_first()
+        };
+        let text = node.map(crate::rendered_text).unwrap_or_default();
+        Ok(Value::String(text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Query;
+
+    const HTML: &str = concat!(
+        r#"<html lang="en-US"><body>"#,
+        r#"<div id="main" class="post  featured" data-kind="NEWS"><p>one</p><p>two</p></div>"#,
+        r#"<div id="other" class="post" lang="ja">こんにちは</div>"#,
+        "</body></html>",
+    );
+
+    fn query() -> Query<'static> {
+        let mut query = Query::new();
+        register_html_functions(query.context_mut());
+        query
+    }
+
+    #[test]
+    fn test_has_class() {
+        let package = crate::parse_html(HTML);
+        let root = package.as_document().root();
+        let query = query();
+
+        assert_eq!(
+            query
+                .select(root, "//div[has-class('post')]")
+                .unwrap()
+                .size(),
+            2
+        );
+        assert_eq!(
+            query
+                .select_attr(root, "//div[has-class('post', 'featured')]", "id")
+                .unwrap(),
+            "main"
+        );
+        assert_eq!(
+            query
+                .select(root, "//div[has-class('pos')]")
+                .unwrap()
+                .size(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_id_and_lang() {
+        let package = crate::parse_html(HTML);
+        let root = package.as_document().root();
+        let query = query();
+
+        assert_eq!(query.select(root, "id('main other')").unwrap().size(), 2);
+        assert_eq!(
+            query.select_text(root, "id('other')").unwrap(),
+            "こんにちは"
+        );
+        assert_eq!(query.select(root, "//div[lang('en')]").unwrap().size(), 1);
+        assert_eq!(query.select(root, "//div[lang('ja')]").unwrap().size(), 1);
+        assert_eq!(query.select(root, "//div[lang('e')]").unwrap().size(), 0);
+    }
+
+    #[test]
+    fn test_attr_ci_and_inner_text() {
+        let package = crate::parse_html(HTML);
+        let root = package.as_document().root();
+        let query = query();
+
+        assert_eq!(
+            query
+                .select_attr(root, "//div[attr-ci('DATA-KIND', 'news')]", "id")
+                .unwrap(),
+            "main"
+        );
+        assert_eq!(
+            query.select_text(root, "inner-text(id('main'))").unwrap(),
+            "one\n\ntwo"
+        );
+
+        let main = query.select_one(root, "id('main')").unwrap();
+        assert_eq!(
+            query.select_text(main, "inner-text()").unwrap(),
+            "one\n\ntwo"
+        );
+    }
+
+    #[test]
+    fn test_html_context_namespaces() {
+        let package = crate::parse_html(
+            r##"<svg><title>icon</title><use xlink:href="#i"></use></svg><math><mi>x</mi></math>"##,
+        );
+        let root = package.as_document().root();
+        let query = Query::with_context(html_context());
+
+        assert_eq!(
+            query.select_text(root, "//svg:svg/svg:title").unwrap(),
+            "icon"
+        );
+        assert_eq!(
+            query.select_text(root, "//svg:use/@xlink:href").unwrap(),
+            "#i"
+        );
+        assert_eq!(query.select_text(root, "//math:mi").unwrap(), "x");
+        assert_eq!(query.select(root, "//title").unwrap().size(), 0);
+    }
+}
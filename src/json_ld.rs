@@ -0,0 +1,326 @@
+use serde_json::{Map, Value as JsonValue};
+use sxd_document::{
+    dom::{Element, ParentOfChild},
+    Package,
+};
+use sxd_xpath::nodeset::Node;
+
+use crate::util;
+
+/// The JSON-LD items and diagnostics of a document, returned by
+/// [`json_ld`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JsonLd<'d> {
+    /// The items in document order.
+    pub items: Vec<JsonLdItem<'d>>,
+    /// The problems found in the scripts, in document order.
+    pub diagnostics: Vec<JsonLdDiagnostic<'d>>,
+}
+
+/// A JSON-LD node: a top-level object of a script, an element of a
+/// top-level array, or a member of a `@graph`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonLdItem<'d> {
+    /// The `script` element the item was read from.
+    pub element: Element<'d>,
+    /// The item. Members of a `@graph` inherit the `@context` of the
+    /// object holding the graph unless they have their own.
+    pub value: JsonValue,
+}
+
+impl JsonLdItem<'_> {
+    /// Returns the values of the `@type` of the item, such as `Article` or
+    /// `https://schema.org/Person`.
+    pub fn types(&self) -> Vec<&str> {
+        match self.value.get("@type") {
+            Some(JsonValue::String(kind)) => vec![kind.as_str()],
+            Some(JsonValue::Array(kinds)) => kinds.iter().filter_map(JsonValue::as_str).collect(),
+            _ => vec![],
+        }
+    }
+}
+
+/// A problem found in a JSON-LD script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonLdDiagnostic<'d> {
+    /// The `script` element.
+    pub element: Element<'d>,
+    /// Whether the script was repaired or could not be read.
+    pub kind: JsonLdDiagnosticKind,
+    /// A description of the problem.
+    pub message: String,
+}
+
+/// What became of a script with a [`JsonLdDiagnostic`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum JsonLdDiagnosticKind {
+    /// The script is not valid JSON, but was read after removing comments
+    /// and trailing commas or escaping line breaks in strings.
+    Repaired,
+    /// The script could not be read, and yields no items.
+    Invalid,
+}
+
+/// Reads the JSON-LD structured data of the document in `package` from its
+/// `<script type="application/ld+json">` elements.
+///
+/// Scripts are read leniently: HTML and JavaScript comments, trailing
+/// commas and unescaped line breaks in strings are tolerated and reported
+/// as [`Repaired`](JsonLdDiagnosticKind::Repaired). Top-level arrays and
+/// `@graph` arrays are flattened into separate items.
+///
+/// ```rust
+/// let package = sxd_html::parse_html(r#"
+///     <script type="application/ld+json">
+///     <!--
+///     {"@context": "https://schema.org", "@type": "Article", "headline": "Hi",}
+///     -->
+///     </script>
+/// "#);
+/// let json_ld = sxd_html::json_ld(&package);
+/// assert_eq!(json_ld.items[0].types(), vec!["Article"]);
+/// assert_eq!(json_ld.items[0].value["headline"], "Hi");
+/// assert_eq!(json_ld.diagnostics.len(), 1);
+/// ```
+pub fn json_ld(package: &Package) -> JsonLd<'_> {
+    let root = package.as_document().root();
+    let mut json_ld = JsonLd::default();
+
+    for element in util::descendant_elements(ParentOfChild::Root(root)) {
+        if util::html_local_name(&element) != Some("script") || !is_json_ld(element) {
+            continue;
+        }
+
+        let mut diagnostic = |kind, message| {
+            json_ld.diagnostics.push(JsonLdDiagnostic {
+                element,
+                kind,
+                message,
+            })
+        };
+        let text = Node::Element(element).string_value();
+        if text.trim().is_empty() {
+            diagnostic(
+                JsonLdDiagnosticKind::Invalid,
+                "the script is empty".to_string(),
+            );
+            continue;
+        }
+
+        let value = match serde_json::from_str(&text) {
+            Ok(value) => value,
+            Err(error) => {
+                let (repaired, repairs) = repair(&text);
+                match serde_json::from_str(&repaired) {
+                    Ok(value) if !repairs.is_empty() => {
+                        diagnostic(
+                            JsonLdDiagnosticKind::Repaired,
+                            format!("{}; read after {}", error, repairs.join(", ")),
+                        );
+                        value
+                    }
+                    _ => {
+                        diagnostic(JsonLdDiagnosticKind::Invalid, error.to_string());
+                        continue;
+                    }
+                }
+            }
+        };
+
+        let mut values = vec![];
+        flatten(value, None, &mut values);
+        json_ld.items.extend(
+            values
+                .into_iter()
+                .map(|value| JsonLdItem { element, value }),
+        );
+    }
+    json_ld
+}
+
+fn is_json_ld(script: Element<'_>) -> bool {
+    let kind = script.attribute_value("type").unwrap_or("");
+    let essence = kind.split(';').next().unwrap_or("").trim();
+    essence.eq_ignore_ascii_case("application/ld+json")
+}
+
+/// Adds the JSON-LD nodes of `value` to `items`, giving the members of a
+/// `@graph` the `@context` of their container.
+fn flatten(value: JsonValue, context: Option<&JsonValue>, items: &mut Vec<JsonValue>) {
+    match value {
+        JsonValue::Array(values) => {
+            for value in values {
+                flatten(value, context, items);
+            }
+        }
+        JsonValue::Object(mut object) => {
+            if let (Some(context), false) = (context, object.contains_key("@context")) {
+                let mut with_context = Map::new();
+                with_context.insert("@context".to_string(), context.clone());
+                with_context.append(&mut object);
+                object = with_context;
+            }
+            match object.remove("@graph") {
+                Some(graph) => flatten(graph, object.get("@context"), items),
+                None => items.push(JsonValue::Object(object)),
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Removes comments and trailing commas from `text` and escapes line
+/// breaks in strings, returning the result and a description of each kind
+/// of repair made.
+fn repair(text: &str) -> (String, Vec<&'static str>) {
+    let mut repairs = vec![];
+    let mut note = |repair| {
+        if !repairs.contains(&repair) {
+            repairs.push(repair);
+        }
+    };
+
+    // Comments first, so that commas before them count as trailing.
+    let mut uncommented = String::with_capacity(text.len());
+    let mut rest = text;
+    let mut in_string = false;
+    while let Some(c) = rest.chars().next() {
+        if in_string {
+            match c {
+                '\\' => {
+                    let escaped = rest.chars().nth(1).map_or(0, char::len_utf8);
+                    uncommented.push_str(&rest[..1 + escaped]);
+                    rest = &rest[1 + escaped..];
+                    continue;
+                }
+                '"' => in_string = false,
+                '\n' => {
+                    note("escaping line breaks in strings");
+                    uncommented.push_str("\\n");
+                    rest = &rest[1..];
+                    continue;
+                }
+                '\r' => {
+                    note("escaping line breaks in strings");
+                    uncommented.push_str("\\r");
+                    rest = &rest[1..];
+                    continue;
+                }
+                _ => {}
+            }
+        } else {
+            let skip = if rest.starts_with("<!--") {
+                Some(4)
+            } else if rest.starts_with("-->") {
+                Some(3)
+            } else if rest.starts_with("//") {
+                Some(rest.find('\n').unwrap_or(rest.len()))
+            } else if rest.starts_with("/*") {
+                Some(rest.find("*/").map_or(rest.len(), |end| end + 2))
+            } else {
+                None
+            };
+            if let Some(skip) = skip {
+                note("removing comments");
+                rest = &rest[skip..];
+                continue;
+            }
+            if c == '"' {
+                in_string = true;
+            }
+        }
+        uncommented.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+
+    let mut repaired = String::with_capacity(uncommented.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    for (index, c) in uncommented.char_indices() {
+        if in_string {
+            match (escaped, c) {
+                (false, '\\') => escaped = true,
+                (false, '"') => in_string = false,
+                _ => escaped = false,
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if c == ',' {
+            let next = uncommented[index + 1..].trim_start().chars().next();
+            if matches!(next, Some('}' | ']')) {
+                note("removing trailing commas");
+                continue;
+            }
+        }
+        repaired.push(c);
+    }
+    (repaired, repairs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_ld() {
+        let package = crate::parse_html(concat!(
+            r#"<script type="application/ld+json">[{"@type": "WebSite"}, {"@type": ["Thing", "Place"]}]</script>"#,
+            r#"<script type="Application/LD+JSON; charset=utf-8">"#,
+            r#"{"@context": "https://schema.org", "@graph": [{"@type": "Person", "name": "A"},"#,
+            r#" {"@context": "https://example.com/", "@type": "Organization"}]}</script>"#,
+            r#"<script type="application/json">{"@type": "Ignored"}</script>"#,
+            r#"<script type="application/ld+json">{"@type": </script>"#,
+            r#"<script type="application/ld+json"> </script>"#,
+        ));
+        let json_ld = json_ld(&package);
+
+        assert_eq!(
+            json_ld
+                .items
+                .iter()
+                .map(JsonLdItem::types)
+                .collect::<Vec<_>>(),
+            vec![
+                vec!["WebSite"],
+                vec!["Thing", "Place"],
+                vec!["Person"],
+                vec!["Organization"]
+            ]
+        );
+        assert_eq!(json_ld.items[2].value["@context"], "https://schema.org");
+        assert_eq!(json_ld.items[2].value["name"], "A");
+        assert_eq!(json_ld.items[3].value["@context"], "https://example.com/");
+        assert_ne!(json_ld.items[1].element, json_ld.items[2].element);
+
+        let kinds: Vec<_> = json_ld
+            .diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![JsonLdDiagnosticKind::Invalid, JsonLdDiagnosticKind::Invalid]
+        );
+    }
+
+    #[test]
+    fn test_repair() {
+        let (repaired, repairs) = repair(concat!(
+            "<!-- //<![CDATA[\n",
+            "{\"a\": [1, 2, ], /* note */ \"b\": \"x,}\n// y\", \"c\": \"\\\"\",\n}\n",
+            "//]]> -->",
+        ));
+        assert_eq!(
+            serde_json::from_str::<JsonValue>(&repaired).unwrap(),
+            serde_json::json!({"a": [1, 2], "b": "x,}\n// y", "c": "\""})
+        );
+        assert_eq!(
+            repairs,
+            vec![
+                "removing comments",
+                "escaping line breaks in strings",
+                "removing trailing commas"
+            ]
+        );
+    }
+}
@@ -1,9 +1,37 @@
+mod article;
+mod audit;
+mod convert;
+#[cfg(feature = "csp")]
+mod csp;
+mod css;
+mod document;
+mod edit;
 mod error;
+mod extract;
+mod form;
+mod from_html;
+mod functions;
 mod handle;
+mod json_ld;
+mod links;
+mod markdown;
+mod metadata;
+mod microdata;
+mod microformats;
+mod query;
+mod rdfa;
+mod render;
+mod sanitize;
+mod serialize;
+mod spec;
+mod string_functions;
+mod table;
+mod text;
 mod util;
 
 use std::{
     cell::{Cell, RefCell},
+    collections::HashMap,
     convert::TryFrom,
 };
 
@@ -11,8 +39,52 @@ use html5ever::tendril::TendrilSink;
 use html5ever::tree_builder::TreeBuilderOpts;
 use html5ever::{driver::ParseOpts, ExpandedName};
 
+pub use article::{extract_article, Article};
+pub use audit::{audit, AuditFinding, AuditKind, Severity};
+pub use convert::{ConversionError, FromXPathValue};
+#[cfg(feature = "csp")]
+pub use csp::{
+    analyze_csp, check_integrity, CspReport, HashAlgorithm, InlineHash, InlineKind, IntegrityCheck,
+    IntegrityStatus,
+};
+pub use css::{css_to_xpath, SelectorError};
+pub use document::{Doctype, HtmlDocument, QuirksMode};
+pub use edit::{insert_adjacent_html, set_inner_html, AdjacentPosition, InsertHtmlError};
 pub use error::Error;
+pub use form::{
+    encode_multipart, encode_urlencoded, Form, FormControl, FormEntry, FormOption, FormSubmission,
+};
+#[doc(hidden)]
+pub use from_html::__private;
+pub use from_html::{ExtractError, ExtractErrorKind, FromHtml, FromHtmlNode};
+pub use functions::{html_context, register_html_functions, register_namespaces};
 pub(crate) use handle::Handle;
+pub use json_ld::{json_ld, JsonLd, JsonLdDiagnostic, JsonLdDiagnosticKind, JsonLdItem};
+pub use links::{base_url, links, Link};
+pub use markdown::{to_markdown, MarkdownOptions};
+pub use metadata::{AlternateLink, Feed, Icon, MetaProperty, MetaTag, PageMetadata, Refresh};
+pub use microdata::microdata;
+pub use microformats::microformats;
+pub use query::{
+    absolute_xpath, evaluate, query_as, select, select_attr, select_css, select_number, select_one,
+    select_text, Query, QueryError, MAX_CACHED_EXPRESSIONS,
+};
+pub use rdfa::{rdfa, RdfaObject, RdfaTriple};
+pub use render::render_text;
+pub use sanitize::{sanitize, sanitize_html, Removal, SanitizePolicy, SanitizeReport};
+pub use serialize::{inner_html, to_html};
+pub use spec::{ExtractionSpec, SpecError};
+pub use string_functions::register_string_functions;
+#[cfg(feature = "derive")]
+pub use sxd_html_derive::FromHtml;
+pub use table::{Table, TableCell};
+pub use text::{
+    rendered_text, rendered_text_with, ruby_annotations, RubyAnnotation, RubyText, TextOptions,
+};
+pub use util::{
+    deep_clone_child_of_element, deep_clone_comment, deep_clone_element,
+    deep_clone_processing_instruction, deep_clone_text, import_node,
+};
 
 use html5ever::{
     tendril::Tendril,
@@ -20,9 +92,10 @@ use html5ever::{
     LocalName, Namespace, QualName,
 };
 use sxd_document::{
-    dom::{ChildOfElement, Document, ParentOfChild},
+    dom::{ChildOfElement, Document, Element, ParentOfChild},
     Package,
 };
+use util::ElementKey;
 
 /// Maximum number of parse errors recorded per parse call.
 ///
@@ -38,10 +111,26 @@ struct DocHtmlSink<'d> {
     document_handle: Handle<'d>,
     errors: RefCell<Vec<Error>>,
     current_line: Cell<u64>,
+    doctype: RefCell<Option<Doctype>>,
+    quirks_mode: Cell<QuirksMode>,
+    /// Whether the controls the parser associates with a form are recorded,
+    /// which only [`HtmlDocument`] makes use of.
+    record_form_associations: bool,
+    form_associations: RefCell<Vec<(Element<'d>, Element<'d>)>>,
+}
+
+/// Everything html5ever reports about a parse besides the tree itself.
+#[derive(Debug)]
+struct ParseOutput {
+    errors: Vec<Error>,
+    doctype: Option<Doctype>,
+    quirks_mode: QuirksMode,
+    /// The forms the parser associated controls with, by control.
+    form_associations: HashMap<ElementKey, ElementKey>,
 }
 
 impl<'d> DocHtmlSink<'d> {
-    fn new(document: Document<'d>) -> Self {
+    fn new(document: Document<'d>, record_form_associations: bool) -> Self {
         let document_handle = Handle::Document(document.root());
 
         Self {
@@ -49,6 +138,19 @@ impl<'d> DocHtmlSink<'d> {
             document_handle,
             errors: Default::default(),
             current_line: Cell::new(0),
+            doctype: Default::default(),
+            quirks_mode: Default::default(),
+            record_form_associations,
+            form_associations: Default::default(),
+        }
+    }
+
+    /// Creates a sink that builds a fragment inside `container` instead of
+    /// appending it to the document root.
+    fn for_fragment(container: Element<'d>) -> Self {
+        Self {
+            document_handle: Handle::from(container),
+            ..Self::new(container.document(), false)
         }
     }
 }
@@ -59,14 +161,28 @@ impl<'d> TreeSink for DocHtmlSink<'d> {
     where
         Self: 'a;
     type Handle = Handle<'d>;
-    type Output = Vec<Error>;
+    type Output = ParseOutput;
 
     fn set_current_line(&self, line_number: u64) {
         self.current_line.set(line_number);
     }
 
     fn finish(self) -> Self::Output {
-        self.errors.into_inner()
+        // Elements cannot be kept beyond the borrow of the package, so the
+        // associations are recorded by element identity.
+        let form_associations = self
+            .form_associations
+            .into_inner()
+            .into_iter()
+            .map(|(control, form)| (ElementKey::new(control), ElementKey::new(form)))
+            .collect();
+
+        ParseOutput {
+            errors: self.errors.into_inner(),
+            doctype: self.doctype.into_inner(),
+            quirks_mode: self.quirks_mode.get(),
+            form_associations,
+        }
     }
 
     fn parse_error(&self, msg: std::borrow::Cow<'static, str>) {
@@ -188,11 +304,14 @@ impl<'d> TreeSink for DocHtmlSink<'d> {
 
     fn append_doctype_to_document(
         &self,
-        _name: html5ever::tendril::StrTendril,
-        _public_id: html5ever::tendril::StrTendril,
-        _system_id: html5ever::tendril::StrTendril,
+        name: html5ever::tendril::StrTendril,
+        public_id: html5ever::tendril::StrTendril,
+        system_id: html5ever::tendril::StrTendril,
     ) {
-        // sxd_document has no DOCTYPE node type, so DOCTYPE declarations are always silently dropped.
+        // sxd_document has no DOCTYPE node type, so the declaration is recorded
+        // beside the tree instead of being appended to it.
+        let doctype = Doctype::new(name.as_ref(), public_id.as_ref(), system_id.as_ref());
+        self.doctype.replace(Some(doctype));
     }
 
     fn get_template_contents(&self, target: &Self::Handle) -> Self::Handle {
@@ -207,8 +326,8 @@ impl<'d> TreeSink for DocHtmlSink<'d> {
         x == y
     }
 
-    fn set_quirks_mode(&self, _mode: html5ever::tree_builder::QuirksMode) {
-        // ignored
+    fn set_quirks_mode(&self, mode: html5ever::tree_builder::QuirksMode) {
+        self.quirks_mode.set(QuirksMode::from_html5ever(mode));
     }
 
     fn append_before_sibling(&self, sibling: &Self::Handle, new_node: NodeOrText<Self::Handle>) {
@@ -258,6 +377,20 @@ impl<'d> TreeSink for DocHtmlSink<'d> {
         }
     }
 
+    fn associate_with_form(
+        &self,
+        target: &Self::Handle,
+        form: &Self::Handle,
+        _nodes: (&Self::Handle, Option<&Self::Handle>),
+    ) {
+        if !self.record_form_associations {
+            return;
+        }
+        if let (Handle::Element(control, _, _), Handle::Element(form, _, _)) = (target, form) {
+            self.form_associations.borrow_mut().push((*control, *form));
+        }
+    }
+
     fn remove_from_parent(&self, target: &Self::Handle) {
         target.remove_from_parent();
     }
@@ -280,9 +413,9 @@ impl<'d> TreeSink for DocHtmlSink<'d> {
 ///
 /// # Note
 ///
-/// DOCTYPE declarations are silently dropped. `sxd_document` has no DOCTYPE node type,
-/// and parsing is configured with `drop_doctype: true`. A `<!DOCTYPE html>` declaration
-/// in the input will not produce any node in the resulting tree.
+/// DOCTYPE declarations are not added to the tree. `sxd_document` has no DOCTYPE node
+/// type, so a `<!DOCTYPE html>` declaration in the input will not produce any node in
+/// the resulting tree. Use [`HtmlDocument`] to inspect the declaration.
 pub fn parse_html(contents: &str) -> Package {
     parse_html_with_errors(contents).0
 }
@@ -310,26 +443,34 @@ pub fn parse_html_fragment(contents: &str) -> Package {
 ///
 /// # Note
 ///
-/// DOCTYPE declarations are silently dropped. `sxd_document` has no DOCTYPE node type,
-/// and parsing is configured with `drop_doctype: true`. A `<!DOCTYPE html>` declaration
-/// in the input will not produce any node in the resulting tree.
+/// DOCTYPE declarations are not added to the tree. `sxd_document` has no DOCTYPE node
+/// type, so a `<!DOCTYPE html>` declaration in the input will not produce any node in
+/// the resulting tree. Use [`HtmlDocument`] to inspect the declaration.
 pub fn parse_html_with_errors(contents: &str) -> (Package, Vec<Error>) {
+    let (package, output) = parse_document(contents, false);
+    (package, output.errors)
+}
+
+/// Parses a complete HTML document, recording the controls the parser
+/// associates with a form if `record_form_associations` is set.
+fn parse_document(contents: &str, record_form_associations: bool) -> (Package, ParseOutput) {
     let package = Package::new();
     let document = package.as_document();
-    let sink = DocHtmlSink::new(document);
+    let sink = DocHtmlSink::new(document, record_form_associations);
 
     let opts = ParseOpts {
         tree_builder: TreeBuilderOpts {
-            drop_doctype: true,
+            // The sink records the DOCTYPE without adding it to the tree.
+            drop_doctype: false,
             exact_errors: true,
             ..Default::default()
         },
         ..Default::default()
     };
     let parser = html5ever::parse_document(sink, opts);
-    let errors = parser.one(contents);
+    let output = parser.one(contents);
 
-    (package, errors)
+    (package, output)
 }
 
 /// Parses an HTML fragment and returns html5ever parse errors.
@@ -346,7 +487,7 @@ pub fn parse_html_with_errors(contents: &str) -> (Package, Vec<Error>) {
 pub fn parse_html_fragment_with_errors(contents: &str) -> (Package, Vec<Error>) {
     let package = Package::new();
     let document = package.as_document();
-    let sink = DocHtmlSink::new(document);
+    let sink = DocHtmlSink::new(document, false);
 
     let opts = ParseOpts {
         tree_builder: TreeBuilderOpts {
@@ -363,9 +504,60 @@ pub fn parse_html_fragment_with_errors(contents: &str) -> (Package, Vec<Error>)
         Default::default(),
         false,
     );
-    let errors = parser.one(contents);
+    let output = parser.one(contents);
+
+    (package, output.errors)
+}
+
+/// Parses `contents` with `context` as the fragment parsing context and
+/// returns the resulting nodes, created in the document of `context` but not
+/// attached to it.
+pub(crate) fn parse_html_fragment_in<'d>(
+    context: Element<'d>,
+    contents: &str,
+) -> Vec<ChildOfElement<'d>> {
+    // html5ever appends an `html` root to the document handle, so the
+    // fragment is built inside a detached container rather than replacing
+    // the document element.
+    let container = context.document().create_element("html");
+    let sink = DocHtmlSink::for_fragment(container);
 
-    (package, errors)
+    let opts = ParseOpts {
+        tree_builder: TreeBuilderOpts {
+            drop_doctype: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let name = context.name();
+    let context_name = QualName::new(
+        None,
+        Namespace::from(name.namespace_uri().unwrap_or(util::HTML_NAMESPACE)),
+        LocalName::from(name.local_part()),
+    );
+    let context_attrs = context
+        .attributes()
+        .iter()
+        .map(|attribute| html5ever::Attribute {
+            name: QualName::new(
+                None,
+                Namespace::from(attribute.name().namespace_uri().unwrap_or_default()),
+                LocalName::from(attribute.name().local_part()),
+            ),
+            value: Tendril::from(attribute.value()),
+        })
+        .collect();
+    let parser = html5ever::parse_fragment(sink, opts, context_name, context_attrs, false);
+    parser.one(contents);
+
+    let mut nodes = vec![];
+    for root in container.children() {
+        if let ChildOfElement::Element(root) = root {
+            nodes.extend(root.children());
+            root.clear_children();
+        }
+    }
+    nodes
 }
 
 #[cfg(test)]
@@ -414,8 +606,8 @@ mod tests {
 
     #[test]
     fn test_doctype_is_dropped() {
-        // DOCTYPE declarations are silently dropped: sxd_document has no DOCTYPE node
-        // type and parsing is configured with drop_doctype: true.
+        // DOCTYPE declarations are not added to the tree: sxd_document has no DOCTYPE
+        // node type.
         let (package, errors) =
             parse_html_with_errors("<!DOCTYPE html><html><head></head><body></body></html>");
         assert_eq!(errors.len(), 0);
@@ -0,0 +1,274 @@
+use sxd_document::{
+    dom::{Element, ParentOfChild},
+    Package, QName,
+};
+use url::Url;
+
+use crate::util;
+
+const SVG_NAMESPACE: &str = "http://www.w3.org/2000/svg";
+const XLINK_NAMESPACE: &str = "http://www.w3.org/1999/xlink";
+
+/// The attributes of HTML elements holding a URL. `srcset` holds several.
+const URL_ATTRIBUTES: &[(&str, &str)] = &[
+    ("a", "href"),
+    ("area", "href"),
+    ("link", "href"),
+    ("base", "href"),
+    ("img", "src"),
+    ("img", "srcset"),
+    ("source", "src"),
+    ("source", "srcset"),
+    ("script", "src"),
+    ("iframe", "src"),
+    ("frame", "src"),
+    ("embed", "src"),
+    ("audio", "src"),
+    ("video", "src"),
+    ("video", "poster"),
+    ("track", "src"),
+    ("input", "src"),
+    ("input", "formaction"),
+    ("button", "formaction"),
+    ("form", "action"),
+    ("blockquote", "cite"),
+    ("q", "cite"),
+    ("del", "cite"),
+    ("ins", "cite"),
+    ("object", "data"),
+];
+
+/// A URL referenced by an attribute, returned by [`links`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Link<'d> {
+    /// The element holding the attribute.
+    pub element: Element<'d>,
+    /// The name of the attribute, such as `href`, `srcset` or `xlink:href`.
+    pub attribute: String,
+    /// The URL as written, without surrounding whitespace. For `srcset`,
+    /// the URL of one image candidate.
+    pub value: String,
+    /// The lowercase tokens of the `rel` attribute of the element.
+    pub rel: Vec<String>,
+    /// The absolute URL, or `None` if the value is not a valid URL.
+    pub url: Option<Url>,
+}
+
+/// Returns the base URL of the document in `package`: the `href` of its
+/// first `base` element resolved against `document_url`, or
+/// `document_url` if there is none.
+pub fn base_url(package: &Package, document_url: &Url) -> Url {
+    let root = package.as_document().root();
+    util::descendant_elements(ParentOfChild::Root(root))
+        .into_iter()
+        .find(|element| {
+            util::html_local_name(element) == Some("base") && element.attribute("href").is_some()
+        })
+        .and_then(|base| document_url.join(base.attribute_value("href")?.trim()).ok())
+        .unwrap_or_else(|| document_url.clone())
+}
+
+/// Returns every URL referenced by the elements in `package`, in document
+/// order, resolved against the [`base_url`] of the document at
+/// `document_url`.
+///
+/// The URLs are those of `href`, `src`, `srcset`, `poster`, `action`,
+/// `formaction`, `cite` and `data` attributes of the HTML elements they
+/// apply to, and the `href` and `xlink:href` attributes of SVG elements.
+/// The `href` of a `base` element is resolved against `document_url`
+/// itself.
+///
+/// ```rust
+/// use url::Url;
+///
+/// let package = sxd_html::parse_html(
+///     r#"<base href="/docs/"><a href="intro" rel="next">Intro</a>"#,
+/// );
+/// let links = sxd_html::links(&package, &Url::parse("https://example.com/").unwrap());
+/// let link = &links[1];
+/// assert_eq!(link.rel, vec!["next"]);
+/// assert_eq!(link.url.as_ref().unwrap().as_str(), "https://example.com/docs/intro");
+/// ```
+pub fn links<'d>(package: &'d Package, document_url: &Url) -> Vec<Link<'d>> {
+    let base_url = base_url(package, document_url);
+    let root = package.as_document().root();
+
+    let mut links = vec![];
+    for element in util::descendant_elements(ParentOfChild::Root(root)) {
+        let rel: Vec<_> = element
+            .attribute_value("rel")
+            .unwrap_or("")
+            .split_ascii_whitespace()
+            .map(str::to_ascii_lowercase)
+            .collect();
+        let mut push = |attribute: &str, value: &str, base_url: &Url| {
+            let value = value.trim_matches(|c: char| c.is_ascii_whitespace());
+            links.push(Link {
+                element,
+                attribute: attribute.to_string(),
+                value: value.to_string(),
+                rel: rel.clone(),
+                url: base_url.join(value).ok(),
+            });
+        };
+
+        let name = element.name();
+        if name.namespace_uri() == Some(SVG_NAMESPACE) {
+            if let Some(value) = element.attribute_value("href") {
+                push("href", value, &base_url);
+            }
+            let xlink_href = QName::with_namespace_uri(Some(XLINK_NAMESPACE), "href");
+            if let Some(value) = element.attribute_value(xlink_href) {
+                push("xlink:href", value, &base_url);
+            }
+            continue;
+        }
+
+        let name = match util::html_local_name(&element) {
+            Some(name) => name,
+            None => continue,
+        };
+        for &(_, attribute) in URL_ATTRIBUTES.iter().filter(|(n, _)| *n == name) {
+            let value = match element.attribute_value(attribute) {
+                Some(value) => value,
+                None => continue,
+            };
+            match (name, attribute) {
+                ("base", _) => push(attribute, value, document_url),
+                (_, "srcset") => {
+                    for candidate in srcset_urls(value) {
+                        push(attribute, candidate, &base_url);
+                    }
+                }
+                _ => push(attribute, value, &base_url),
+            }
+        }
+    }
+    links
+}
+
+/// Returns the URLs of the image candidates of a `srcset` attribute.
+pub(crate) fn srcset_urls(srcset: &str) -> Vec<&str> {
+    let mut urls = vec![];
+    let mut rest = srcset;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == ',');
+        if rest.is_empty() {
+            return urls;
+        }
+
+        let end = rest
+            .find(|c: char| c.is_ascii_whitespace())
+            .unwrap_or(rest.len());
+        let (url, after) = rest.split_at(end);
+        // A URL ending with commas ends the candidate; otherwise
+        // descriptors follow, up to a comma outside of parentheses.
+        let trimmed = url.trim_end_matches(',');
+        urls.push(trimmed);
+        rest = after;
+        if trimmed.len() == url.len() {
+            let mut depth = 0usize;
+            let end = rest
+                .char_indices()
+                .find(|&(_, c)| match c {
+                    '(' => {
+                        depth += 1;
+                        false
+                    }
+                    ')' => {
+                        depth = depth.saturating_sub(1);
+                        false
+                    }
+                    ',' => depth == 0,
+                    _ => false,
+                })
+                .map_or(rest.len(), |(index, _)| index);
+            rest = &rest[end..];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(links: &[Link<'_>]) -> Vec<(String, String, Option<String>)> {
+        links
+            .iter()
+            .map(|link| {
+                (
+                    link.element.name().local_part().to_string(),
+                    link.attribute.clone(),
+                    link.url.as_ref().map(Url::to_string),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_links() {
+        let package = crate::parse_html(concat!(
+            r#"<head><base href="../base/"><link rel="Stylesheet alternate" href="a.css"></head>"#,
+            r#"<body><img src=" i.png " srcset="i-1x.png 1x, data:image/png;base64,AA== 2x,i,3.png 3x">"#,
+            r#"<video src="v.mp4" poster="p.jpg"></video><form action="/post"><button formaction="?b">"#,
+            r#"</form><q cite="https://quote.example/"></q><object data="o.swf"></object>"#,
+            r#"<a href="http://[::1">broken</a>"#,
+            r##"<svg><a xlink:href="#x"><use href="s.svg#y"/></a></svg></body>"##,
+        ));
+        let document_url = Url::parse("https://example.com/dir/page.html").unwrap();
+        let links = links(&package, &document_url);
+
+        let expected = [
+            ("base", "href", "https://example.com/base/"),
+            ("link", "href", "https://example.com/base/a.css"),
+            ("img", "src", "https://example.com/base/i.png"),
+            ("img", "srcset", "https://example.com/base/i-1x.png"),
+            ("img", "srcset", "data:image/png;base64,AA=="),
+            ("img", "srcset", "https://example.com/base/i,3.png"),
+            ("video", "src", "https://example.com/base/v.mp4"),
+            ("video", "poster", "https://example.com/base/p.jpg"),
+            ("form", "action", "https://example.com/post"),
+            ("button", "formaction", "https://example.com/base/?b"),
+            ("q", "cite", "https://quote.example/"),
+            ("object", "data", "https://example.com/base/o.swf"),
+        ];
+        let mut expected: Vec<_> = expected
+            .iter()
+            .map(|(element, attribute, url)| {
+                (
+                    element.to_string(),
+                    attribute.to_string(),
+                    Some(url.to_string()),
+                )
+            })
+            .collect();
+        expected.push(("a".to_string(), "href".to_string(), None));
+        expected.push((
+            "a".to_string(),
+            "xlink:href".to_string(),
+            Some("https://example.com/base/#x".to_string()),
+        ));
+        expected.push((
+            "use".to_string(),
+            "href".to_string(),
+            Some("https://example.com/base/s.svg#y".to_string()),
+        ));
+        assert_eq!(summary(&links), expected);
+
+        assert_eq!(links[1].rel, vec!["stylesheet", "alternate"]);
+        assert_eq!(links[2].value, "i.png");
+    }
+
+    #[test]
+    fn test_base_url() {
+        let document_url = Url::parse("https://example.com/dir/").unwrap();
+        let package = crate::parse_html(r#"<base target="_top"><base href="http://[::1">"#);
+        assert_eq!(base_url(&package, &document_url), document_url);
+
+        let package = crate::parse_html(r#"<base href="https://cdn.example/"><p>"#);
+        assert_eq!(
+            base_url(&package, &document_url).as_str(),
+            "https://cdn.example/"
+        );
+    }
+}
@@ -0,0 +1,633 @@
+use sxd_document::dom::{ChildOfElement, Element};
+use sxd_xpath::nodeset::Node;
+use url::Url;
+
+use crate::{text, util};
+
+/// Options for [`to_markdown`].
+#[derive(Debug, Clone, Default)]
+pub struct MarkdownOptions {
+    /// Base URL that relative `href` and `src` attributes are resolved
+    /// against. When `None`, URLs are emitted as written in the document.
+    pub base_url: Option<Url>,
+}
+
+/// Converts `node` and its descendants to CommonMark with GitHub Flavored
+/// Markdown extensions.
+///
+/// Headings, emphasis, links, images, nested lists, code blocks,
+/// blockquotes, horizontal rules and tables are converted to their Markdown
+/// equivalents. The language of a code block is taken from a
+/// `language-*` or `lang-*` class on the `<pre>` or its `<code>` child.
+/// Markup without a Markdown equivalent falls back to its text content, and
+/// content that is not rendered (see [`rendered_text`](crate::rendered_text))
+/// is dropped.
+///
+/// Pass `package.as_document().root()` to convert a whole document.
+pub fn to_markdown<'d>(node: impl Into<Node<'d>>, options: &MarkdownOptions) -> String {
+    let converter = Converter { options };
+
+    let blocks = match node.into() {
+        Node::Root(root) => {
+            let children: Vec<ChildOfElement> = root
+                .children()
+                .into_iter()
+                .filter_map(|child| child.element())
+                .map(ChildOfElement::Element)
+                .collect();
+            converter.blocks(&children)
+        }
+        Node::Element(element) => converter.blocks(&[ChildOfElement::Element(element)]),
+        Node::Text(text) => converter.blocks(&[ChildOfElement::Text(text)]),
+        Node::Attribute(attribute) => vec![escape_text(attribute.value())],
+        Node::Comment(_) | Node::Namespace(_) | Node::ProcessingInstruction(_) => vec![],
+    };
+
+    let mut markdown = blocks.join("\n\n");
+    if !markdown.is_empty() {
+        markdown.push('\n');
+    }
+    markdown
+}
+
+struct Converter<'o> {
+    options: &'o MarkdownOptions,
+}
+
+impl Converter<'_> {
+    /// Converts a sequence of sibling nodes to Markdown blocks, grouping
+    /// runs of inline content into paragraphs.
+    fn blocks(&self, children: &[ChildOfElement]) -> Vec<String> {
+        let mut blocks = vec![];
+        let mut inline = String::new();
+
+        for child in children {
+            match child {
+                ChildOfElement::Element(element) if is_block(element) => {
+                    push_paragraph(&mut blocks, &mut inline);
+                    blocks.extend(self.block(*element));
+                }
+                child => self.inline(&mut inline, *child),
+            }
+        }
+        push_paragraph(&mut blocks, &mut inline);
+
+        blocks
+    }
+
+    fn block(&self, element: Element) -> Vec<String> {
+        if text::is_skipped(&element) {
+            return vec![];
+        }
+
+        let name = util::html_local_name(&element).unwrap_or_default();
+        let block = match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = name[1..].parse().unwrap_or(1);
+                let content = self.single_line_content(element);
+                if content.is_empty() {
+                    return vec![];
+                }
+                format!("{} {}", "#".repeat(level), content)
+            }
+            "p" => {
+                let content = self.inline_content(element);
+                if content.is_empty() {
+                    return vec![];
+                }
+                escape_block_start(&content)
+            }
+            "hr" => "---".to_string(),
+            "pre" => code_block(element),
+            "ul" | "ol" | "menu" | "dir" => self.list(element, name == "ol"),
+            "blockquote" => {
+                let content = self.blocks(&element.children()).join("\n\n");
+                if content.is_empty() {
+                    return vec![];
+                }
+                prefix_lines(&content, "> ", ">")
+            }
+            "table" => self.table(element),
+            _ => return self.blocks(&element.children()),
+        };
+
+        if block.is_empty() {
+            vec![]
+        } else {
+            vec![block]
+        }
+    }
+
+    fn list(&self, element: Element, ordered: bool) -> String {
+        let mut number: i64 = element
+            .attribute_value("start")
+            .and_then(|start| start.trim().parse().ok())
+            .unwrap_or(1);
+        let mut items = vec![];
+
+        for child in element.children() {
+            let item = match child.element() {
+                Some(item) if util::html_local_name(&item) == Some("li") => item,
+                _ => continue,
+            };
+            if text::is_skipped(&item) {
+                continue;
+            }
+
+            let marker = if ordered {
+                format!("{}. ", number)
+            } else {
+                "- ".to_string()
+            };
+            number += 1;
+
+            let blocks = self.blocks(&item.children());
+            let mut content = String::new();
+            for (index, block) in blocks.iter().enumerate() {
+                if index > 0 {
+                    // Keep nested lists tight so the outer list stays tight as well.
+                    content.push_str(if is_list_block(block) { "\n" } else { "\n\n" });
+                }
+                content.push_str(block);
+            }
+
+            let indent = " ".repeat(marker.len());
+            let indented = prefix_lines(&content, &indent, "");
+            let body = indented.strip_prefix(&indent).unwrap_or(&indented);
+            items.push(format!("{}{}", marker, body).trim_end().to_string());
+        }
+
+        items.join("\n")
+    }
+
+    fn table(&self, element: Element) -> String {
+        let mut rows: Vec<Vec<String>> = vec![];
+        let mut header_row = None;
+
+        for child in element.children() {
+            let section = match child.element() {
+                Some(section) => section,
+                None => continue,
+            };
+            match util::html_local_name(&section) {
+                Some("tr") => rows.push(self.table_row(section)),
+                Some(section_name @ ("thead" | "tbody" | "tfoot")) => {
+                    for row in section.children().iter().filter_map(|row| row.element()) {
+                        if util::html_local_name(&row) == Some("tr") {
+                            if section_name == "thead" && header_row.is_none() {
+                                header_row = Some(rows.len());
+                            }
+                            rows.push(self.table_row(row));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        if columns == 0 {
+            return String::new();
+        }
+
+        // GFM tables require exactly one header row. It is the first row of
+        // the <thead>, or the first row without one, and any further header
+        // rows are kept as body rows.
+        if let Some(header_row) = header_row {
+            let header = rows.remove(header_row);
+            rows.insert(0, header);
+        }
+        let mut lines = vec![];
+        for (index, row) in rows.iter().enumerate() {
+            let mut cells = row.clone();
+            cells.resize(columns, String::new());
+            lines.push(format!("| {} |", cells.join(" | ")));
+            if index == 0 {
+                lines.push(format!("|{}", " --- |".repeat(columns)));
+            }
+        }
+
+        lines.join("\n")
+    }
+
+    fn table_row(&self, row: Element) -> Vec<String> {
+        row.children()
+            .iter()
+            .filter_map(|cell| cell.element())
+            .filter(|cell| matches!(util::html_local_name(cell), Some("td" | "th")))
+            .map(|cell| self.single_line_content(cell).replace('|', "\\|"))
+            .collect()
+    }
+
+    /// Converts the children of `element` to inline Markdown on one line,
+    /// for headings and table cells, turning line breaks into spaces.
+    fn single_line_content(&self, element: Element) -> String {
+        let content = self.inline_content(element).replace("\\\n", "\n");
+        normalize_inline(&content.replace('\n', " "))
+    }
+
+    /// Converts the children of `element` to a single line-wrapped run of
+    /// inline Markdown.
+    fn inline_content(&self, element: Element) -> String {
+        let mut inline = String::new();
+        for child in element.children() {
+            self.inline(&mut inline, child);
+        }
+        normalize_inline(&inline)
+    }
+
+    fn inline(&self, output: &mut String, node: ChildOfElement) {
+        let element = match node {
+            ChildOfElement::Text(text) => {
+                output.push_str(&escape_text(&collapse_whitespace(text.text())));
+                return;
+            }
+            ChildOfElement::Element(element) => element,
+            ChildOfElement::Comment(_) | ChildOfElement::ProcessingInstruction(_) => return,
+        };
+        if text::is_skipped(&element) {
+            return;
+        }
+
+        match util::html_local_name(&element).unwrap_or_default() {
+            "br" => output.push_str("\\\n"),
+            "strong" | "b" => self.wrap(output, element, "**"),
+            "em" | "i" | "cite" | "dfn" => self.wrap(output, element, "*"),
+            "del" | "s" | "strike" => self.wrap(output, element, "~~"),
+            "code" | "kbd" | "samp" | "tt" => {
+                output.push_str(&inline_code(&collapse_whitespace(&string_value(element))))
+            }
+            "a" => self.link(output, element),
+            "img" => self.image(output, element),
+            _ => {
+                for child in element.children() {
+                    self.inline(output, child);
+                }
+            }
+        }
+    }
+
+    fn wrap(&self, output: &mut String, element: Element, marker: &str) {
+        let mut content = String::new();
+        for child in element.children() {
+            self.inline(&mut content, child);
+        }
+
+        // Emphasis markers must touch the emphasized text, so surrounding
+        // whitespace is moved outside of them.
+        let trimmed = content.trim();
+        if trimmed.is_empty() {
+            output.push_str(&content);
+            return;
+        }
+        if content.starts_with(char::is_whitespace) {
+            output.push(' ');
+        }
+        output.push_str(marker);
+        output.push_str(trimmed);
+        output.push_str(marker);
+        if content.ends_with(char::is_whitespace) {
+            output.push(' ');
+        }
+    }
+
+    fn link(&self, output: &mut String, element: Element) {
+        let mut content = String::new();
+        for child in element.children() {
+            self.inline(&mut content, child);
+        }
+        let content = normalize_inline(&content);
+
+        let href = match element.attribute_value("href") {
+            Some(href) if !href.trim().is_empty() => self.resolve(href),
+            _ => {
+                output.push_str(&content);
+                return;
+            }
+        };
+
+        if content.is_empty() && is_autolink(&href) {
+            output.push_str(&format!("<{}>", href));
+            return;
+        }
+        let content = match content.is_empty() {
+            true => escape_text(&href),
+            false => content,
+        };
+
+        output.push('[');
+        output.push_str(&content);
+        output.push_str("](");
+        output.push_str(&link_destination(&href));
+        if let Some(title) = element.attribute_value("title") {
+            output.push_str(&link_title(title));
+        }
+        output.push(')');
+    }
+
+    fn image(&self, output: &mut String, element: Element) {
+        let src = match element.attribute_value("src") {
+            Some(src) if !src.trim().is_empty() => self.resolve(src),
+            _ => return,
+        };
+        let alt = element.attribute_value("alt").unwrap_or_default();
+
+        output.push_str("![");
+        output.push_str(&escape_text(&collapse_whitespace(alt)));
+        output.push_str("](");
+        output.push_str(&link_destination(&src));
+        if let Some(title) = element.attribute_value("title") {
+            output.push_str(&link_title(title));
+        }
+        output.push(')');
+    }
+
+    fn resolve(&self, url: &str) -> String {
+        let url = url.trim();
+        match &self.options.base_url {
+            Some(base) => base
+                .join(url)
+                .map(String::from)
+                .unwrap_or_else(|_| url.to_string()),
+            None => url.to_string(),
+        }
+    }
+}
+
+fn is_block(element: &Element) -> bool {
+    util::html_local_name(element).is_some_and(|name| {
+        text::BLOCK_ELEMENTS.contains(&name) || matches!(name, "p" | "head" | "template")
+    })
+}
+
+fn is_list_block(block: &str) -> bool {
+    block.starts_with("- ")
+        || block.split_once(". ").is_some_and(|(number, _)| {
+            !number.is_empty() && number.chars().all(|c| c.is_ascii_digit())
+        })
+}
+
+fn push_paragraph(blocks: &mut Vec<String>, inline: &mut String) {
+    let paragraph = normalize_inline(inline);
+    if !paragraph.is_empty() {
+        blocks.push(escape_block_start(&paragraph));
+    }
+    inline.clear();
+}
+
+fn code_block(element: Element) -> String {
+    let code = element
+        .children()
+        .iter()
+        .filter_map(|child| child.element())
+        .find(|child| util::html_local_name(child) == Some("code"));
+
+    let language = [Some(element), code]
+        .iter()
+        .flatten()
+        .filter_map(|element| element.attribute_value("class"))
+        .flat_map(str::split_ascii_whitespace)
+        .find_map(|class| {
+            class
+                .strip_prefix("language-")
+                .or_else(|| class.strip_prefix("lang-"))
+        })
+        .unwrap_or_default();
+
+    let content = string_value(element);
+    let content = content.strip_prefix('\n').unwrap_or(&content);
+    let content = content.trim_end_matches('\n');
+    let fence = "`".repeat(longest_run(content, '`').max(2) + 1);
+
+    format!("{}{}\n{}\n{}", fence, language, content, fence)
+}
+
+fn inline_code(code: &str) -> String {
+    if code.is_empty() {
+        return String::new();
+    }
+    let fence = "`".repeat(longest_run(code, '`') + 1);
+    if code.starts_with('`') || code.ends_with('`') {
+        format!("{} {} {}", fence, code, fence)
+    } else {
+        format!("{}{}{}", fence, code, fence)
+    }
+}
+
+fn link_destination(url: &str) -> String {
+    if url.contains([' ', '(', ')', '<', '>']) {
+        format!("<{}>", url.replace('<', "%3C").replace('>', "%3E"))
+    } else {
+        url.to_string()
+    }
+}
+
+/// Returns whether `url` can be written as a CommonMark autolink: an
+/// absolute URL with a scheme and no spaces, controls, `<` or `>`.
+fn is_autolink(url: &str) -> bool {
+    let scheme = match url.split_once(':') {
+        Some((scheme, _)) => scheme,
+        None => return false,
+    };
+    (2..=32).contains(&scheme.len())
+        && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '.' | '-'))
+        && !url
+            .chars()
+            .any(|c| c.is_ascii_control() || matches!(c, ' ' | '<' | '>'))
+}
+
+fn link_title(title: &str) -> String {
+    format!(" \"{}\"", title.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn longest_run(text: &str, c: char) -> usize {
+    let mut longest = 0;
+    let mut current = 0;
+    for ch in text.chars() {
+        if ch == c {
+            current += 1;
+            longest = longest.max(current);
+        } else {
+            current = 0;
+        }
+    }
+    longest
+}
+
+fn string_value(element: Element) -> String {
+    Node::Element(element).string_value()
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    let mut collapsed = String::with_capacity(text.len());
+    let mut in_whitespace = false;
+    for c in text.chars() {
+        if c.is_ascii_whitespace() {
+            if !in_whitespace {
+                collapsed.push(' ');
+            }
+            in_whitespace = true;
+        } else {
+            collapsed.push(c);
+            in_whitespace = false;
+        }
+    }
+    collapsed
+}
+
+/// Collapses the spaces left between inline fragments and trims each line.
+fn normalize_inline(inline: &str) -> String {
+    let lines: Vec<String> = inline
+        .split('\n')
+        .map(|line| {
+            let mut normalized = String::with_capacity(line.len());
+            for word in line.split(' ').filter(|word| !word.is_empty()) {
+                if !normalized.is_empty() {
+                    normalized.push(' ');
+                }
+                normalized.push_str(word);
+            }
+            // Keep the backslash of a hard line break attached to the line end.
+            if line.ends_with('\\') && !normalized.ends_with('\\') {
+                normalized.push('\\');
+            }
+            normalized
+        })
+        .collect();
+
+    let joined = lines.join("\n");
+    joined
+        .trim_start_matches(['\n', '\\'])
+        .trim_end_matches(['\n', '\\'])
+        .to_string()
+}
+
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '_' | '`' | '[' | ']' | '<' | '>' | '~') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Escapes characters at the start of a paragraph that would otherwise be
+/// read as a heading, list item or thematic break.
+fn escape_block_start(paragraph: &str) -> String {
+    if paragraph.starts_with(['#', '-', '+', '=']) {
+        return format!("\\{}", paragraph);
+    }
+    let digits = paragraph.chars().take_while(char::is_ascii_digit).count();
+    if digits > 0 && paragraph[digits..].starts_with(['.', ')']) {
+        return format!("{}\\{}", &paragraph[..digits], &paragraph[digits..]);
+    }
+    paragraph.to_string()
+}
+
+fn prefix_lines(text: &str, prefix: &str, empty_prefix: &str) -> String {
+    text.split('\n')
+        .map(|line| {
+            if line.is_empty() {
+                empty_prefix.to_string()
+            } else {
+                format!("{}{}", prefix, line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn markdown(html: &str, options: &MarkdownOptions) -> String {
+        let package = crate::parse_html(html);
+        let root = package.as_document().root();
+        to_markdown(root, options)
+    }
+
+    #[test]
+    fn test_headings_and_inline() {
+        let html = r#"<h1>Title</h1><p>Some <strong>bold</strong>, <em>italic </em>and <code>a`b</code> text.</p>"#;
+        assert_eq!(
+            markdown(html, &Default::default()),
+            "# Title\n\nSome **bold**, *italic* and ``a`b`` text.\n"
+        );
+    }
+
+    #[test]
+    fn test_nested_lists() {
+        let html = "<ul><li>one<ul><li>nested</li></ul></li><li>two</li></ul><ol start=\"3\"><li>three</li></ol>";
+        assert_eq!(
+            markdown(html, &Default::default()),
+            "- one\n  - nested\n- two\n\n3. three\n"
+        );
+    }
+
+    #[test]
+    fn test_code_block_and_blockquote() {
+        let html = r#"<pre><code class="language-rust">fn main() {}
+</code></pre><blockquote><p>quoted</p><p>twice</p></blockquote>"#;
+        assert_eq!(
+            markdown(html, &Default::default()),
+            "```rust\nfn main() {}\n```\n\n> quoted\n>\n> twice\n"
+        );
+    }
+
+    #[test]
+    fn test_links_and_images_with_base_url() {
+        let html =
+            r#"<p><a href="/docs" title="Docs">read</a> <img src="logo.png" alt="logo"></p>"#;
+        let options = MarkdownOptions {
+            base_url: Some(Url::parse("https://example.com/a/").unwrap()),
+        };
+        assert_eq!(
+            markdown(html, &options),
+            "[read](https://example.com/docs \"Docs\") ![logo](https://example.com/a/logo.png)\n"
+        );
+        assert_eq!(
+            markdown(html, &Default::default()),
+            "[read](/docs \"Docs\") ![logo](logo.png)\n"
+        );
+
+        let html =
+            r#"<p><a href="https://example.com/"></a> <a href="/x"></a> <a href="a b_c"></a></p>"#;
+        assert_eq!(
+            markdown(html, &Default::default()),
+            "<https://example.com/> [/x](/x) [a b\\_c](<a b_c>)\n"
+        );
+    }
+
+    #[test]
+    fn test_table() {
+        let html = "<table><thead><tr><th>a</th><th>b</th></tr></thead><tbody><tr><td>1</td><td>x|y</td></tr><tr><td>2</td></tr></tbody></table>";
+        assert_eq!(
+            markdown(html, &Default::default()),
+            "| a | b |\n| --- | --- |\n| 1 | x\\|y |\n| 2 |  |\n"
+        );
+    }
+
+    #[test]
+    fn test_line_breaks_and_header_rows() {
+        let html = concat!(
+            "<h2>Line<br>break</h2>",
+            "<table><thead><tr><th>a<br>b</th><th>c</th></tr><tr><th>d</th><th>e</th></tr></thead>",
+            "<tbody><tr><td>1<br><br>2</td><td>3</td></tr></tbody></table>",
+            "<p>one<br>two</p>",
+        );
+        assert_eq!(
+            markdown(html, &Default::default()),
+            concat!(
+                "## Line break\n\n",
+                "| a b | c |\n| --- | --- |\n| d | e |\n| 1 2 | 3 |\n\n",
+                "one\\\ntwo\n",
+            )
+        );
+    }
+}
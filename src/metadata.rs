@@ -0,0 +1,503 @@
+use sxd_document::{
+    dom::{Element, ParentOfChild},
+    Package,
+};
+use sxd_xpath::nodeset::Node;
+use url::Url;
+
+use crate::util;
+
+/// The prefixes of OpenGraph properties: `og:` and the object types of the
+/// OpenGraph protocol.
+const OPEN_GRAPH_PREFIXES: &[&str] = &["og:", "article:", "book:", "profile:", "music:", "video:"];
+
+/// The `rel` values of icons.
+const ICON_RELS: &[&str] = &[
+    "icon",
+    "apple-touch-icon",
+    "apple-touch-icon-precomposed",
+    "mask-icon",
+];
+
+/// The `type` values of feed links.
+const FEED_TYPES: &[&str] = &[
+    "application/rss+xml",
+    "application/atom+xml",
+    "application/feed+json",
+];
+
+/// A `meta` element.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MetaTag {
+    /// The `name` attribute.
+    pub name: Option<String>,
+    /// The `property` attribute, used by OpenGraph and RDFa.
+    pub property: Option<String>,
+    /// The `http-equiv` attribute.
+    pub http_equiv: Option<String>,
+    /// The `content` attribute.
+    pub content: Option<String>,
+}
+
+/// An OpenGraph or Twitter card property.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MetaProperty {
+    /// The lowercase property, such as `og:title` or `twitter:card`.
+    pub property: String,
+    /// The value of the property.
+    pub content: String,
+}
+
+/// A `link` to an alternate version of the page, such as a translation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AlternateLink {
+    /// The URL of the alternate version.
+    pub href: String,
+    /// The `hreflang` attribute, `x-default` for the fallback version.
+    pub hreflang: Option<String>,
+    /// The `media` attribute.
+    pub media: Option<String>,
+    /// The `type` attribute.
+    pub media_type: Option<String>,
+}
+
+/// An icon of the page.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Icon {
+    /// The URL of the icon.
+    pub href: String,
+    /// The icon `rel` value, such as `icon` or `apple-touch-icon`.
+    pub rel: String,
+    /// The `sizes` attribute, such as `32x32` or `any`.
+    pub sizes: Option<String>,
+    /// The `type` attribute.
+    pub media_type: Option<String>,
+}
+
+/// An RSS, Atom or JSON feed of the page.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Feed {
+    /// The URL of the feed.
+    pub href: String,
+    /// The type of the feed, such as `application/rss+xml`.
+    pub media_type: String,
+    /// The `title` attribute.
+    pub title: Option<String>,
+}
+
+/// A `<meta http-equiv="refresh">` directive.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Refresh {
+    /// The number of seconds to wait.
+    pub delay: u64,
+    /// The URL to navigate to, or `None` to reload the page.
+    pub url: Option<String>,
+}
+
+/// The metadata of a page, collected from its `title`, `meta` and `link`
+/// elements.
+///
+/// URLs are as written in the document when extracted with
+/// [`extract`](Self::extract), and resolved against the base URL of the
+/// document with [`extract_with_url`](Self::extract_with_url).
+///
+/// ```rust
+/// use sxd_html::PageMetadata;
+///
+/// let package = sxd_html::parse_html(concat!(
+///     r#"<title> Home </title><meta property="og:title" content="Welcome">"#,
+///     r#"<meta name="robots" content="noindex, follow">"#,
+/// ));
+/// let metadata = PageMetadata::extract(&package);
+/// assert_eq!(metadata.title.as_deref(), Some("Home"));
+/// assert_eq!(metadata.property("og:title"), Some("Welcome"));
+/// assert!(metadata.noindex());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PageMetadata {
+    /// The text of the first `title` element, with whitespace collapsed.
+    pub title: Option<String>,
+    /// The `lang` attribute of the `html` element.
+    pub language: Option<String>,
+    /// The `charset` attribute of the first `meta` element that has one.
+    pub charset: Option<String>,
+    /// The content of `<meta name="description">`.
+    pub description: Option<String>,
+    /// Every `meta` element with a `name`, `property` or `http-equiv`
+    /// attribute, in document order.
+    pub meta: Vec<MetaTag>,
+    /// The OpenGraph properties (`og:*`, `article:*` and the other
+    /// OpenGraph object types), in document order.
+    pub open_graph: Vec<MetaProperty>,
+    /// The Twitter card properties (`twitter:*`), in document order.
+    pub twitter: Vec<MetaProperty>,
+    /// The `href` of the first `<link rel="canonical">`.
+    pub canonical: Option<String>,
+    /// The `<link rel="alternate">` elements other than feeds.
+    pub alternates: Vec<AlternateLink>,
+    /// The icons of the page.
+    pub icons: Vec<Icon>,
+    /// The lowercase directives of `<meta name="robots">`, such as
+    /// `noindex` or `max-snippet:50`.
+    pub robots: Vec<String>,
+    /// The first `<meta http-equiv="refresh">` that could be parsed.
+    pub refresh: Option<Refresh>,
+    /// The RSS, Atom and JSON feeds linked with `<link rel="alternate">`.
+    pub feeds: Vec<Feed>,
+}
+
+impl PageMetadata {
+    /// Collects the metadata of the document in `package`.
+    pub fn extract(package: &Package) -> Self {
+        let root = package.as_document().root();
+        let mut metadata = Self::default();
+
+        for element in util::descendant_elements(ParentOfChild::Root(root)) {
+            match util::html_local_name(&element) {
+                Some("html") if metadata.language.is_none() => {
+                    metadata.language = attribute(element, "lang");
+                }
+                Some("title") if metadata.title.is_none() => {
+                    metadata.title =
+                        Some(collapse_whitespace(&Node::Element(element).string_value()));
+                }
+                Some("meta") => metadata.add_meta(element),
+                Some("link") => metadata.add_link(element),
+                _ => {}
+            }
+        }
+        metadata
+    }
+
+    /// Collects the metadata of the document in `package` located at
+    /// `document_url`, resolving the URLs of links and of the refresh
+    /// directive against the [`base_url`](crate::base_url) of the
+    /// document. URLs that cannot be resolved are left as written.
+    pub fn extract_with_url(package: &Package, document_url: &Url) -> Self {
+        let base_url = crate::base_url(package, document_url);
+        let resolve = |url: &mut String| {
+            if let Ok(resolved) = base_url.join(url) {
+                *url = resolved.into();
+            }
+        };
+
+        let mut metadata = Self::extract(package);
+        if let Some(canonical) = &mut metadata.canonical {
+            resolve(canonical);
+        }
+        for alternate in &mut metadata.alternates {
+            resolve(&mut alternate.href);
+        }
+        for icon in &mut metadata.icons {
+            resolve(&mut icon.href);
+        }
+        for feed in &mut metadata.feeds {
+            resolve(&mut feed.href);
+        }
+        if let Some(url) = metadata
+            .refresh
+            .as_mut()
+            .and_then(|refresh| refresh.url.as_mut())
+        {
+            resolve(url);
+        }
+        metadata
+    }
+
+    /// Returns the content of the first OpenGraph or Twitter card property
+    /// `property`, such as `og:image` or `twitter:card`.
+    pub fn property(&self, property: &str) -> Option<&str> {
+        self.open_graph
+            .iter()
+            .chain(&self.twitter)
+            .find(|candidate| candidate.property.eq_ignore_ascii_case(property))
+            .map(|candidate| candidate.content.as_str())
+    }
+
+    /// Returns whether the robots directives forbid indexing the page.
+    pub fn noindex(&self) -> bool {
+        self.robots
+            .iter()
+            .any(|directive| directive == "noindex" || directive == "none")
+    }
+
+    /// Returns whether the robots directives forbid following the links of
+    /// the page.
+    pub fn nofollow(&self) -> bool {
+        self.robots
+            .iter()
+            .any(|directive| directive == "nofollow" || directive == "none")
+    }
+
+    fn add_meta(&mut self, element: Element<'_>) {
+        if self.charset.is_none() {
+            self.charset = attribute(element, "charset");
+        }
+
+        let tag = MetaTag {
+            name: attribute(element, "name"),
+            property: attribute(element, "property"),
+            http_equiv: attribute(element, "http-equiv"),
+            content: attribute(element, "content"),
+        };
+        if tag.name.is_none() && tag.property.is_none() && tag.http_equiv.is_none() {
+            return;
+        }
+        let content = tag.content.clone().unwrap_or_default();
+
+        // Twitter cards are specified with `name`, but `property` is common.
+        for key in tag.property.iter().chain(&tag.name) {
+            let property = key.to_ascii_lowercase();
+            let list = if OPEN_GRAPH_PREFIXES
+                .iter()
+                .any(|prefix| property.starts_with(prefix))
+            {
+                &mut self.open_graph
+            } else if property.starts_with("twitter:") {
+                &mut self.twitter
+            } else {
+                continue;
+            };
+            list.push(MetaProperty {
+                property,
+                content: content.clone(),
+            });
+            break;
+        }
+
+        let name = tag.name.as_deref().map(str::to_ascii_lowercase);
+        match name.as_deref() {
+            Some("description") if self.description.is_none() => {
+                self.description = Some(content.clone());
+            }
+            Some("robots") => self.robots.extend(
+                content
+                    .split(',')
+                    .map(|directive| directive.trim().to_ascii_lowercase())
+                    .filter(|directive| !directive.is_empty()),
+            ),
+            _ => {}
+        }
+
+        let http_equiv = tag.http_equiv.as_deref().map(str::to_ascii_lowercase);
+        if http_equiv.as_deref() == Some("refresh") && self.refresh.is_none() {
+            self.refresh = parse_refresh(&content);
+        }
+
+        self.meta.push(tag);
+    }
+
+    fn add_link(&mut self, element: Element<'_>) {
+        let href = match element.attribute_value("href") {
+            Some(href) => href.trim().to_string(),
+            None => return,
+        };
+        let rel = element
+            .attribute_value("rel")
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        let rel: Vec<_> = rel.split_ascii_whitespace().collect();
+        let media_type = attribute(element, "type");
+
+        if rel.contains(&"canonical") && self.canonical.is_none() {
+            self.canonical = Some(href.clone());
+        }
+        if let Some(icon_rel) = rel.iter().find(|rel| ICON_RELS.contains(rel)) {
+            self.icons.push(Icon {
+                href: href.clone(),
+                rel: icon_rel.to_string(),
+                sizes: attribute(element, "sizes"),
+                media_type: media_type.clone(),
+            });
+        }
+        if rel.contains(&"alternate") {
+            let feed_type = media_type
+                .as_deref()
+                .map(str::to_ascii_lowercase)
+                .filter(|media_type| FEED_TYPES.contains(&media_type.as_str()));
+            match feed_type {
+                Some(feed_type) => self.feeds.push(Feed {
+                    href,
+                    media_type: feed_type,
+                    title: attribute(element, "title"),
+                }),
+                None => self.alternates.push(AlternateLink {
+                    href,
+                    hreflang: attribute(element, "hreflang"),
+                    media: attribute(element, "media"),
+                    media_type,
+                }),
+            }
+        }
+    }
+}
+
+fn attribute(element: Element<'_>, name: &str) -> Option<String> {
+    element.attribute_value(name).map(str::to_string)
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_ascii_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Parses the content of `<meta http-equiv="refresh">`, such as
+/// `5; url=/next`, as in the HTML standard.
+fn parse_refresh(content: &str) -> Option<Refresh> {
+    let content = content.trim_start_matches(|c: char| c.is_ascii_whitespace());
+    let digits = content
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(content.len());
+    let delay = match &content[..digits] {
+        "" if content.starts_with('.') => 0,
+        "" => return None,
+        digits => digits.parse().ok()?,
+    };
+
+    let rest = content[digits..].trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+    let rest = rest.trim_start_matches(|c: char| c.is_ascii_whitespace());
+    let rest = match rest.strip_prefix(|c| c == ';' || c == ',') {
+        Some(rest) => rest.trim_start_matches(|c: char| c.is_ascii_whitespace()),
+        None if rest.is_empty() => rest,
+        None => return None,
+    };
+
+    let rest = match rest.get(..3) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("url") => {
+            let after = rest[3..].trim_start_matches(|c: char| c.is_ascii_whitespace());
+            match after.strip_prefix('=') {
+                Some(after) => after.trim_start_matches(|c: char| c.is_ascii_whitespace()),
+                None => rest,
+            }
+        }
+        _ => rest,
+    };
+    let url = match rest.chars().next() {
+        Some(quote @ ('"' | '\'')) => {
+            let rest = &rest[1..];
+            &rest[..rest.find(quote).unwrap_or(rest.len())]
+        }
+        _ => rest,
+    }
+    .trim_end_matches(|c: char| c.is_ascii_whitespace());
+
+    Some(Refresh {
+        delay,
+        url: Some(url.to_string()).filter(|url| !url.is_empty()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = concat!(
+        r#"<html lang="en"><head><meta charset="utf-8"><title>  A   page </title>"#,
+        r#"<base href="https://example.com/blog/">"#,
+        r#"<meta name="Description" content="About things">"#,
+        r#"<meta name="ROBOTS" content="NoIndex, max-snippet:50">"#,
+        r#"<meta property="og:title" content="OG title"><meta property="og:image" content="a.png">"#,
+        r#"<meta property="article:author" content="Ann">"#,
+        r#"<meta name="twitter:card" content="summary"><meta property="twitter:site" content="@x">"#,
+        r#"<meta http-equiv="refresh" content="5; URL='next.html'">"#,
+        r#"<link rel="canonical" href="/post"><link rel="shortcut icon" href="favicon.ico">"#,
+        r#"<link rel="apple-touch-icon" sizes="180x180" href="touch.png">"#,
+        r#"<link rel="alternate" hreflang="de" href="de/">"#,
+        r#"<link rel="alternate" type="application/rss+xml" title="Feed" href="feed.xml">"#,
+        r#"</head><body><title>Not the title</title></body></html>"#,
+    );
+
+    #[test]
+    fn test_extract() {
+        let package = crate::parse_html(PAGE);
+        let metadata = PageMetadata::extract(&package);
+
+        assert_eq!(metadata.title.as_deref(), Some("A page"));
+        assert_eq!(metadata.language.as_deref(), Some("en"));
+        assert_eq!(metadata.charset.as_deref(), Some("utf-8"));
+        assert_eq!(metadata.description.as_deref(), Some("About things"));
+        assert_eq!(metadata.meta.len(), 8);
+        assert_eq!(
+            metadata
+                .open_graph
+                .iter()
+                .map(|property| property.property.as_str())
+                .collect::<Vec<_>>(),
+            vec!["og:title", "og:image", "article:author"]
+        );
+        assert_eq!(metadata.property("twitter:site"), Some("@x"));
+        assert_eq!(metadata.twitter.len(), 2);
+        assert_eq!(metadata.robots, vec!["noindex", "max-snippet:50"]);
+        assert!(metadata.noindex());
+        assert!(!metadata.nofollow());
+        assert_eq!(
+            metadata.refresh,
+            Some(Refresh {
+                delay: 5,
+                url: Some("next.html".to_string())
+            })
+        );
+        assert_eq!(metadata.canonical.as_deref(), Some("/post"));
+        assert_eq!(
+            metadata
+                .icons
+                .iter()
+                .map(|icon| (icon.rel.as_str(), icon.sizes.as_deref()))
+                .collect::<Vec<_>>(),
+            vec![("icon", None), ("apple-touch-icon", Some("180x180"))]
+        );
+        assert_eq!(metadata.alternates[0].hreflang.as_deref(), Some("de"));
+        assert_eq!(metadata.feeds[0].title.as_deref(), Some("Feed"));
+    }
+
+    #[test]
+    fn test_extract_with_url() {
+        let package = crate::parse_html(PAGE);
+        let document_url = Url::parse("https://example.org/").unwrap();
+        let metadata = PageMetadata::extract_with_url(&package, &document_url);
+
+        assert_eq!(
+            metadata.canonical.as_deref(),
+            Some("https://example.com/post")
+        );
+        assert_eq!(
+            metadata.icons[0].href,
+            "https://example.com/blog/favicon.ico"
+        );
+        assert_eq!(metadata.alternates[0].href, "https://example.com/blog/de/");
+        assert_eq!(metadata.feeds[0].href, "https://example.com/blog/feed.xml");
+        assert_eq!(
+            metadata.refresh.and_then(|refresh| refresh.url).as_deref(),
+            Some("https://example.com/blog/next.html")
+        );
+    }
+
+    #[test]
+    fn test_parse_refresh() {
+        let refresh = |content| parse_refresh(content).map(|refresh| (refresh.delay, refresh.url));
+        assert_eq!(refresh("0"), Some((0, None)));
+        assert_eq!(refresh("3.5, url = /a "), Some((3, Some("/a".to_string()))));
+        assert_eq!(refresh("1;/b"), Some((1, Some("/b".to_string()))));
+        assert_eq!(refresh("1 x"), None);
+        assert_eq!(refresh("url=/c"), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let package = crate::parse_html(PAGE);
+        let metadata = PageMetadata::extract(&package);
+        let json = serde_json::to_value(&metadata).unwrap();
+        assert_eq!(json["open_graph"][0]["property"], "og:title");
+        assert_eq!(
+            serde_json::from_value::<PageMetadata>(json).unwrap(),
+            metadata
+        );
+    }
+}
@@ -0,0 +1,247 @@
+use std::collections::{HashMap, HashSet};
+
+use serde_json::{json, Map, Value as JsonValue};
+use sxd_document::{
+    dom::{Element, ParentOfChild},
+    Package,
+};
+use sxd_xpath::nodeset::Node;
+use url::Url;
+
+use crate::util;
+
+/// Extracts the microdata items of the document in `package` in the JSON
+/// format of the WHATWG microdata specification.
+///
+/// The result is an object with an `items` array holding the top-level
+/// items, those with an `itemscope` but no `itemprop`, in document order.
+/// Each item has its `type` tokens, its `id` and its `properties`, which
+/// map property names to arrays of values. Properties are found among the
+/// descendants of the item and the elements its `itemref` names, and
+/// nested items are values themselves.
+///
+/// The values of URL properties, such as the `href` of `a` elements, and
+/// `itemid` are resolved against the base URL of the document at
+/// `document_url` if given, and are as written otherwise.
+///
+/// ```rust
+/// let package = sxd_html::parse_html(r#"
+///     <div itemscope itemtype="https://schema.org/Person">
+///         <span itemprop="name">Ann</span>
+///         <a itemprop="url" href="/ann">home</a>
+///     </div>
+/// "#);
+/// let microdata = sxd_html::microdata(&package, None);
+/// assert_eq!(
+///     microdata,
+///     serde_json::json!({"items": [{
+///         "type": ["https://schema.org/Person"],
+///         "properties": {"name": ["Ann"], "url": ["/ann"]},
+///     }]}),
+/// );
+/// ```
+pub fn microdata(package: &Package, document_url: Option<&Url>) -> JsonValue {
+    let base_url = document_url.map(|document_url| crate::base_url(package, document_url));
+    let root = package.as_document().root();
+    let elements = util::descendant_elements(ParentOfChild::Root(root));
+    let extractor = Extractor {
+        elements: &elements,
+        positions: elements
+            .iter()
+            .enumerate()
+            .map(|(index, &element)| (element, index))
+            .collect(),
+        base_url: base_url.as_ref(),
+    };
+
+    let items: Vec<_> = elements
+        .iter()
+        .filter(|element| {
+            element.attribute("itemscope").is_some() && element.attribute("itemprop").is_none()
+        })
+        .map(|&item| extractor.item(item, &mut vec![]))
+        .collect();
+    json!({ "items": items })
+}
+
+struct Extractor<'a, 'd> {
+    /// The elements of the document in document order.
+    elements: &'a [Element<'d>],
+    positions: HashMap<Element<'d>, usize>,
+    base_url: Option<&'a Url>,
+}
+
+impl<'d> Extractor<'_, 'd> {
+    /// Returns the JSON object of `item`, given the items it is nested in.
+    fn item(&self, item: Element<'d>, memory: &mut Vec<Element<'d>>) -> JsonValue {
+        let mut object = Map::new();
+
+        let types = tokens(item.attribute_value("itemtype"));
+        if !types.is_empty() {
+            object.insert("type".to_string(), json!(types));
+        }
+        if let Some(id) = item.attribute_value("itemid") {
+            object.insert("id".to_string(), json!(self.url(id)));
+        }
+
+        memory.push(item);
+        let mut properties = Map::new();
+        for property in self.properties(item) {
+            let value = if property.attribute("itemscope").is_some() {
+                match memory.contains(&property) {
+                    true => json!("ERROR"),
+                    false => self.item(property, memory),
+                }
+            } else {
+                json!(self.value(property))
+            };
+            for name in tokens(property.attribute_value("itemprop")) {
+                let values = properties.entry(name).or_insert_with(|| json!([]));
+                if let JsonValue::Array(values) = values {
+                    values.push(value.clone());
+                }
+            }
+        }
+        memory.pop();
+
+        object.insert("properties".to_string(), JsonValue::Object(properties));
+        JsonValue::Object(object)
+    }
+
+    /// Returns the properties of `item` in document order, crawling its
+    /// descendants and the elements named by its `itemref` without
+    /// entering nested items.
+    fn properties(&self, item: Element<'d>) -> Vec<Element<'d>> {
+        let mut pending = child_elements(item);
+        for id in tokens(item.attribute_value("itemref")) {
+            if let Some(&element) = self
+                .elements
+                .iter()
+                .find(|element| element.attribute_value("id") == Some(id.as_str()))
+            {
+                pending.push(element);
+            }
+        }
+
+        let mut memory: HashSet<_> = std::iter::once(item).collect();
+        let mut results = vec![];
+        while let Some(current) = pending.pop() {
+            if !memory.insert(current) {
+                continue;
+            }
+            if current.attribute("itemscope").is_none() {
+                pending.extend(child_elements(current));
+            }
+            if !tokens(current.attribute_value("itemprop")).is_empty() {
+                results.push(current);
+            }
+        }
+
+        results.sort_by_key(|result| self.positions.get(result));
+        results
+    }
+
+    /// Returns the value of the property `element` that is not an item.
+    fn value(&self, element: Element<'d>) -> String {
+        let attribute = |name| element.attribute_value(name).unwrap_or("");
+        match util::html_local_name(&element) {
+            Some("meta") => attribute("content").to_string(),
+            Some("audio" | "embed" | "iframe" | "img" | "source" | "track" | "video") => {
+                self.url(attribute("src"))
+            }
+            Some("a" | "area" | "link") => self.url(attribute("href")),
+            Some("object") => self.url(attribute("data")),
+            Some("data" | "meter") => attribute("value").to_string(),
+            Some("time") if element.attribute("datetime").is_some() => {
+                attribute("datetime").to_string()
+            }
+            _ => Node::Element(element).string_value(),
+        }
+    }
+
+    fn url(&self, value: &str) -> String {
+        match self.base_url {
+            Some(base_url) => base_url
+                .join(value.trim())
+                .map(String::from)
+                .unwrap_or_default(),
+            None => value.to_string(),
+        }
+    }
+}
+
+fn child_elements(element: Element<'_>) -> Vec<Element<'_>> {
+    element
+        .children()
+        .into_iter()
+        .filter_map(|child| child.element())
+        .collect()
+}
+
+/// Returns the unique whitespace-separated tokens of an attribute value.
+fn tokens(value: Option<&str>) -> Vec<String> {
+    let mut tokens: Vec<String> = vec![];
+    for token in value.unwrap_or("").split_ascii_whitespace() {
+        if !tokens.iter().any(|existing| existing == token) {
+            tokens.push(token.to_string());
+        }
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_microdata() {
+        let package = crate::parse_html(concat!(
+            r#"<base href="https://example.com/">"#,
+            r#"<div itemscope itemtype="https://schema.org/Event https://schema.org/Event" itemid="/e/1" itemref="loc extra">"#,
+            r#"<h1 itemprop="name headline">Concert</h1><time itemprop="startDate" datetime="2025-05-01">May 1</time>"#,
+            r#"<meta itemprop="eventStatus" content="Scheduled"><img itemprop="image" src="c.jpg">"#,
+            r#"<data itemprop="price" value="10">ten</data><meter itemprop="rating" value="4.5">"#,
+            r#"<div itemprop="performer" itemscope><span itemprop="name">Band</span></div>"#,
+            r#"</div><p id="loc">At <span itemprop="location">the hall</span></p>"#,
+            r#"<div itemscope><b itemprop="note">top-level</b></div>"#,
+        ));
+        let document_url = Url::parse("https://example.org/page").unwrap();
+
+        assert_eq!(
+            microdata(&package, Some(&document_url)),
+            json!({"items": [
+                {
+                    "type": ["https://schema.org/Event"],
+                    "id": "https://example.com/e/1",
+                    "properties": {
+                        "name": ["Concert"],
+                        "headline": ["Concert"],
+                        "startDate": ["2025-05-01"],
+                        "eventStatus": ["Scheduled"],
+                        "image": ["https://example.com/c.jpg"],
+                        "price": ["10"],
+                        "rating": ["4.5"],
+                        "performer": [{"properties": {"name": ["Band"]}}],
+                        "location": ["the hall"],
+                    },
+                },
+                {"properties": {"note": ["top-level"]}},
+            ]})
+        );
+    }
+
+    #[test]
+    fn test_itemref_cycle() {
+        let package = crate::parse_html(concat!(
+            r#"<div itemscope itemref="c"></div>"#,
+            r#"<div id="c" itemprop="child" itemscope itemref="d"></div>"#,
+            r#"<div id="d"><div itemprop="back" itemscope itemref="c"></div></div>"#,
+        ));
+        assert_eq!(
+            microdata(&package, None),
+            json!({"items": [{"properties": {"child": [{"properties": {"back": [
+                {"properties": {"child": ["ERROR"]}},
+            ]}}]}}]})
+        );
+    }
+}
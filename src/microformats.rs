@@ -0,0 +1,697 @@
+use serde_json::{json, Map, Value as JsonValue};
+use sxd_document::{
+    dom::{ChildOfElement, Element, ParentOfChild},
+    Package,
+};
+use url::Url;
+
+use crate::util;
+
+/// Parses the microformats2 markup of the document in `package` into the
+/// canonical mf2 JSON format: an object with the `items`, `rels` and
+/// `rel-urls` of the document.
+///
+/// Items are elements with `h-*` classes. Their `p-*`, `u-*`, `dt-*` and
+/// `e-*` properties are parsed as the microformats2 parsing specification
+/// describes, including the value class pattern and the implied `name`,
+/// `photo` and `url` properties, and nested items become property values
+/// or `children`. Classic microformats (such as `vcard`) are not
+/// recognized.
+///
+/// URLs are resolved against the base URL of the document at
+/// `document_url` if given, and are as written otherwise.
+///
+/// ```rust
+/// let package = sxd_html::parse_html(
+///     r#"<a class="h-card" href="https://example.com/">Ann</a>"#,
+/// );
+/// assert_eq!(
+///     sxd_html::microformats(&package, None),
+///     serde_json::json!({
+///         "items": [{
+///             "type": ["h-card"],
+///             "properties": {"name": ["Ann"], "url": ["https://example.com/"]},
+///         }],
+///         "rels": {},
+///         "rel-urls": {},
+///     }),
+/// );
+/// ```
+pub fn microformats(package: &Package, document_url: Option<&Url>) -> JsonValue {
+    let base_url = document_url.map(|document_url| crate::base_url(package, document_url));
+    let parser = Parser {
+        base_url: base_url.as_ref(),
+    };
+    let root = package.as_document().root();
+
+    let mut items = vec![];
+    let mut pending: Vec<_> = root
+        .children()
+        .into_iter()
+        .rev()
+        .filter_map(|child| child.element())
+        .collect();
+    while let Some(element) = pending.pop() {
+        if root_classes(element).is_empty() {
+            pending.extend(child_elements(element).into_iter().rev());
+        } else {
+            items.push(parser.item(element));
+        }
+    }
+
+    let (rels, rel_urls) = parser.rels(package);
+    json!({ "items": items, "rels": rels, "rel-urls": rel_urls })
+}
+
+/// The prefix of a property class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Prefix {
+    P,
+    U,
+    Dt,
+    E,
+}
+
+/// The properties found so far while parsing an item, and whether they
+/// rule out implied properties.
+#[derive(Default)]
+struct Properties {
+    properties: Map<String, JsonValue>,
+    children: Vec<JsonValue>,
+    has_p_or_e: bool,
+    has_u: bool,
+    has_nested: bool,
+}
+
+impl Properties {
+    fn add(&mut self, name: &str, value: JsonValue) {
+        let values = self
+            .properties
+            .entry(name.to_string())
+            .or_insert_with(|| json!([]));
+        if let JsonValue::Array(values) = values {
+            values.push(value);
+        }
+    }
+}
+
+struct Parser<'a> {
+    base_url: Option<&'a Url>,
+}
+
+impl Parser<'_> {
+    /// Parses the `h-*` element `element`.
+    fn item(&self, element: Element<'_>) -> JsonValue {
+        let mut properties = Properties::default();
+        for child in child_elements(element) {
+            self.properties(child, &mut properties);
+        }
+
+        let implied = |properties: &Properties, name: &str, ruled_out: bool| {
+            !properties.properties.contains_key(name) && !ruled_out && !properties.has_nested
+        };
+        if implied(&properties, "name", properties.has_p_or_e) {
+            let name = self.implied_name(element);
+            properties.add("name", json!(name));
+        }
+        if implied(&properties, "photo", properties.has_u) {
+            if let Some(photo) = self.implied_photo(element) {
+                properties.add("photo", photo);
+            }
+        }
+        if implied(&properties, "url", properties.has_u) {
+            if let Some(url) = self.implied_url(element) {
+                properties.add("url", json!(url));
+            }
+        }
+
+        let mut item = Map::new();
+        item.insert("type".to_string(), json!(root_classes(element)));
+        if let Some(id) = element.attribute_value("id").filter(|id| !id.is_empty()) {
+            item.insert("id".to_string(), json!(id));
+        }
+        item.insert(
+            "properties".to_string(),
+            JsonValue::Object(properties.properties),
+        );
+        if !properties.children.is_empty() {
+            item.insert("children".to_string(), json!(properties.children));
+        }
+        JsonValue::Object(item)
+    }
+
+    /// Adds the properties of `element`, a descendant of an item, and of
+    /// its descendants to `properties`.
+    fn properties(&self, element: Element<'_>, properties: &mut Properties) {
+        let property_classes = property_classes(element);
+        for (prefix, _) in &property_classes {
+            match prefix {
+                Prefix::P | Prefix::E => properties.has_p_or_e = true,
+                Prefix::U => properties.has_u = true,
+                Prefix::Dt => {}
+            }
+        }
+
+        if !root_classes(element).is_empty() {
+            properties.has_nested = true;
+            let item = self.item(element);
+            if property_classes.is_empty() {
+                properties.children.push(item);
+                return;
+            }
+            for (prefix, name) in property_classes {
+                let mut nested = item.clone();
+                let first =
+                    |property: &str| item["properties"][property][0].as_str().map(str::to_string);
+                let value = match prefix {
+                    Prefix::P => first("name").unwrap_or_else(|| self.text(element)),
+                    Prefix::U => first("url").unwrap_or_else(|| self.u_value(element)),
+                    Prefix::Dt => self.dt_value(element),
+                    Prefix::E => {
+                        nested["html"] = json!(crate::inner_html(element).trim());
+                        self.text(element)
+                    }
+                };
+                nested["value"] = json!(value);
+                properties.add(&name, nested);
+            }
+            return;
+        }
+
+        for (prefix, name) in property_classes {
+            let value = match prefix {
+                Prefix::P => json!(self.p_value(element)),
+                Prefix::U => self.u_property(element),
+                Prefix::Dt => json!(self.dt_value(element)),
+                Prefix::E => json!({
+                    "html": crate::inner_html(element).trim(),
+                    "value": self.text(element),
+                }),
+            };
+            properties.add(&name, value);
+        }
+        for child in child_elements(element) {
+            self.properties(child, properties);
+        }
+    }
+
+    fn p_value(&self, element: Element<'_>) -> String {
+        if let Some(value) = self.value_class(element, false) {
+            return value;
+        }
+        let attribute = match util::html_local_name(&element) {
+            Some("abbr" | "link") => "title",
+            Some("data" | "input") => "value",
+            Some("img" | "area") => "alt",
+            _ => "",
+        };
+        if let Some(value) = element.attribute_value(attribute) {
+            return value.to_string();
+        }
+        self.text(element)
+    }
+
+    /// Parses a `u-*` property, which for images with an `alt` is an
+    /// object holding the URL and the alternative text.
+    fn u_property(&self, element: Element<'_>) -> JsonValue {
+        let value = self.u_value(element);
+        match (
+            util::html_local_name(&element),
+            element.attribute_value("alt"),
+        ) {
+            (Some("img"), Some(alt)) if element.attribute("src").is_some() => {
+                json!({ "value": value, "alt": alt })
+            }
+            _ => json!(value),
+        }
+    }
+
+    fn u_value(&self, element: Element<'_>) -> String {
+        let name = util::html_local_name(&element).unwrap_or("");
+        let url_attribute = match name {
+            "a" | "area" | "link" => Some("href"),
+            "img" | "audio" | "video" | "source" | "iframe" => Some("src"),
+            "object" => Some("data"),
+            _ => None,
+        };
+        if let Some(url) = url_attribute.and_then(|attribute| element.attribute_value(attribute)) {
+            return self.resolve(url);
+        }
+        if name == "video" {
+            if let Some(poster) = element.attribute_value("poster") {
+                return self.resolve(poster);
+            }
+        }
+
+        let value = match (name, self.value_class(element, false)) {
+            (_, Some(value)) => value,
+            ("abbr", _) if element.attribute("title").is_some() => {
+                element.attribute_value("title").unwrap_or("").to_string()
+            }
+            ("data" | "input", _) if element.attribute("value").is_some() => {
+                element.attribute_value("value").unwrap_or("").to_string()
+            }
+            _ => self.text(element),
+        };
+        self.resolve(&value)
+    }
+
+    fn dt_value(&self, element: Element<'_>) -> String {
+        if let Some(value) = self.value_class(element, true) {
+            return value;
+        }
+        let name = util::html_local_name(&element).unwrap_or("");
+        let attribute = match name {
+            "time" | "ins" | "del" => "datetime",
+            "abbr" => "title",
+            "data" | "input" => "value",
+            _ => "",
+        };
+        match element.attribute_value(attribute) {
+            Some(value) => value.to_string(),
+            None => self.text(element),
+        }
+    }
+
+    /// Applies the value class pattern: the values of the descendants with
+    /// the class `value` (or `value-title`), joined. Date and time parts of
+    /// a `dt-*` property are joined with a space.
+    fn value_class(&self, element: Element<'_>, date_time: bool) -> Option<String> {
+        let mut values = vec![];
+        let mut pending = child_elements(element);
+        pending.reverse();
+        while let Some(current) = pending.pop() {
+            if !root_classes(current).is_empty() || !property_classes(current).is_empty() {
+                continue;
+            }
+            let classes = classes(current);
+            if classes.contains(&"value-title") {
+                values.push(current.attribute_value("title").unwrap_or("").to_string());
+                continue;
+            }
+            if !classes.contains(&"value") {
+                pending.extend(child_elements(current).into_iter().rev());
+                continue;
+            }
+
+            let name = util::html_local_name(&current).unwrap_or("");
+            let attribute = match name {
+                "img" | "area" => Some("alt"),
+                "data" => Some("value"),
+                "abbr" => Some("title"),
+                "time" | "ins" | "del" if date_time => Some("datetime"),
+                _ => None,
+            };
+            let value = attribute
+                .and_then(|attribute| current.attribute_value(attribute))
+                .map(str::to_string)
+                .unwrap_or_else(|| text_content(current));
+            values.push(value);
+        }
+
+        match (values.is_empty(), date_time) {
+            (true, _) => None,
+            (false, true) => Some(values.join(" ")),
+            (false, false) => Some(values.concat()),
+        }
+    }
+
+    fn implied_name(&self, element: Element<'_>) -> String {
+        let candidates = [
+            Some(element),
+            only_child(element),
+            only_child(element).and_then(only_child),
+        ];
+        for (depth, candidate) in candidates.iter().enumerate() {
+            let candidate = match candidate {
+                Some(candidate) if depth == 0 || root_classes(*candidate).is_empty() => *candidate,
+                _ => break,
+            };
+            let name = util::html_local_name(&candidate);
+            let value = match name {
+                Some("img" | "area") => candidate.attribute_value("alt"),
+                Some("abbr") => candidate.attribute_value("title"),
+                _ => None,
+            };
+            if let Some(value) = value.filter(|value| depth == 0 || !value.is_empty()) {
+                return value.to_string();
+            }
+        }
+        self.text(element)
+    }
+
+    fn implied_photo(&self, element: Element<'_>) -> Option<JsonValue> {
+        let photo = |candidate: Element<'_>| match util::html_local_name(&candidate) {
+            Some("img") if candidate.attribute("src").is_some() => Some(self.u_property(candidate)),
+            Some("object") if candidate.attribute("data").is_some() => {
+                Some(json!(self.u_value(candidate)))
+            }
+            _ => None,
+        };
+        if let Some(photo) = photo(element) {
+            return Some(photo);
+        }
+        // `.h-x>img[src]:only-of-type`, then the same below an only child.
+        for parent in implied_parents(element) {
+            for name in ["img", "object"] {
+                if let Some(photo) = only_of_type(parent, name).and_then(photo) {
+                    return Some(photo);
+                }
+            }
+        }
+        None
+    }
+
+    fn implied_url(&self, element: Element<'_>) -> Option<String> {
+        let url = |candidate: Element<'_>| match util::html_local_name(&candidate) {
+            Some("a" | "area") => candidate
+                .attribute_value("href")
+                .map(|href| self.resolve(href)),
+            _ => None,
+        };
+        if let Some(url) = url(element) {
+            return Some(url);
+        }
+        // `.h-x>a[href]:only-of-type`, then the same below an only child.
+        for parent in implied_parents(element) {
+            for name in ["a", "area"] {
+                if let Some(url) = only_of_type(parent, name).and_then(url) {
+                    return Some(url);
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns the `rels` and `rel-urls` of the document.
+    fn rels(&self, package: &Package) -> (Map<String, JsonValue>, Map<String, JsonValue>) {
+        let mut rels = Map::new();
+        let mut rel_urls = Map::new();
+        let root = package.as_document().root();
+        for element in util::descendant_elements(ParentOfChild::Root(root)) {
+            if !matches!(util::html_local_name(&element), Some("a" | "area" | "link")) {
+                continue;
+            }
+            let (rel, href) = match (
+                element.attribute_value("rel"),
+                element.attribute_value("href"),
+            ) {
+                (Some(rel), Some(href)) if !rel.trim().is_empty() => (rel, href),
+                _ => continue,
+            };
+            let url = self.resolve(href);
+
+            let entry = rel_urls
+                .entry(url.clone())
+                .or_insert_with(|| json!({ "rels": [] }));
+            for attribute in ["hreflang", "media", "title", "type"] {
+                if let Some(value) = element.attribute_value(attribute) {
+                    if entry.get(attribute).is_none() {
+                        entry[attribute] = json!(value);
+                    }
+                }
+            }
+            let text = text_content(element);
+            if entry.get("text").is_none() && !text.is_empty() {
+                entry["text"] = json!(text);
+            }
+
+            for rel in rel.split_ascii_whitespace() {
+                if let JsonValue::Array(rels) = &mut entry["rels"] {
+                    if !rels.iter().any(|existing| existing == rel) {
+                        rels.push(json!(rel));
+                    }
+                }
+                let urls = rels.entry(rel.to_string()).or_insert_with(|| json!([]));
+                if let JsonValue::Array(urls) = urls {
+                    if !urls.iter().any(|existing| *existing == url) {
+                        urls.push(json!(url));
+                    }
+                }
+            }
+        }
+        (rels, rel_urls)
+    }
+
+    /// Returns the text of `element` with images replaced by their `alt`
+    /// text (or else their URL) and scripts and styles dropped, trimmed.
+    fn text(&self, element: Element<'_>) -> String {
+        let mut text = String::new();
+        self.push_text(&mut text, element);
+        text.trim().to_string()
+    }
+
+    fn push_text(&self, text: &mut String, element: Element<'_>) {
+        for child in element.children() {
+            match child {
+                ChildOfElement::Text(child) => text.push_str(child.text()),
+                ChildOfElement::Element(child) => match util::html_local_name(&child) {
+                    Some("script" | "style" | "template") => {}
+                    Some("img") => {
+                        match (child.attribute_value("alt"), child.attribute_value("src")) {
+                            (Some(alt), _) => text.push_str(alt),
+                            (None, Some(src)) => {
+                                text.push(' ');
+                                text.push_str(&self.resolve(src));
+                                text.push(' ');
+                            }
+                            (None, None) => {}
+                        }
+                    }
+                    _ => self.push_text(text, child),
+                },
+                _ => {}
+            }
+        }
+    }
+
+    fn resolve(&self, url: &str) -> String {
+        let url = url.trim();
+        self.base_url
+            .and_then(|base_url| base_url.join(url).ok())
+            .map_or_else(|| url.to_string(), String::from)
+    }
+}
+
+fn classes(element: Element<'_>) -> Vec<&str> {
+    element
+        .attribute_value("class")
+        .unwrap_or("")
+        .split_ascii_whitespace()
+        .collect()
+}
+
+/// Returns the sorted, unique `h-*` classes of `element`.
+fn root_classes(element: Element<'_>) -> Vec<String> {
+    let mut roots: Vec<_> = classes(element)
+        .into_iter()
+        .filter(|class| class.strip_prefix("h-").is_some_and(is_name))
+        .map(str::to_string)
+        .collect();
+    roots.sort();
+    roots.dedup();
+    roots
+}
+
+/// Returns the unique property classes of `element` with their names.
+fn property_classes(element: Element<'_>) -> Vec<(Prefix, String)> {
+    let mut properties: Vec<(Prefix, String)> = vec![];
+    for class in classes(element) {
+        let (prefix, name) = match class.split_once('-') {
+            Some(("p", name)) => (Prefix::P, name),
+            Some(("u", name)) => (Prefix::U, name),
+            Some(("dt", name)) => (Prefix::Dt, name),
+            Some(("e", name)) => (Prefix::E, name),
+            _ => continue,
+        };
+        if is_name(name) && !properties.iter().any(|(p, n)| *p == prefix && n == name) {
+            properties.push((prefix, name.to_string()));
+        }
+    }
+    properties
+}
+
+/// Returns whether `name` is a valid microformats2 name: lowercase words
+/// separated by hyphens, optionally after an alphanumeric vendor prefix.
+fn is_name(name: &str) -> bool {
+    let segments: Vec<_> = name.split('-').collect();
+    segments.iter().enumerate().all(|(index, segment)| {
+        !segment.is_empty()
+            && segment.chars().all(|c| match index {
+                0 if segments.len() > 1 => c.is_ascii_lowercase() || c.is_ascii_digit(),
+                _ => c.is_ascii_lowercase(),
+            })
+    })
+}
+
+fn child_elements(element: Element<'_>) -> Vec<Element<'_>> {
+    element
+        .children()
+        .into_iter()
+        .filter_map(|child| child.element())
+        .collect()
+}
+
+/// Returns the only child element of `element`, if it has exactly one.
+fn only_child(element: Element<'_>) -> Option<Element<'_>> {
+    match child_elements(element).as_slice() {
+        [child] => Some(*child),
+        _ => None,
+    }
+}
+
+/// Returns the elements whose children can imply a photo or URL for the
+/// item `element`: the element itself and its only child, unless that child
+/// is a microformat itself.
+fn implied_parents(element: Element<'_>) -> impl Iterator<Item = Element<'_>> {
+    std::iter::once(element)
+        .chain(only_child(element).filter(|child| root_classes(*child).is_empty()))
+}
+
+/// Returns the only child of `element` named `name`, unless it is a
+/// microformat itself.
+fn only_of_type<'d>(element: Element<'d>, name: &str) -> Option<Element<'d>> {
+    let mut children = child_elements(element)
+        .into_iter()
+        .filter(|child| util::html_local_name(child) == Some(name));
+    match (children.next(), children.next()) {
+        (Some(child), None) if root_classes(child).is_empty() => Some(child),
+        _ => None,
+    }
+}
+
+fn text_content(element: Element<'_>) -> String {
+    sxd_xpath::nodeset::Node::Element(element)
+        .string_value()
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(html: &str) -> JsonValue {
+        let package = crate::parse_html(html);
+        let document_url = Url::parse("https://example.com/").unwrap();
+        microformats(&package, Some(&document_url))
+    }
+
+    #[test]
+    fn test_implied_properties() {
+        assert_eq!(
+            parse(r#"<p class="h-card"><img src="jane.jpg" alt="Jane Doe"/></p>"#)["items"],
+            json!([{
+                "type": ["h-card"],
+                "properties": {
+                    "name": ["Jane Doe"],
+                    "photo": [{"value": "https://example.com/jane.jpg", "alt": "Jane Doe"}],
+                },
+            }])
+        );
+        assert_eq!(
+            parse(
+                r#"<div class="h-card"><a href="/jane"><abbr title="Jane Doe">JD</abbr></a></div>"#
+            )["items"],
+            json!([{
+                "type": ["h-card"],
+                "properties": {"name": ["Jane Doe"], "url": ["https://example.com/jane"]},
+            }])
+        );
+        assert_eq!(
+            parse(r#"<p class="h-card"><span class="p-org">Acme</span> and Jane</p>"#)["items"],
+            json!([{"type": ["h-card"], "properties": {"org": ["Acme"]}}])
+        );
+    }
+
+    #[test]
+    fn test_properties_and_nesting() {
+        let parsed = parse(concat!(
+            r#"<article class="h-entry h-entry" id="post">"#,
+            r#"<h1 class="p-name">Hello <img src="wave.png"></h1>"#,
+            r#"<time class="dt-published" datetime="2025-01-02T03:04:05Z">Jan 2</time>"#,
+            r#"<p class="dt-updated"><span class="value">2025-01-03</span> at <span class="value">10:00</span></p>"#,
+            r#"<p class="p-summary"><span class="value">Short</span><span class="value-title" title=" story"></span></p>"#,
+            r#"<a class="u-url u-uid" href="/post">link</a><data class="p-rating" value="5">five</data>"#,
+            r#"<div class="p-author h-card"><a class="u-url" href="/ann">Ann</a></div>"#,
+            r#"<div class="e-content"><p>Body <b>text</b></p></div>"#,
+            r#"<div class="h-cite"><span class="p-name">Cited</span></div>"#,
+            r#"<span class="p-Invalid p-x-9 p-">x</span>"#,
+            r#"</article><a rel="me author" href="/ann" title="Ann">Ann's page</a><link rel="me" href="/ann">"#,
+        ));
+
+        assert_eq!(
+            parsed["items"],
+            json!([{
+                "type": ["h-entry"],
+                "id": "post",
+                "properties": {
+                    "name": ["Hello  https://example.com/wave.png"],
+                    "published": ["2025-01-02T03:04:05Z"],
+                    "updated": ["2025-01-03 10:00"],
+                    "summary": ["Short story"],
+                    "url": ["https://example.com/post"],
+                    "uid": ["https://example.com/post"],
+                    "rating": ["5"],
+                    "author": [{
+                        "type": ["h-card"],
+                        "properties": {"name": ["Ann"], "url": ["https://example.com/ann"]},
+                        "value": "Ann",
+                    }],
+                    "content": [{"html": "<p>Body <b>text</b></p>", "value": "Body text"}],
+                },
+                "children": [{"type": ["h-cite"], "properties": {"name": ["Cited"]}}],
+            }])
+        );
+        assert_eq!(
+            parsed["rels"],
+            json!({"me": ["https://example.com/ann"], "author": ["https://example.com/ann"]})
+        );
+        assert_eq!(
+            parsed["rel-urls"],
+            json!({"https://example.com/ann": {
+                "rels": ["me", "author"],
+                "text": "Ann's page",
+                "title": "Ann",
+            }})
+        );
+    }
+
+    #[test]
+    fn test_fixtures() {
+        // Pages covering the implied photo and url rules and a nested
+        // author, with the expected JSON of each when parsed with
+        // `http://example.com/` as the document URL.
+        let fixtures = [
+            (
+                include_str!("../tests/fixtures/microformats/implied-photo.html"),
+                include_str!("../tests/fixtures/microformats/implied-photo.json"),
+            ),
+            (
+                include_str!("../tests/fixtures/microformats/implied-url.html"),
+                include_str!("../tests/fixtures/microformats/implied-url.json"),
+            ),
+            (
+                include_str!("../tests/fixtures/microformats/nested-author.html"),
+                include_str!("../tests/fixtures/microformats/nested-author.json"),
+            ),
+        ];
+        let document_url = Url::parse("http://example.com/").unwrap();
+        for (html, expected) in fixtures {
+            let package = crate::parse_html(html);
+            let expected: JsonValue = serde_json::from_str(expected).unwrap();
+            assert_eq!(microformats(&package, Some(&document_url)), expected);
+        }
+    }
+
+    #[test]
+    fn test_is_name() {
+        assert!(is_name("card"));
+        assert!(is_name("x-card"));
+        assert!(is_name("9a-card-name"));
+        assert!(!is_name("Card"));
+        assert!(!is_name("9"));
+        assert!(!is_name("card-"));
+        assert!(!is_name("x-9"));
+    }
+}
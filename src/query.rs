@@ -0,0 +1,645 @@
+use std::{cell::RefCell, collections::HashMap, fmt, rc::Rc};
+
+use sxd_document::QName;
+use sxd_xpath::{
+    nodeset::{Node, Nodeset},
+    Context, ExecutionError, Factory, ParserError, Value, XPath,
+};
+
+use crate::{ConversionError, FromXPathValue, SelectorError};
+
+/// Maximum number of compiled expressions cached per thread.
+///
+/// Expressions are usually string literals, so a program only uses a
+/// bounded set of them. The cap keeps memory bounded for programs that
+/// build expressions dynamically; the cache is cleared when it is reached.
+pub const MAX_CACHED_EXPRESSIONS: usize = 256;
+
+thread_local! {
+    static EXPRESSIONS: RefCell<HashMap<String, Rc<XPath>>> = RefCell::new(HashMap::new());
+    static DEFAULT_QUERY: Query<'static> = Query::new();
+}
+
+/// Errors returned by the query functions.
+///
+/// A syntax error in the expression is reported separately from an
+/// expression that is valid but does not match anything.
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryError {
+    /// The expression is not valid XPath.
+    Syntax { xpath: String, error: ParserError },
+    /// The expression is empty.
+    Empty,
+    /// The CSS selector is not valid or uses an unsupported feature.
+    Selector {
+        selector: String,
+        error: SelectorError,
+    },
+    /// The expression could not be evaluated, for example because it calls
+    /// an unknown function or uses an unregistered namespace prefix.
+    Evaluation {
+        xpath: String,
+        error: ExecutionError,
+    },
+    /// The expression evaluated to a boolean, number or string where a node
+    /// set was expected.
+    NotANodeset { xpath: String },
+    /// The expression did not select any node.
+    NoMatch { xpath: String },
+    /// The selected node is an element without the requested attribute, or
+    /// is not an element.
+    MissingAttribute { xpath: String, attribute: String },
+    /// The result could not be converted to a number.
+    NotANumber { xpath: String },
+    /// The result is a number, but not an integer in the range of the
+    /// requested type.
+    NotAnInteger { xpath: String },
+}
+
+impl QueryError {
+    fn from_conversion(xpath: &str, error: ConversionError) -> Self {
+        let xpath = xpath.to_string();
+        match error {
+            ConversionError::NoMatch => Self::NoMatch { xpath },
+            ConversionError::NotANodeset => Self::NotANodeset { xpath },
+            ConversionError::NotANumber => Self::NotANumber { xpath },
+            ConversionError::NotAnInteger => Self::NotAnInteger { xpath },
+        }
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax { xpath, error } => write!(f, "invalid XPath `{}`: {}", xpath, error),
+            Self::Empty => write!(f, "XPath expression is empty"),
+            Self::Selector { selector, error } => {
+                write!(f, "invalid CSS selector `{}`: {}", selector, error)
+            }
+            Self::Evaluation { xpath, error } => {
+                write!(f, "unable to evaluate XPath `{}`: {}", xpath, error)
+            }
+            Self::NotANodeset { xpath } => write!(f, "XPath `{}` did not select nodes", xpath),
+            Self::NoMatch { xpath } => write!(f, "XPath `{}` did not match any node", xpath),
+            Self::MissingAttribute { xpath, attribute } => write!(
+                f,
+                "node selected by XPath `{}` has no `{}` attribute",
+                xpath, attribute
+            ),
+            Self::NotANumber { xpath } => write!(f, "XPath `{}` did not yield a number", xpath),
+            Self::NotAnInteger { xpath } => {
+                write!(f, "XPath `{}` did not yield an integer in range", xpath)
+            }
+        }
+    }
+}
+
+impl std::error::Error for QueryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Syntax { error, .. } => Some(error),
+            Self::Selector { error, .. } => Some(error),
+            Self::Evaluation { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Evaluates XPath expressions against a [`Context`].
+///
+/// The `select*` functions of this crate use a `Query` with the core XPath
+/// 1.0 functions. Create a `Query` to evaluate expressions with custom
+/// functions, variables or namespace prefixes. Compiled expressions are
+/// cached per thread and shared by every `Query`.
+pub struct Query<'d> {
+    context: Context<'d>,
+}
+
+impl<'d> Query<'d> {
+    /// Creates a query with the core XPath 1.0 functions.
+    pub fn new() -> Self {
+        Self::with_context(Context::new())
+    }
+
+    /// Creates a query evaluating expressions in `context`.
+    pub fn with_context(context: Context<'d>) -> Self {
+        Self { context }
+    }
+
+    /// Returns the context expressions are evaluated in.
+    pub fn context(&self) -> &Context<'d> {
+        &self.context
+    }
+
+    /// Returns the context expressions are evaluated in, to register
+    /// functions, variables or namespaces.
+    pub fn context_mut(&mut self) -> &mut Context<'d> {
+        &mut self.context
+    }
+
+    /// Evaluates `xpath` with `node` as the context node.
+    pub fn evaluate(
+        &self,
+        node: impl Into<Node<'d>>,
+        xpath: &str,
+    ) -> Result<Value<'d>, QueryError> {
+        let expression = compile(xpath)?;
+        expression
+            .evaluate(&self.context, node)
+            .map_err(|error| QueryError::Evaluation {
+                xpath: xpath.to_string(),
+                error,
+            })
+    }
+
+    /// Returns the nodes selected by `xpath`.
+    ///
+    /// An expression that matches nothing yields an empty node set.
+    pub fn select(
+        &self,
+        node: impl Into<Node<'d>>,
+        xpath: &str,
+    ) -> Result<Nodeset<'d>, QueryError> {
+        match self.evaluate(node, xpath)? {
+            Value::Nodeset(nodes) => Ok(nodes),
+            _ => Err(QueryError::NotANodeset {
+                xpath: xpath.to_string(),
+            }),
+        }
+    }
+
+    /// Returns the elements matching the CSS `selector` among the
+    /// descendants of `node`.
+    ///
+    /// See [`css_to_xpath`](crate::css_to_xpath) for the supported
+    /// selectors.
+    pub fn select_css(
+        &self,
+        node: impl Into<Node<'d>>,
+        selector: &str,
+    ) -> Result<Nodeset<'d>, QueryError> {
+        let xpath = crate::css_to_xpath(selector).map_err(|error| QueryError::Selector {
+            selector: selector.to_string(),
+            error,
+        })?;
+        self.select(node, &xpath)
+    }
+
+    /// Returns the first node in document order selected by `xpath`.
+    pub fn select_one(
+        &self,
+        node: impl Into<Node<'d>>,
+        xpath: &str,
+    ) -> Result<Node<'d>, QueryError> {
+        self.select(node, xpath)?
+            .document_order_first()
+            .ok_or_else(|| QueryError::NoMatch {
+                xpath: xpath.to_string(),
+            })
+    }
+
+    /// Returns the string value of the first node selected by `xpath`.
+    ///
+    /// Expressions that evaluate to a string, number or boolean, such as
+    /// `normalize-space(//h1)`, are converted to a string.
+    pub fn select_text(
+        &self,
+        node: impl Into<Node<'d>>,
+        xpath: &str,
+    ) -> Result<String, QueryError> {
+        match self.evaluate(node, xpath)? {
+            Value::Nodeset(nodes) => nodes
+                .document_order_first()
+                .map(|node| node.string_value())
+                .ok_or_else(|| QueryError::NoMatch {
+                    xpath: xpath.to_string(),
+                }),
+            value => Ok(value.into_string()),
+        }
+    }
+
+    /// Returns the value of the `attribute` attribute of the first element
+    /// selected by `xpath`.
+    pub fn select_attr(
+        &self,
+        node: impl Into<Node<'d>>,
+        xpath: &str,
+        attribute: &str,
+    ) -> Result<String, QueryError> {
+        self.select_one(node, xpath)?
+            .element()
+            .and_then(|element| element.attribute_value(attribute))
+            .map(str::to_string)
+            .ok_or_else(|| QueryError::MissingAttribute {
+                xpath: xpath.to_string(),
+                attribute: attribute.to_string(),
+            })
+    }
+
+    /// Returns the result of `xpath` converted to a number.
+    ///
+    /// Node sets are converted using the string value of their first node.
+    pub fn select_number(&self, node: impl Into<Node<'d>>, xpath: &str) -> Result<f64, QueryError> {
+        let value = self.evaluate(node, xpath)?;
+        if let Value::Nodeset(nodes) = &value {
+            if nodes.size() == 0 {
+                return Err(QueryError::NoMatch {
+                    xpath: xpath.to_string(),
+                });
+            }
+        }
+
+        let number = value.into_number();
+        if number.is_nan() {
+            return Err(QueryError::NotANumber {
+                xpath: xpath.to_string(),
+            });
+        }
+        Ok(number)
+    }
+
+    /// Returns the result of `xpath` converted to `T`.
+    ///
+    /// ```rust
+    /// let package = sxd_html::parse_html("<li>1</li><li>2</li>");
+    /// let root = package.as_document().root();
+    /// let query = sxd_html::Query::new();
+    /// let numbers = query.query_as::<Vec<i64>>(root, "//li").unwrap();
+    /// assert_eq!(numbers, vec![1, 2]);
+    /// let title = query.query_as::<Option<String>>(root, "//title").unwrap();
+    /// assert_eq!(title, None);
+    /// ```
+    ///
+    /// See [`FromXPathValue`] for the available conversions.
+    pub fn query_as<T: FromXPathValue<'d>>(
+        &self,
+        node: impl Into<Node<'d>>,
+        xpath: &str,
+    ) -> Result<T, QueryError> {
+        let value = self.evaluate(node, xpath)?;
+        T::from_xpath_value(value).map_err(|error| QueryError::from_conversion(xpath, error))
+    }
+}
+
+impl Default for Query<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Query<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Query").finish_non_exhaustive()
+    }
+}
+
+/// Evaluates `xpath` with `node` as the context node.
+///
+/// See [`Query::evaluate`].
+pub fn evaluate<'d>(node: impl Into<Node<'d>>, xpath: &str) -> Result<Value<'d>, QueryError> {
+    DEFAULT_QUERY.with(|query| query.evaluate(node, xpath))
+}
+
+/// Returns the nodes selected by `xpath`.
+///
+/// See [`Query::select`].
+pub fn select<'d>(node: impl Into<Node<'d>>, xpath: &str) -> Result<Nodeset<'d>, QueryError> {
+    DEFAULT_QUERY.with(|query| query.select(node, xpath))
+}
+
+/// Returns the elements matching the CSS `selector` among the descendants
+/// of `node`.
+///
+/// See [`Query::select_css`].
+pub fn select_css<'d>(
+    node: impl Into<Node<'d>>,
+    selector: &str,
+) -> Result<Nodeset<'d>, QueryError> {
+    DEFAULT_QUERY.with(|query| query.select_css(node, selector))
+}
+
+/// Returns the first node in document order selected by `xpath`.
+///
+/// See [`Query::select_one`].
+pub fn select_one<'d>(node: impl Into<Node<'d>>, xpath: &str) -> Result<Node<'d>, QueryError> {
+    DEFAULT_QUERY.with(|query| query.select_one(node, xpath))
+}
+
+/// Returns the string value of the first node selected by `xpath`.
+///
+/// See [`Query::select_text`].
+pub fn select_text<'d>(node: impl Into<Node<'d>>, xpath: &str) -> Result<String, QueryError> {
+    DEFAULT_QUERY.with(|query| query.select_text(node, xpath))
+}
+
+/// Returns an attribute of the first element selected by `xpath`.
+///
+/// See [`Query::select_attr`].
+pub fn select_attr<'d>(
+    node: impl Into<Node<'d>>,
+    xpath: &str,
+    attribute: &str,
+) -> Result<String, QueryError> {
+    DEFAULT_QUERY.with(|query| query.select_attr(node, xpath, attribute))
+}
+
+/// Returns the result of `xpath` converted to a number.
+///
+/// See [`Query::select_number`].
+pub fn select_number<'d>(node: impl Into<Node<'d>>, xpath: &str) -> Result<f64, QueryError> {
+    DEFAULT_QUERY.with(|query| query.select_number(node, xpath))
+}
+
+/// Returns the result of `xpath` converted to `T`.
+///
+/// See [`Query::query_as`].
+pub fn query_as<'d, T: FromXPathValue<'d>>(
+    node: impl Into<Node<'d>>,
+    xpath: &str,
+) -> Result<T, QueryError> {
+    DEFAULT_QUERY.with(|query| query.query_as(node, xpath))
+}
+
+/// Returns an absolute XPath expression that selects `node`, such as
+/// `/html[1]/body[1]/div[2]/a[1]/@href`.
+///
+/// Every step has the position of the node among its siblings of the same
+/// name, or of the same kind for text, comments and processing
+/// instructions. SVG and MathML elements and `xlink` attributes are named
+/// with the prefixes of [`register_namespaces`](crate::register_namespaces),
+/// so the expression is evaluated in a context that has them, such as
+/// [`html_context`](crate::html_context).
+///
+/// ```rust
+/// let package = sxd_html::parse_html("<p>one</p><p>two <a href='/x'>x</a></p>");
+/// let root = package.as_document().root();
+/// let href = sxd_html::select_one(root, "//a/@href").unwrap();
+/// assert_eq!(sxd_html::absolute_xpath(href), "/html[1]/body[1]/p[2]/a[1]/@href");
+/// ```
+pub fn absolute_xpath<'d>(node: impl Into<Node<'d>>) -> String {
+    let mut steps = vec![];
+    let mut current = node.into();
+    loop {
+        let position = |same: &dyn Fn(&Node<'d>) -> bool| {
+            current
+                .preceding_siblings()
+                .iter()
+                .filter(|sibling| same(sibling))
+                .count()
+                + 1
+        };
+        let step = match current {
+            Node::Root(_) => break,
+            Node::Element(element) => {
+                let name = element.name();
+                let position = position(
+                    &|sibling| matches!(sibling, Node::Element(sibling) if sibling.name() == name),
+                );
+                format!("{}[{}]", name_test(name), position)
+            }
+            Node::Attribute(attribute) => format!("@{}", name_test(attribute.name())),
+            Node::Text(_) => {
+                let position = position(&|sibling| matches!(sibling, Node::Text(_)));
+                format!("text()[{}]", position)
+            }
+            Node::Comment(_) => {
+                let position = position(&|sibling| matches!(sibling, Node::Comment(_)));
+                format!("comment()[{}]", position)
+            }
+            Node::ProcessingInstruction(_) => {
+                let position =
+                    position(&|sibling| matches!(sibling, Node::ProcessingInstruction(_)));
+                format!("processing-instruction()[{}]", position)
+            }
+            Node::Namespace(namespace) => format!("namespace::{}", namespace.prefix()),
+        };
+        steps.push(step);
+        match current.parent() {
+            Some(parent) => current = parent,
+            None => break,
+        }
+    }
+    steps.reverse();
+    format!("/{}", steps.join("/"))
+}
+
+/// Returns the name test of a step selecting `name`, falling back to a
+/// predicate on the name for names a name test cannot express, such as
+/// `on:click`, and for namespaces without a registered prefix.
+fn name_test(name: QName<'_>) -> String {
+    let local_part = name.local_part();
+    let prefix = match name.namespace_uri() {
+        None => None,
+        Some(namespace_uri) => {
+            match crate::functions::NAMESPACES
+                .iter()
+                .find(|(_, uri)| *uri == namespace_uri)
+            {
+                Some((prefix, _)) => Some(*prefix),
+                None => {
+                    return format!(
+                        "*[local-name()={} and namespace-uri()={}]",
+                        crate::css::literal(local_part),
+                        crate::css::literal(namespace_uri)
+                    )
+                }
+            }
+        }
+    };
+    match (prefix, is_ncname(local_part)) {
+        (Some(prefix), true) => format!("{}:{}", prefix, local_part),
+        (None, true) => local_part.to_string(),
+        (Some(_), false) => format!(
+            "*[local-name()={} and namespace-uri()={}]",
+            crate::css::literal(local_part),
+            crate::css::literal(name.namespace_uri().unwrap_or_default())
+        ),
+        (None, false) => format!("*[name()={}]", crate::css::literal(local_part)),
+    }
+}
+
+/// Returns `true` if `name` can be written as an unprefixed name test.
+fn is_ncname(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_' || !c.is_ascii())
+        && chars.all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.') || !c.is_ascii())
+}
+
+/// Compiles `xpath`, reusing the thread's cached expression if it was
+/// compiled before.
+fn compile(xpath: &str) -> Result<Rc<XPath>, QueryError> {
+    if let Some(expression) = EXPRESSIONS.with(|cache| cache.borrow().get(xpath).cloned()) {
+        return Ok(expression);
+    }
+
+    let expression = Factory::new()
+        .build(xpath)
+        .map_err(|error| QueryError::Syntax {
+            xpath: xpath.to_string(),
+            error,
+        })?
+        .ok_or(QueryError::Empty)?;
+    let expression = Rc::new(expression);
+
+    EXPRESSIONS.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if cache.len() >= MAX_CACHED_EXPRESSIONS {
+            cache.clear();
+        }
+        cache.insert(xpath.to_string(), Rc::clone(&expression));
+    });
+
+    Ok(expression)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HTML: &str = r#"<ul><li><a href="/a">first</a></li><li>2.5</li></ul>"#;
+
+    #[test]
+    fn test_absolute_xpath() {
+        let package = crate::parse_html(
+            r##"<p>a<!--c-->b<br>c</p><p><svg><g/><g><use xlink:href="#i"/></g></svg></p>"##,
+        );
+        let root = package.as_document().root();
+        let query = Query::with_context(crate::html_context());
+        let expected = [
+            ("//p[1]/text()[3]", "/html[1]/body[1]/p[1]/text()[3]"),
+            ("//p[1]/comment()", "/html[1]/body[1]/p[1]/comment()[1]"),
+            (
+                "//svg:use/@xlink:href",
+                "/html[1]/body[1]/p[2]/svg:svg[1]/svg:g[2]/svg:use[1]/@xlink:href",
+            ),
+        ];
+        for (xpath, absolute) in expected {
+            let node = query.select_one(root, xpath).unwrap();
+            assert_eq!(absolute_xpath(node), absolute);
+            assert_eq!(query.select_one(root, absolute).unwrap(), node);
+        }
+        assert_eq!(absolute_xpath(root), "/");
+    }
+
+    #[test]
+    fn test_absolute_xpath_unusual_names() {
+        let package =
+            crate::parse_html(r#"<p xml:lang="en" a:b="1" on:click="go()"><x:y>text</x:y></p>"#);
+        let root = package.as_document().root();
+        let query = Query::with_context(crate::html_context());
+        for attribute in ["xml:lang", "a:b", "on:click"] {
+            let node = query
+                .select_one(root, &format!("//p/@*[name()='{}']", attribute))
+                .unwrap();
+            let absolute = absolute_xpath(node);
+            assert_eq!(
+                absolute,
+                format!("/html[1]/body[1]/p[1]/@*[name()='{}']", attribute)
+            );
+            assert_eq!(query.select_one(root, &absolute).unwrap(), node);
+        }
+        let element = query.select_one(root, "//p/*").unwrap();
+        assert_eq!(
+            query.select_one(root, &absolute_xpath(element)).unwrap(),
+            element
+        );
+
+        let xml = sxd_document::parser::parse(r#"<r xmlns="urn:it's"><i/><i/></r>"#).unwrap();
+        let root = xml.as_document().root();
+        let node = query.select_one(root, "/*/*[2]").unwrap();
+        let absolute = absolute_xpath(node);
+        assert_eq!(
+            absolute,
+            "/*[local-name()='r' and namespace-uri()=\"urn:it's\"][1]/*[local-name()='i' and namespace-uri()=\"urn:it's\"][2]"
+        );
+        assert_eq!(query.select_one(root, &absolute).unwrap(), node);
+    }
+
+    #[test]
+    fn test_select_functions() {
+        let package = crate::parse_html(HTML);
+        let root = package.as_document().root();
+
+        assert_eq!(select(root, "//li").unwrap().size(), 2);
+        assert_eq!(select(root, "//table").unwrap().size(), 0);
+        assert_eq!(select_text(root, "//a").unwrap(), "first");
+        assert_eq!(select_text(root, "count(//li)").unwrap(), "2");
+        assert_eq!(select_attr(root, "//a", "href").unwrap(), "/a");
+        assert_eq!(select_number(root, "//li[2]").unwrap(), 2.5);
+
+        let first = select_one(root, "//li").unwrap();
+        assert_eq!(select_text(first, "./a/text()").unwrap(), "first");
+    }
+
+    #[test]
+    fn test_errors_are_distinguished() {
+        let package = crate::parse_html(HTML);
+        let root = package.as_document().root();
+
+        assert!(matches!(
+            select(root, "//li[").unwrap_err(),
+            QueryError::Syntax { .. }
+        ));
+        assert!(matches!(select(root, "").unwrap_err(), QueryError::Empty));
+        assert!(matches!(
+            select_css(root, "li >").unwrap_err(),
+            QueryError::Selector { .. }
+        ));
+        assert!(matches!(
+            select(root, "unknown-function()").unwrap_err(),
+            QueryError::Evaluation { .. }
+        ));
+        assert!(matches!(
+            select(root, "count(//li)").unwrap_err(),
+            QueryError::NotANodeset { .. }
+        ));
+        assert!(matches!(
+            select_one(root, "//table").unwrap_err(),
+            QueryError::NoMatch { .. }
+        ));
+        assert!(matches!(
+            select_attr(root, "//a", "title").unwrap_err(),
+            QueryError::MissingAttribute { .. }
+        ));
+        assert!(matches!(
+            select_number(root, "//a").unwrap_err(),
+            QueryError::NotANumber { .. }
+        ));
+    }
+
+    #[test]
+    fn test_query_with_custom_context() {
+        let package = crate::parse_html(HTML);
+        let root = package.as_document().root();
+
+        let mut query = Query::new();
+        query.context_mut().set_variable("n", 2.0);
+        assert_eq!(query.select_text(root, "//li[$n]").unwrap(), "2.5");
+    }
+
+    #[test]
+    fn test_query_as() {
+        let package = crate::parse_html(HTML);
+        let root = package.as_document().root();
+
+        assert_eq!(query_as::<f64>(root, "//li[2]").unwrap(), 2.5);
+        assert_eq!(
+            query_as::<Vec<String>>(root, "//li").unwrap(),
+            vec!["first", "2.5"]
+        );
+        assert_eq!(
+            query_as::<Option<String>>(root, "//table/@id").unwrap(),
+            None
+        );
+        assert!(matches!(
+            query_as::<String>(root, "//table").unwrap_err(),
+            QueryError::NoMatch { .. }
+        ));
+        assert!(matches!(
+            query_as::<i64>(root, "//li[2]").unwrap_err(),
+            QueryError::NotAnInteger { .. }
+        ));
+    }
+}
@@ -0,0 +1,408 @@
+use std::collections::HashMap;
+
+use sxd_document::{dom::Element, Package};
+use sxd_xpath::nodeset::Node;
+use url::Url;
+
+use crate::util;
+
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+
+/// The prefixes of the RDFa initial context that are in common use.
+const INITIAL_PREFIXES: &[(&str, &str)] = &[
+    ("cc", "http://creativecommons.org/ns#"),
+    ("dc", "http://purl.org/dc/terms/"),
+    ("dcterms", "http://purl.org/dc/terms/"),
+    ("foaf", "http://xmlns.com/foaf/0.1/"),
+    ("og", "http://ogp.me/ns#"),
+    ("owl", "http://www.w3.org/2002/07/owl#"),
+    ("rdf", "http://www.w3.org/1999/02/22-rdf-syntax-ns#"),
+    ("rdfs", "http://www.w3.org/2000/01/rdf-schema#"),
+    ("schema", "http://schema.org/"),
+    ("sioc", "http://rdfs.org/sioc/ns#"),
+    ("skos", "http://www.w3.org/2004/02/skos/core#"),
+    ("vcard", "http://www.w3.org/2006/vcard/ns#"),
+    ("xsd", "http://www.w3.org/2001/XMLSchema#"),
+];
+
+/// A statement made with RDFa attributes, returned by [`rdfa`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RdfaTriple<'d> {
+    /// The element with the `property` or `typeof` attribute.
+    pub element: Element<'d>,
+    /// The IRI or blank node the statement is about.
+    pub subject: String,
+    /// The IRI of the property, `rdf:type` for `typeof`.
+    pub predicate: String,
+    /// The value.
+    pub object: RdfaObject,
+}
+
+/// The object of an [`RdfaTriple`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RdfaObject {
+    /// An IRI, or a blank node such as `_:b0`.
+    Resource(String),
+    /// A plain literal in the language of the element, if given by `lang`.
+    Literal {
+        value: String,
+        language: Option<String>,
+    },
+}
+
+/// Extracts the RDFa Lite statements of the document in `package`.
+///
+/// The RDFa Lite attributes `vocab`, `typeof`, `property`, `resource` and
+/// `prefix` are processed as in RDFa 1.1: `typeof` starts a new resource,
+/// named by `resource`, `href` or `src` or else a blank node, and
+/// `property` adds a statement about the current resource whose value is
+/// the typed resource, the IRI in `resource`, `href` or `src`, or a literal
+/// from `content`, `datetime` or the text of the element. Terms are
+/// expanded with the `vocab` in scope, and CURIEs with `prefix` and the
+/// common prefixes of the RDFa initial context.
+///
+/// The document is the subject of top-level statements, and IRIs are
+/// resolved against its base URL, if `document_url` is given. Otherwise
+/// the document is named by the empty IRI and IRIs are as written.
+///
+/// ```rust
+/// use sxd_html::RdfaObject;
+///
+/// let package = sxd_html::parse_html(r##"
+///     <div vocab="http://schema.org/" typeof="Person" resource="#me">
+///         <span property="name">Ann</span>
+///     </div>
+/// "##);
+/// let triples = sxd_html::rdfa(&package, None);
+/// assert_eq!(triples[1].subject, "#me");
+/// assert_eq!(triples[1].predicate, "http://schema.org/name");
+/// assert_eq!(
+///     triples[1].object,
+///     RdfaObject::Literal { value: "Ann".to_string(), language: None },
+/// );
+/// ```
+pub fn rdfa<'d>(package: &'d Package, document_url: Option<&Url>) -> Vec<RdfaTriple<'d>> {
+    let base_url = document_url.map(|document_url| crate::base_url(package, document_url));
+    let mut processor = Processor {
+        base_url: base_url.as_ref(),
+        blank_nodes: 0,
+        triples: vec![],
+    };
+    let context = Context {
+        subject: base_url.as_ref().map(Url::to_string).unwrap_or_default(),
+        vocab: None,
+        prefixes: INITIAL_PREFIXES
+            .iter()
+            .map(|&(prefix, iri)| (prefix.to_string(), iri.to_string()))
+            .collect(),
+        language: None,
+    };
+
+    let root = package.as_document().root();
+    for element in root
+        .children()
+        .into_iter()
+        .filter_map(|child| child.element())
+    {
+        processor.process(element, &context);
+    }
+    processor.triples
+}
+
+/// The evaluation context an element inherits from its parent.
+#[derive(Clone)]
+struct Context {
+    subject: String,
+    vocab: Option<String>,
+    prefixes: HashMap<String, String>,
+    language: Option<String>,
+}
+
+struct Processor<'a, 'd> {
+    base_url: Option<&'a Url>,
+    blank_nodes: usize,
+    triples: Vec<RdfaTriple<'d>>,
+}
+
+impl<'d> Processor<'_, 'd> {
+    fn process(&mut self, element: Element<'d>, parent: &Context) {
+        let mut context = parent.clone();
+        if let Some(vocab) = element.attribute_value("vocab") {
+            context.vocab = match vocab.trim() {
+                "" => None,
+                vocab => Some(self.resolve(vocab)),
+            };
+        }
+        if let Some(prefix) = element.attribute_value("prefix") {
+            let mut tokens = prefix.split_ascii_whitespace();
+            while let (Some(prefix), Some(iri)) = (tokens.next(), tokens.next()) {
+                if let Some(prefix) = prefix.strip_suffix(':') {
+                    context
+                        .prefixes
+                        .insert(prefix.to_ascii_lowercase(), iri.to_string());
+                }
+            }
+        }
+        if let Some(language) = element.attribute_value("lang") {
+            context.language = Some(language.to_string()).filter(|language| !language.is_empty());
+        }
+
+        let resource = ["resource", "href", "src"]
+            .iter()
+            .find_map(|&name| element.attribute_value(name))
+            .map(|resource| self.resource(resource, &context));
+        let typed = element.attribute("typeof").map(|_| match &resource {
+            Some(resource) => resource.clone(),
+            None => self.blank_node(),
+        });
+
+        if let Some(typed) = &typed {
+            for kind in self.terms(element.attribute_value("typeof"), &context) {
+                self.push(
+                    element,
+                    typed,
+                    RDF_TYPE.to_string(),
+                    RdfaObject::Resource(kind),
+                );
+            }
+        }
+        if element.attribute("property").is_some() {
+            let object = match (&typed, &resource) {
+                (Some(typed), _) => RdfaObject::Resource(typed.clone()),
+                (None, Some(resource)) => RdfaObject::Resource(resource.clone()),
+                (None, None) => RdfaObject::Literal {
+                    value: literal(element),
+                    language: context.language.clone(),
+                },
+            };
+            for property in self.terms(element.attribute_value("property"), &context) {
+                let subject = context.subject.clone();
+                self.push(element, &subject, property, object.clone());
+            }
+        }
+
+        // Without `property`, the resource is the new subject even when it
+        // is not typed.
+        let has_property = element.attribute("property").is_some();
+        if let Some(subject) = typed.or(resource.filter(|_| !has_property)) {
+            context.subject = subject;
+        }
+        for child in element
+            .children()
+            .into_iter()
+            .filter_map(|child| child.element())
+        {
+            self.process(child, &context);
+        }
+    }
+
+    fn push(&mut self, element: Element<'d>, subject: &str, predicate: String, object: RdfaObject) {
+        self.triples.push(RdfaTriple {
+            element,
+            subject: subject.to_string(),
+            predicate,
+            object,
+        });
+    }
+
+    fn blank_node(&mut self) -> String {
+        let node = format!("_:b{}", self.blank_nodes);
+        self.blank_nodes += 1;
+        node
+    }
+
+    fn resolve(&self, iri: &str) -> String {
+        let iri = iri.trim();
+        self.base_url
+            .and_then(|base_url| base_url.join(iri).ok())
+            .map_or_else(|| iri.to_string(), String::from)
+    }
+
+    /// Expands a `resource` value, which may be a safe CURIE `[prefix:name]`
+    /// or a blank node.
+    fn resource(&self, value: &str, context: &Context) -> String {
+        let value = value.trim();
+        if let Some(curie) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+            return expand_curie(curie, context).unwrap_or_default();
+        }
+        if value.starts_with("_:") {
+            return value.to_string();
+        }
+        match value.split_once(':') {
+            Some((prefix, _)) if context.prefixes.contains_key(prefix) => {
+                expand_curie(value, context).unwrap_or_default()
+            }
+            _ => self.resolve(value),
+        }
+    }
+
+    /// Expands the terms, CURIEs and IRIs of a `typeof` or `property`
+    /// attribute, dropping the ones that cannot be expanded.
+    fn terms(&self, value: Option<&str>, context: &Context) -> Vec<String> {
+        value
+            .unwrap_or("")
+            .split_ascii_whitespace()
+            .filter_map(|term| match term.contains(':') {
+                true => expand_curie(term, context).or_else(|| Some(term.to_string())),
+                false => Some(format!("{}{}", context.vocab.as_ref()?, term)),
+            })
+            .collect()
+    }
+}
+
+fn expand_curie(curie: &str, context: &Context) -> Option<String> {
+    let (prefix, reference) = curie.split_once(':')?;
+    if prefix == "_" {
+        return Some(curie.to_string());
+    }
+    let iri = context.prefixes.get(&prefix.to_ascii_lowercase())?;
+    Some(format!("{}{}", iri, reference))
+}
+
+fn literal(element: Element<'_>) -> String {
+    if let Some(content) = element.attribute_value("content") {
+        return content.to_string();
+    }
+    if util::html_local_name(&element) == Some("time") {
+        if let Some(datetime) = element.attribute_value("datetime") {
+            return datetime.to_string();
+        }
+    }
+    Node::Element(element).string_value()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(triples: &[RdfaTriple<'_>]) -> Vec<(String, String, String)> {
+        triples
+            .iter()
+            .map(|triple| {
+                let object = match &triple.object {
+                    RdfaObject::Resource(iri) => format!("<{}>", iri),
+                    RdfaObject::Literal { value, language } => {
+                        format!("{:?}@{}", value, language.as_deref().unwrap_or(""))
+                    }
+                };
+                (triple.subject.clone(), triple.predicate.clone(), object)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_rdfa() {
+        let package = crate::parse_html(concat!(
+            r#"<body vocab="http://schema.org/" prefix="ex: http://example.com/ns#" lang="en">"#,
+            r#"<p property="ex:note dc:title">Hello</p>"#,
+            r#"<div typeof="Event"><span property="name">Party</span>"#,
+            r#"<time property="startDate" datetime="2025-01-01">New year</time>"#,
+            r#"<a property="url" href="/party">link</a>"#,
+            r#"<div property="location" typeof="Place" resource="[ex:hall]"><meta property="name" content="Hall" lang="de"></div>"#,
+            r#"<span property="unknown:x">kept</span></div></body>"#,
+        ));
+        let document_url = Url::parse("https://example.com/page").unwrap();
+        let triples = rdfa(&package, Some(&document_url));
+
+        let schema = |term: &str| format!("http://schema.org/{}", term);
+        let page = "https://example.com/page".to_string();
+        let hall = "http://example.com/ns#hall".to_string();
+        let expected = vec![
+            (
+                page.clone(),
+                "http://example.com/ns#note".to_string(),
+                "\"Hello\"@en".to_string(),
+            ),
+            (
+                page,
+                "http://purl.org/dc/terms/title".to_string(),
+                "\"Hello\"@en".to_string(),
+            ),
+            (
+                "_:b0".to_string(),
+                RDF_TYPE.to_string(),
+                "<http://schema.org/Event>".to_string(),
+            ),
+            (
+                "_:b0".to_string(),
+                schema("name"),
+                "\"Party\"@en".to_string(),
+            ),
+            (
+                "_:b0".to_string(),
+                schema("startDate"),
+                "\"2025-01-01\"@en".to_string(),
+            ),
+            (
+                "_:b0".to_string(),
+                schema("url"),
+                "<https://example.com/party>".to_string(),
+            ),
+            (
+                hall.clone(),
+                RDF_TYPE.to_string(),
+                "<http://schema.org/Place>".to_string(),
+            ),
+            (
+                "_:b0".to_string(),
+                schema("location"),
+                format!("<{}>", hall),
+            ),
+            (hall, schema("name"), "\"Hall\"@de".to_string()),
+            (
+                "_:b0".to_string(),
+                "unknown:x".to_string(),
+                "\"kept\"@en".to_string(),
+            ),
+        ];
+        assert_eq!(summary(&triples), expected);
+    }
+
+    #[test]
+    fn test_resource_sets_subject() {
+        let package = crate::parse_html(concat!(
+            r##"<div vocab="http://schema.org/" resource="#me"><span property="name">Ann</span>"##,
+            r#"<a href="/friend"><span property="name">Bo</span></a></div>"#,
+            r#"<p vocab="http://schema.org/"><span property="name">Page</span></p>"#,
+        ));
+        let document_url = Url::parse("https://example.com/page").unwrap();
+        let triples = rdfa(&package, Some(&document_url));
+
+        let name = "http://schema.org/name".to_string();
+        assert_eq!(
+            summary(&triples),
+            vec![
+                (
+                    "https://example.com/page#me".to_string(),
+                    name.clone(),
+                    "\"Ann\"@".to_string()
+                ),
+                (
+                    "https://example.com/friend".to_string(),
+                    name.clone(),
+                    "\"Bo\"@".to_string()
+                ),
+                (
+                    "https://example.com/page".to_string(),
+                    name,
+                    "\"Page\"@".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_terms_without_vocab() {
+        let package =
+            crate::parse_html(r#"<p property="name">dropped</p><p property="schema:name">x</p>"#);
+        let triples = rdfa(&package, None);
+        assert_eq!(
+            summary(&triples),
+            vec![(
+                String::new(),
+                "http://schema.org/name".to_string(),
+                "\"x\"@".to_string()
+            )]
+        );
+    }
+}
@@ -0,0 +1,414 @@
+use sxd_document::{
+    dom::{ChildOfElement, Element},
+    Package,
+};
+
+use sxd_xpath::nodeset::Node;
+
+use crate::{text, util};
+
+/// Narrowest width that text is wrapped at.
+const MIN_WIDTH: usize = 20;
+
+/// Bullets used for unordered lists, cycled by nesting depth.
+const BULLETS: &[&str] = &["*", "+", "o", "-"];
+
+/// Renders `package` as plain text laid out like a terminal browser such as
+/// lynx or w3m.
+///
+/// Paragraphs are wrapped at `width` columns. Each link is followed by a
+/// bracketed reference number, and the numbered link targets are listed in
+/// a `References` section at the end of the output. Lists get bullets or
+/// numbers, blockquotes are indented, `<pre>` blocks are kept as written and
+/// tables are drawn with ASCII borders. Words longer than `width` are not
+/// broken, and widths below 20 columns are treated as 20.
+pub fn render_text(package: &Package, width: usize) -> String {
+    let document = package.as_document();
+    let children: Vec<ChildOfElement> = document
+        .root()
+        .children()
+        .into_iter()
+        .filter_map(|child| child.element())
+        .map(ChildOfElement::Element)
+        .collect();
+
+    let mut renderer = Renderer::default();
+    let lines = renderer.blocks(&children, width.max(MIN_WIDTH), 0);
+
+    let mut output = lines.join("\n");
+    if !renderer.links.is_empty() {
+        if !output.is_empty() {
+            output.push_str("\n\n");
+        }
+        output.push_str("References\n");
+        for (index, link) in renderer.links.iter().enumerate() {
+            output.push_str(&format!("\n{:>4}. {}", index + 1, link));
+        }
+    }
+    if !output.is_empty() {
+        output.push('\n');
+    }
+    output
+}
+
+#[derive(Debug, Default)]
+struct Renderer {
+    /// Link targets in the order their reference numbers were assigned.
+    links: Vec<String>,
+}
+
+impl Renderer {
+    /// Renders sibling nodes as blocks separated by blank lines, grouping
+    /// runs of inline content into wrapped paragraphs.
+    fn blocks(&mut self, children: &[ChildOfElement], width: usize, depth: usize) -> Vec<String> {
+        // Each block is paired with whether it follows the previous one
+        // without a separating blank line.
+        let mut blocks: Vec<(Vec<String>, bool)> = vec![];
+        let mut inline = String::new();
+
+        for child in children {
+            match child {
+                ChildOfElement::Element(element) if is_block(element) => {
+                    blocks.push((wrap(&inline, width), false));
+                    inline.clear();
+                    // Lists nested in a list item stay attached to the item text.
+                    let tight = depth > 0 && is_list(element);
+                    blocks.push((self.block(*element, width, depth), tight));
+                }
+                child => self.inline(&mut inline, *child),
+            }
+        }
+        blocks.push((wrap(&inline, width), false));
+
+        let mut lines = vec![];
+        for (block, tight) in blocks.into_iter().filter(|(block, _)| !block.is_empty()) {
+            if !lines.is_empty() && !tight {
+                lines.push(String::new());
+            }
+            lines.extend(block);
+        }
+        lines
+    }
+
+    fn block(&mut self, element: Element, width: usize, depth: usize) -> Vec<String> {
+        if text::is_skipped(&element) {
+            return vec![];
+        }
+
+        match util::html_local_name(&element).unwrap_or_default() {
+            name @ ("h1" | "h2") => {
+                let lines = wrap(&self.inline_content(element), width);
+                let underline = if name == "h1" { "=" } else { "-" };
+                let length = lines.iter().map(|line| line.chars().count()).max();
+                match length {
+                    Some(length) => {
+                        let mut lines = lines;
+                        lines.push(underline.repeat(length));
+                        lines
+                    }
+                    None => lines,
+                }
+            }
+            "p" | "h3" | "h4" | "h5" | "h6" => wrap(&self.inline_content(element), width),
+            "hr" => vec!["-".repeat(width)],
+            "pre" => {
+                let content = Node::Element(element).string_value();
+                let content = content.strip_prefix('\n').unwrap_or(&content);
+                content.trim_end().lines().map(str::to_string).collect()
+            }
+            "ul" | "menu" | "dir" => self.list(element, false, width, depth),
+            "ol" => self.list(element, true, width, depth),
+            "blockquote" => {
+                let lines = self.blocks(&element.children(), width.saturating_sub(4), depth);
+                indent(lines, "    ", "    ")
+            }
+            "table" => self.table(element),
+            _ => self.blocks(&element.children(), width, depth),
+        }
+    }
+
+    fn list(&mut self, element: Element, ordered: bool, width: usize, depth: usize) -> Vec<String> {
+        let mut number: i64 = element
+            .attribute_value("start")
+            .and_then(|start| start.trim().parse().ok())
+            .unwrap_or(1);
+        let mut lines = vec![];
+
+        for child in element.children() {
+            let item = match child.element() {
+                Some(item) if util::html_local_name(&item) == Some("li") => item,
+                _ => continue,
+            };
+            if text::is_skipped(&item) {
+                continue;
+            }
+
+            let marker = if ordered {
+                format!("{}. ", number)
+            } else {
+                format!("{} ", BULLETS[depth % BULLETS.len()])
+            };
+            number += 1;
+
+            let item_width = width.saturating_sub(marker.len() + 2);
+            let item_lines = self.blocks(&item.children(), item_width, depth + 1);
+            let padding = " ".repeat(marker.len() + 2);
+            let first = format!("  {}", marker);
+            lines.extend(indent(item_lines, &first, &padding));
+        }
+
+        lines
+    }
+
+    fn table(&mut self, element: Element) -> Vec<String> {
+        let mut rows: Vec<(Vec<String>, bool)> = vec![];
+        for child in element.children() {
+            let section = match child.element() {
+                Some(section) => section,
+                None => continue,
+            };
+            match util::html_local_name(&section) {
+                Some("tr") => rows.push(self.table_row(section)),
+                Some("thead" | "tbody" | "tfoot") => {
+                    for row in section.children().iter().filter_map(|row| row.element()) {
+                        if util::html_local_name(&row) == Some("tr") {
+                            rows.push(self.table_row(row));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let columns = rows.iter().map(|(cells, _)| cells.len()).max().unwrap_or(0);
+        if columns == 0 {
+            return vec![];
+        }
+        let mut widths = vec![0; columns];
+        for (cells, _) in &rows {
+            for (index, cell) in cells.iter().enumerate() {
+                widths[index] = widths[index].max(cell.chars().count());
+            }
+        }
+
+        let border = |fill: &str| {
+            let segments: Vec<String> = widths.iter().map(|width| fill.repeat(width + 2)).collect();
+            format!("+{}+", segments.join("+"))
+        };
+
+        let mut lines = vec![border("-")];
+        for (cells, header) in &rows {
+            let padded: Vec<String> = widths
+                .iter()
+                .enumerate()
+                .map(|(index, width)| {
+                    let cell = cells.get(index).map(String::as_str).unwrap_or_default();
+                    let padding = width - cell.chars().count();
+                    format!(" {}{} ", cell, " ".repeat(padding))
+                })
+                .collect();
+            lines.push(format!("|{}|", padded.join("|")));
+            if *header {
+                lines.push(border("="));
+            }
+        }
+        lines.push(border("-"));
+        lines
+    }
+
+    /// Returns the cell texts of a row, and whether the row only holds
+    /// header cells.
+    fn table_row(&mut self, row: Element) -> (Vec<String>, bool) {
+        let cells: Vec<Element> = row
+            .children()
+            .iter()
+            .filter_map(|cell| cell.element())
+            .filter(|cell| matches!(util::html_local_name(cell), Some("td" | "th")))
+            .collect();
+        let header = !cells.is_empty()
+            && cells
+                .iter()
+                .all(|cell| util::html_local_name(cell) == Some("th"));
+        let texts = cells
+            .into_iter()
+            .map(|cell| {
+                let content = self.inline_content(cell);
+                content.split_whitespace().collect::<Vec<_>>().join(" ")
+            })
+            .collect();
+        (texts, header)
+    }
+
+    fn inline_content(&mut self, element: Element) -> String {
+        let mut inline = String::new();
+        for child in element.children() {
+            self.inline(&mut inline, child);
+        }
+        inline
+    }
+
+    fn inline(&mut self, output: &mut String, node: ChildOfElement) {
+        let element = match node {
+            ChildOfElement::Text(text) => {
+                output.push_str(&text.text().replace(|c: char| c.is_ascii_whitespace(), " "));
+                return;
+            }
+            ChildOfElement::Element(element) => element,
+            ChildOfElement::Comment(_) | ChildOfElement::ProcessingInstruction(_) => return,
+        };
+        if text::is_skipped(&element) {
+            return;
+        }
+
+        match util::html_local_name(&element).unwrap_or_default() {
+            "br" => output.push('\n'),
+            "img" => {
+                let alt = element.attribute_value("alt").unwrap_or_default().trim();
+                if alt.is_empty() {
+                    output.push_str("[IMAGE]");
+                } else {
+                    output.push_str(&format!("[{}]", alt));
+                }
+            }
+            "a" => {
+                for child in element.children() {
+                    self.inline(output, child);
+                }
+                if let Some(href) = element.attribute_value("href") {
+                    self.links.push(href.trim().to_string());
+                    output.push_str(&format!("[{}]", self.links.len()));
+                }
+            }
+            _ => {
+                for child in element.children() {
+                    self.inline(output, child);
+                }
+            }
+        }
+    }
+}
+
+fn is_block(element: &Element) -> bool {
+    util::html_local_name(element)
+        .is_some_and(|name| text::BLOCK_ELEMENTS.contains(&name) || name == "p")
+}
+
+fn is_list(element: &Element) -> bool {
+    matches!(
+        util::html_local_name(element),
+        Some("ul" | "ol" | "menu" | "dir")
+    )
+}
+
+/// Wraps inline text at `width` columns, honouring line breaks from `<br>`.
+fn wrap(inline: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![];
+    for segment in inline.split('\n') {
+        let mut line = String::new();
+        let mut line_width = 0;
+        for word in segment.split_whitespace() {
+            let word_width = word.chars().count();
+            if line_width > 0 && line_width + 1 + word_width > width {
+                lines.push(std::mem::take(&mut line));
+                line_width = 0;
+            }
+            if line_width > 0 {
+                line.push(' ');
+                line_width += 1;
+            }
+            line.push_str(word);
+            line_width += word_width;
+        }
+        lines.push(line);
+    }
+
+    // Only drop the empty lines at the edges; inner ones come from <br>.
+    while lines.last().is_some_and(String::is_empty) {
+        lines.pop();
+    }
+    let leading = lines.iter().take_while(|line| line.is_empty()).count();
+    lines.drain(..leading);
+    lines
+}
+
+fn indent(lines: Vec<String>, first: &str, rest: &str) -> Vec<String> {
+    lines
+        .into_iter()
+        .enumerate()
+        .map(|(index, line)| {
+            if line.is_empty() {
+                line
+            } else if index == 0 {
+                format!("{}{}", first, line)
+            } else {
+                format!("{}{}", rest, line)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrapping_and_references() {
+        let package = crate::parse_html(concat!(
+            "<h1>News</h1>",
+            "<p>The quick brown fox jumps over the <a href=\"/dog\">lazy dog</a> again.</p>",
+        ));
+        assert_eq!(
+            render_text(&package, 20),
+            concat!(
+                "News\n",
+                "====\n",
+                "\n",
+                "The quick brown fox\n",
+                "jumps over the lazy\n",
+                "dog[1] again.\n",
+                "\n",
+                "References\n",
+                "\n",
+                "   1. /dog\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_lists_and_blockquote() {
+        let package = crate::parse_html(concat!(
+            "<ul><li>one<ul><li>nested</li></ul></li><li>two</li></ul>",
+            "<ol><li>first</li></ol>",
+            "<blockquote>quoted</blockquote>",
+        ));
+        assert_eq!(
+            render_text(&package, 40),
+            concat!(
+                "  * one\n",
+                "      + nested\n",
+                "  * two\n",
+                "\n",
+                "  1. first\n",
+                "\n",
+                "    quoted\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_ascii_table() {
+        let package = crate::parse_html(
+            "<table><tr><th>name</th><th>n</th></tr><tr><td>a</td><td>10</td></tr></table>",
+        );
+        assert_eq!(
+            render_text(&package, 40),
+            concat!(
+                "+------+----+\n",
+                "| name | n  |\n",
+                "+======+====+\n",
+                "| a    | 10 |\n",
+                "+------+----+\n",
+            )
+        );
+    }
+}
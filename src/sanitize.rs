@@ -0,0 +1,464 @@
+use std::collections::{HashMap, HashSet};
+
+use sxd_document::dom::{ChildOfElement, Element};
+
+use crate::util;
+
+/// The elements the default policy allows, with their allowed attributes.
+const DEFAULT_ELEMENTS: &[(&str, &[&str])] = &[
+    ("a", &["href", "hreflang"]),
+    ("abbr", &[]),
+    ("b", &[]),
+    ("blockquote", &["cite"]),
+    ("br", &[]),
+    ("caption", &[]),
+    ("cite", &[]),
+    ("code", &[]),
+    ("col", &["span"]),
+    ("colgroup", &["span"]),
+    ("dd", &[]),
+    ("del", &["cite", "datetime"]),
+    ("details", &["open"]),
+    ("dfn", &[]),
+    ("div", &[]),
+    ("dl", &[]),
+    ("dt", &[]),
+    ("em", &[]),
+    ("figcaption", &[]),
+    ("figure", &[]),
+    ("h1", &[]),
+    ("h2", &[]),
+    ("h3", &[]),
+    ("h4", &[]),
+    ("h5", &[]),
+    ("h6", &[]),
+    ("hr", &[]),
+    ("i", &[]),
+    ("img", &["src", "alt", "width", "height"]),
+    ("ins", &["cite", "datetime"]),
+    ("kbd", &[]),
+    ("li", &["value"]),
+    ("mark", &[]),
+    ("ol", &["start", "reversed", "type"]),
+    ("p", &[]),
+    ("pre", &[]),
+    ("q", &["cite"]),
+    ("rp", &[]),
+    ("rt", &[]),
+    ("ruby", &[]),
+    ("s", &[]),
+    ("samp", &[]),
+    ("small", &[]),
+    ("span", &[]),
+    ("strong", &[]),
+    ("sub", &[]),
+    ("summary", &[]),
+    ("sup", &[]),
+    ("table", &[]),
+    ("tbody", &[]),
+    ("td", &["colspan", "rowspan"]),
+    ("tfoot", &[]),
+    ("th", &["colspan", "rowspan", "scope"]),
+    ("thead", &[]),
+    ("time", &["datetime"]),
+    ("tr", &[]),
+    ("u", &[]),
+    ("ul", &[]),
+    ("var", &[]),
+    ("wbr", &[]),
+];
+
+/// The attributes the default policy allows on every allowed element.
+const DEFAULT_GLOBAL_ATTRIBUTES: &[&str] = &["dir", "lang", "title"];
+
+/// The elements the default policy removes with their contents.
+const DEFAULT_REMOVED_ELEMENTS: &[&str] = &[
+    "applet", "base", "embed", "frame", "frameset", "head", "iframe", "link", "math", "meta",
+    "noembed", "noframes", "noscript", "object", "script", "style", "svg", "template", "title",
+    "xmp",
+];
+
+/// The URL schemes the default policy allows.
+const DEFAULT_URL_SCHEMES: &[&str] = &["http", "https", "mailto"];
+
+/// Attributes whose value is a URL.
+pub(crate) const URL_ATTRIBUTES: &[&str] = &[
+    "action",
+    "background",
+    "cite",
+    "codebase",
+    "data",
+    "formaction",
+    "href",
+    "longdesc",
+    "manifest",
+    "ping",
+    "poster",
+    "src",
+    "usemap",
+];
+
+/// What [`sanitize`] keeps.
+///
+/// The [default](Default) policy is a basic allowlist for user-submitted
+/// text: formatting, lists, tables, links and images, with `http`, `https`
+/// and `mailto` URLs and without styles, scripts, forms or embedded content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SanitizePolicy {
+    /// The allowed elements, by lowercase name, with the attributes allowed
+    /// on each. Other HTML elements are unwrapped: replaced by their
+    /// sanitized contents.
+    pub elements: HashMap<String, HashSet<String>>,
+    /// The attributes allowed on every allowed element.
+    pub global_attributes: HashSet<String>,
+    /// The disallowed elements removed with their contents rather than
+    /// unwrapped. SVG and MathML elements are always removed.
+    pub removed_elements: HashSet<String>,
+    /// The allowed schemes of URL attributes such as `href` and `src`, in
+    /// lowercase. Relative URLs are always allowed.
+    pub url_schemes: HashSet<String>,
+    /// Whether `on*` event handler attributes are removed even if allowed.
+    pub strip_event_handlers: bool,
+    /// Whether `style` attributes are removed even if allowed.
+    pub strip_style: bool,
+    /// Whether comments are kept.
+    pub allow_comments: bool,
+}
+
+impl Default for SanitizePolicy {
+    fn default() -> Self {
+        let set = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        Self {
+            elements: DEFAULT_ELEMENTS
+                .iter()
+                .map(|(name, attributes)| (name.to_string(), set(attributes)))
+                .collect(),
+            global_attributes: set(DEFAULT_GLOBAL_ATTRIBUTES),
+            removed_elements: set(DEFAULT_REMOVED_ELEMENTS),
+            url_schemes: set(DEFAULT_URL_SCHEMES),
+            strip_event_handlers: true,
+            strip_style: true,
+            allow_comments: false,
+        }
+    }
+}
+
+impl SanitizePolicy {
+    /// Allows the element `name` with the attributes `attributes`, adding
+    /// to the attributes already allowed on it.
+    pub fn allow_element(&mut self, name: &str, attributes: &[&str]) -> &mut Self {
+        self.removed_elements.remove(name);
+        self.elements
+            .entry(name.to_string())
+            .or_default()
+            .extend(attributes.iter().map(|attribute| attribute.to_string()));
+        self
+    }
+
+    fn allows_attribute(&self, element: &str, attribute: &str) -> bool {
+        if self.strip_event_handlers && attribute.starts_with("on") {
+            return false;
+        }
+        if self.strip_style && attribute == "style" {
+            return false;
+        }
+        self.global_attributes.contains(attribute)
+            || self
+                .elements
+                .get(element)
+                .is_some_and(|attributes| attributes.contains(attribute))
+    }
+
+    /// Returns whether the URL `value` is relative or has an allowed scheme.
+    fn allows_url(&self, value: &str) -> bool {
+        match url_scheme(value) {
+            Some(scheme) => self.url_schemes.contains(&scheme),
+            None => true,
+        }
+    }
+}
+
+/// Something [`sanitize`] removed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Removal {
+    /// An element removed with its contents.
+    Element { name: String },
+    /// A disallowed element replaced by its contents.
+    Unwrapped { name: String },
+    /// An attribute not allowed on its element.
+    Attribute {
+        element: String,
+        name: String,
+        value: String,
+    },
+    /// A URL attribute whose scheme is not allowed, such as
+    /// `javascript:alert(1)`.
+    Url {
+        element: String,
+        attribute: String,
+        url: String,
+    },
+    /// A comment.
+    Comment { text: String },
+    /// A processing instruction.
+    ProcessingInstruction { target: String },
+}
+
+/// What [`sanitize`] removed, in document order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SanitizeReport {
+    /// The elements, attributes and other nodes removed.
+    pub removed: Vec<Removal>,
+}
+
+impl SanitizeReport {
+    /// Returns whether nothing was removed.
+    pub fn is_clean(&self) -> bool {
+        self.removed.is_empty()
+    }
+}
+
+/// Sanitizes the contents of `element` in place with `policy`.
+///
+/// Disallowed elements are unwrapped, or removed with their contents if
+/// they are listed in [`SanitizePolicy::removed_elements`] or are SVG or
+/// MathML elements. Allowed elements keep only their allowed attributes,
+/// and URL attributes whose scheme is not allowed are removed. `element`
+/// itself is kept as is: pass the `body` of a document, or use
+/// [`sanitize_html`] for markup.
+///
+/// ```rust
+/// let package = sxd_html::parse_html(r#"<p onclick="steal()">Hi <a href="javascript:go()">there</a></p>"#);
+/// let root = package.as_document().root();
+/// let body = sxd_html::select_one(root, "//body").unwrap().element().unwrap();
+/// let report = sxd_html::sanitize(body, &Default::default());
+/// assert_eq!(sxd_html::inner_html(body), "<p>Hi <a>there</a></p>");
+/// assert_eq!(report.removed.len(), 2);
+/// ```
+pub fn sanitize(element: Element<'_>, policy: &SanitizePolicy) -> SanitizeReport {
+    let mut report = SanitizeReport::default();
+    sanitize_children(element, policy, &mut report);
+    report
+}
+
+/// Sanitizes the HTML fragment `contents` with `policy`, returning the
+/// sanitized markup and what was removed.
+///
+/// ```rust
+/// let (html, report) = sxd_html::sanitize_html(
+///     r#"<b style="color: red">bold</b><script>alert(1)</script><blink>text</blink>"#,
+///     &Default::default(),
+/// );
+/// assert_eq!(html, "<b>bold</b>text");
+/// assert_eq!(report.removed.len(), 3);
+/// ```
+pub fn sanitize_html(contents: &str, policy: &SanitizePolicy) -> (String, SanitizeReport) {
+    let package = crate::parse_html_fragment(contents);
+    let root = package.as_document().root();
+    let mut report = SanitizeReport::default();
+    let mut html = String::new();
+    for element in root
+        .children()
+        .into_iter()
+        .filter_map(|child| child.element())
+    {
+        sanitize_children(element, policy, &mut report);
+        html.push_str(&crate::inner_html(element));
+    }
+    (html, report)
+}
+
+fn sanitize_children(parent: Element<'_>, policy: &SanitizePolicy, report: &mut SanitizeReport) {
+    let mut children = vec![];
+    let mut changed = false;
+    for child in parent.children() {
+        match child {
+            ChildOfElement::Element(element) => {
+                let name = util::html_local_name(&element);
+                match name {
+                    Some(name) if policy.elements.contains_key(name) => {
+                        sanitize_attributes(element, name, policy, report);
+                        sanitize_children(element, policy, report);
+                        children.push(child);
+                    }
+                    Some(name) if !policy.removed_elements.contains(name) => {
+                        report.removed.push(Removal::Unwrapped {
+                            name: name.to_string(),
+                        });
+                        sanitize_children(element, policy, report);
+                        children.extend(element.children());
+                        element.clear_children();
+                        changed = true;
+                    }
+                    _ => {
+                        report.removed.push(Removal::Element {
+                            name: element.name().local_part().to_string(),
+                        });
+                        changed = true;
+                    }
+                }
+            }
+            ChildOfElement::Text(_) => children.push(child),
+            ChildOfElement::Comment(_) if policy.allow_comments => children.push(child),
+            ChildOfElement::Comment(comment) => {
+                report.removed.push(Removal::Comment {
+                    text: comment.text().to_string(),
+                });
+                changed = true;
+            }
+            ChildOfElement::ProcessingInstruction(pi) => {
+                report.removed.push(Removal::ProcessingInstruction {
+                    target: pi.target().to_string(),
+                });
+                changed = true;
+            }
+        }
+    }
+    if changed {
+        parent.replace_children(children);
+    }
+}
+
+fn sanitize_attributes(
+    element: Element<'_>,
+    name: &str,
+    policy: &SanitizePolicy,
+    report: &mut SanitizeReport,
+) {
+    for attribute in element.attributes() {
+        let attribute_name = attribute.name();
+        let local_name = attribute_name.local_part();
+        let allowed =
+            attribute_name.namespace_uri().is_none() && policy.allows_attribute(name, local_name);
+        if !allowed {
+            report.removed.push(Removal::Attribute {
+                element: name.to_string(),
+                name: local_name.to_string(),
+                value: attribute.value().to_string(),
+            });
+            element.remove_attribute(attribute_name);
+            continue;
+        }
+
+        let urls: Vec<&str> = if local_name == "srcset" {
+            crate::links::srcset_urls(attribute.value())
+        } else if URL_ATTRIBUTES.contains(&local_name) {
+            vec![attribute.value()]
+        } else {
+            vec![]
+        };
+        if urls.into_iter().any(|url| !policy.allows_url(url)) {
+            report.removed.push(Removal::Url {
+                element: name.to_string(),
+                attribute: local_name.to_string(),
+                url: attribute.value().to_string(),
+            });
+            element.remove_attribute(attribute_name);
+        }
+    }
+}
+
+/// Returns the lowercase scheme of the URL attribute value `value`, if it
+/// is absolute, as a browser reads it.
+pub(crate) fn url_scheme(value: &str) -> Option<String> {
+    // Browsers ignore tabs and line breaks in URLs, and leading and
+    // trailing control characters and spaces.
+    let url: String = value
+        .trim_matches(|c: char| c <= ' ')
+        .chars()
+        .filter(|c| !matches!(c, '\t' | '\n' | '\r'))
+        .collect();
+    let (scheme, _) = url.split_once(':')?;
+    let mut chars = scheme.chars();
+    let valid = chars.next()?.is_ascii_alphabetic()
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    valid.then(|| scheme.to_ascii_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_policy() {
+        let (html, report) = sanitize_html(
+            concat!(
+                r#"<div class="x" onmouseover="evil()"><a href=" JaVa&#9;Script:alert(1)" title="t">a</a>"#,
+                r#"<a href="/relative?x=a:b">b</a><img src="data:image/png;base64,AAAA" alt="i">"#,
+                r#"<form action="/post"><input name="q"><button>Go</button></form>"#,
+                r#"<svg><script>alert(2)</script></svg><!-- note --></div>"#,
+            ),
+            &SanitizePolicy::default(),
+        );
+        assert_eq!(
+            html,
+            r#"<div><a title="t">a</a><a href="/relative?x=a:b">b</a><img alt="i">Go</div>"#
+        );
+        assert_eq!(
+            report.removed,
+            vec![
+                Removal::Attribute {
+                    element: "div".to_string(),
+                    name: "class".to_string(),
+                    value: "x".to_string(),
+                },
+                Removal::Attribute {
+                    element: "div".to_string(),
+                    name: "onmouseover".to_string(),
+                    value: "evil()".to_string(),
+                },
+                Removal::Url {
+                    element: "a".to_string(),
+                    attribute: "href".to_string(),
+                    url: " JaVa\tScript:alert(1)".to_string(),
+                },
+                Removal::Url {
+                    element: "img".to_string(),
+                    attribute: "src".to_string(),
+                    url: "data:image/png;base64,AAAA".to_string(),
+                },
+                Removal::Unwrapped {
+                    name: "form".to_string(),
+                },
+                Removal::Unwrapped {
+                    name: "input".to_string(),
+                },
+                Removal::Unwrapped {
+                    name: "button".to_string(),
+                },
+                Removal::Element {
+                    name: "svg".to_string(),
+                },
+                Removal::Comment {
+                    text: " note ".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_custom_policy() {
+        let mut policy = SanitizePolicy::default();
+        policy
+            .allow_element("span", &["style", "onclick", "class"])
+            .allow_element("style", &[])
+            .allow_element("img", &["srcset"]);
+        policy.url_schemes.insert("data".to_string());
+        policy.allow_comments = true;
+
+        let contents = r#"<span style="color: red" onclick="f()" class="c">x</span><style>p {}</style><!--c--><img srcset="a.png 1x, javascript:x 2x" src="data:,">"#;
+        let (html, report) = sanitize_html(contents, &policy);
+        assert_eq!(
+            html,
+            r#"<span class="c">x</span><style>p {}</style><!--c--><img src="data:,">"#
+        );
+        assert_eq!(report.removed.len(), 3);
+
+        policy.strip_event_handlers = false;
+        policy.strip_style = false;
+        let (html, report) = sanitize_html(contents, &policy);
+        assert!(html.starts_with(r#"<span style="color: red" onclick="f()" class="c">"#));
+        assert_eq!(report.removed.len(), 1);
+        assert!(!report.is_clean());
+    }
+}
@@ -0,0 +1,177 @@
+use sxd_document::{
+    dom::{Attribute, ChildOfElement, Element},
+    QName,
+};
+use sxd_xpath::nodeset::Node;
+
+use crate::util;
+
+const XLINK_NAMESPACE: &str = "http://www.w3.org/1999/xlink";
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+const XMLNS_NAMESPACE: &str = "http://www.w3.org/2000/xmlns/";
+
+/// Elements that never have an end tag.
+pub(crate) const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "basefont", "bgsound", "br", "col", "embed", "frame", "hr", "img", "input",
+    "keygen", "link", "meta", "param", "source", "track", "wbr",
+];
+
+/// Elements whose text content is serialized without escaping.
+const RAW_TEXT_ELEMENTS: &[&str] = &[
+    "style",
+    "script",
+    "xmp",
+    "iframe",
+    "noembed",
+    "noframes",
+    "plaintext",
+    "noscript",
+];
+
+/// Serializes `node` and its descendants as HTML markup.
+///
+/// This follows the HTML fragment serialization algorithm: void elements
+/// such as `<br>` have no end tag, the contents of `<script>` and `<style>`
+/// are written unescaped, and attribute values are always double quoted.
+/// Serializing an element includes the element itself, while serializing the
+/// document root writes its children.
+pub fn to_html<'d>(node: impl Into<Node<'d>>) -> String {
+    let mut output = String::new();
+    match node.into() {
+        Node::Root(root) => {
+            for child in root.children() {
+                write_child(&mut output, child.into(), false);
+            }
+        }
+        Node::Element(element) => write_element(&mut output, element),
+        Node::Text(text) => escape(&mut output, text.text(), false),
+        Node::Comment(comment) => write_child(&mut output, ChildOfElement::Comment(comment), false),
+        Node::ProcessingInstruction(pi) => write_child(
+            &mut output,
+            ChildOfElement::ProcessingInstruction(pi),
+            false,
+        ),
+        Node::Attribute(attribute) => escape(&mut output, attribute.value(), true),
+        Node::Namespace(namespace) => escape(&mut output, namespace.uri(), true),
+    }
+    output
+}
+
+/// Serializes the children of `element` as HTML markup, like the DOM
+/// `innerHTML` property.
+pub fn inner_html(element: Element) -> String {
+    let mut output = String::new();
+    write_children(&mut output, element);
+    output
+}
+
+fn write_element(output: &mut String, element: Element) {
+    let name = element.name().local_part();
+    output.push('<');
+    output.push_str(name);
+    for attribute in element.attributes() {
+        output.push(' ');
+        output.push_str(&attribute_name(&attribute));
+        output.push_str("=\"");
+        escape(output, attribute.value(), true);
+        output.push('"');
+    }
+    output.push('>');
+
+    let html_name = util::html_local_name(&element);
+    if html_name.is_some_and(|name| VOID_ELEMENTS.contains(&name)) {
+        return;
+    }
+
+    write_children(output, element);
+
+    output.push_str("</");
+    output.push_str(name);
+    output.push('>');
+}
+
+fn write_children(output: &mut String, element: Element) {
+    let raw = util::html_local_name(&element).is_some_and(|name| RAW_TEXT_ELEMENTS.contains(&name));
+    for child in element.children() {
+        write_child(output, child, raw);
+    }
+}
+
+fn write_child(output: &mut String, child: ChildOfElement, raw: bool) {
+    match child {
+        ChildOfElement::Element(element) => write_element(output, element),
+        ChildOfElement::Text(text) if raw => output.push_str(text.text()),
+        ChildOfElement::Text(text) => escape(output, text.text(), false),
+        ChildOfElement::Comment(comment) => {
+            output.push_str("<!--");
+            output.push_str(comment.text());
+            output.push_str("-->");
+        }
+        ChildOfElement::ProcessingInstruction(pi) => {
+            output.push_str("<?");
+            output.push_str(pi.target());
+            if let Some(value) = pi.value() {
+                output.push(' ');
+                output.push_str(value);
+            }
+            output.push('>');
+        }
+    }
+}
+
+/// Returns the serialized name of an attribute, restoring the prefixes of
+/// the namespaced attributes HTML allows on foreign elements.
+fn attribute_name(attribute: &Attribute) -> String {
+    let name: QName = attribute.name();
+    let prefix = match name.namespace_uri() {
+        Some(XLINK_NAMESPACE) => Some("xlink"),
+        Some(XML_NAMESPACE) => Some("xml"),
+        Some(XMLNS_NAMESPACE) if name.local_part() != "xmlns" => Some("xmlns"),
+        _ => None,
+    };
+    match prefix {
+        Some(prefix) => format!("{}:{}", prefix, name.local_part()),
+        None => name.local_part().to_string(),
+    }
+}
+
+fn escape(output: &mut String, text: &str, attribute_mode: bool) {
+    for c in text.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '\u{a0}' => output.push_str("&nbsp;"),
+            '"' if attribute_mode => output.push_str("&quot;"),
+            '<' if !attribute_mode => output.push_str("&lt;"),
+            '>' if !attribute_mode => output.push_str("&gt;"),
+            c => output.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let html = concat!(
+            r#"<html><head><script>if (a < b) {}</script></head>"#,
+            r#"<body><p class="x" title="&quot;q&quot;">a &amp; b<br>c</p><!-- note -->"#,
+            r##"<svg><use xlink:href="#i"></use></svg></body></html>"##,
+        );
+        let package = crate::parse_html(html);
+        assert_eq!(to_html(package.as_document().root()), html);
+    }
+
+    #[test]
+    fn test_inner_html() {
+        let package = crate::parse_html("<div id=\"d\"><b>bold</b> text</div>");
+        let document = package.as_document();
+        let body = document.root().children()[0].element().unwrap().children()[1]
+            .element()
+            .unwrap();
+        let div = body.children()[0].element().unwrap();
+        assert_eq!(inner_html(div), "<b>bold</b> text");
+        assert_eq!(to_html(div), "<div id=\"d\"><b>bold</b> text</div>");
+    }
+}
@@ -0,0 +1,529 @@
+use std::fmt;
+
+use serde_json::{Map, Number, Value as JsonValue};
+use sxd_document::Package;
+use sxd_xpath::{nodeset::Node, Factory, Value};
+
+use crate::{
+    extract::{select, within, Selected},
+    ExtractError, ExtractErrorKind,
+};
+
+/// Errors returned when an [`ExtractionSpec`] cannot be loaded.
+#[derive(Debug)]
+pub enum SpecError {
+    /// The spec is not valid JSON.
+    Json(serde_json::Error),
+    /// The spec is not valid TOML.
+    #[cfg(feature = "toml")]
+    Toml(toml::de::Error),
+    /// The spec is well-formed, but not a valid spec. `path` is the
+    /// location of the problem, such as `fields.repos.fields.name`.
+    Invalid { path: String, message: String },
+}
+
+impl SpecError {
+    fn invalid(path: &str, message: impl Into<String>) -> Self {
+        Self::Invalid {
+            path: path.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for SpecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Json(error) => write!(f, "invalid JSON spec: {}", error),
+            #[cfg(feature = "toml")]
+            Self::Toml(error) => write!(f, "invalid TOML spec: {}", error),
+            Self::Invalid { path, message } => write!(f, "invalid spec at `{}`: {}", path, message),
+        }
+    }
+}
+
+impl std::error::Error for SpecError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Json(error) => Some(error),
+            #[cfg(feature = "toml")]
+            Self::Toml(error) => Some(error),
+            Self::Invalid { .. } => None,
+        }
+    }
+}
+
+/// How many matches a field holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cardinality {
+    One,
+    Optional,
+    Many,
+}
+
+#[derive(Debug)]
+struct FieldSpec {
+    name: String,
+    /// The expression as written in the spec.
+    expression: String,
+    /// The expression as XPath, translated if it is a CSS selector.
+    xpath: String,
+    cardinality: Cardinality,
+    attribute: Option<String>,
+    trim: bool,
+    #[cfg(feature = "regex")]
+    regex: Option<regex::Regex>,
+    number: bool,
+    fields: Option<Vec<FieldSpec>>,
+}
+
+/// A set of named fields to extract from a document, loaded from JSON or
+/// TOML so that scrapers can change without recompiling.
+///
+/// A spec is an object with a `fields` table. Each field has an `xpath` or
+/// `css` expression, evaluated with the node of the enclosing scope as the
+/// context node, and these optional keys:
+///
+/// * `cardinality`: `"one"` (the default) requires a match, `"optional"`
+///   yields `null` when nothing matches and `"many"` yields an array of
+///   every match in document order.
+/// * `fields`: extracts an object from each matched node, using the node
+///   as the scope of the nested fields.
+/// * `attribute`: uses the value of an attribute of the matched elements,
+///   which needs an expression that selects nodes.
+/// * `trim`: trims whitespace from the text.
+/// * `regex`: replaces the text with the first capture group of a regular
+///   expression, or the whole match if it has no groups. Text that does not
+///   match is treated as missing. This needs the `regex` feature.
+/// * `number`: converts the text to a JSON number.
+///
+/// The post-processing keys apply in the order listed and cannot be
+/// combined with `fields`. Extracted objects list their fields in the order
+/// of the spec.
+///
+/// ```rust
+/// let spec = sxd_html::ExtractionSpec::from_json(r#"{
+///     "fields": {
+///         "title": { "css": "h1", "trim": true },
+///         "links": {
+///             "xpath": "//a",
+///             "cardinality": "many",
+///             "fields": {
+///                 "text": { "xpath": "." },
+///                 "href": { "xpath": "@href" }
+///             }
+///         }
+///     }
+/// }"#).unwrap();
+///
+/// let package = sxd_html::parse_html("<h1> Title </h1><a href='/a'>A</a>");
+/// assert_eq!(
+///     spec.extract(&package).unwrap(),
+///     serde_json::json!({ "title": "Title", "links": [{ "text": "A", "href": "/a" }] })
+/// );
+/// ```
+#[derive(Debug)]
+pub struct ExtractionSpec {
+    fields: Vec<FieldSpec>,
+}
+
+impl ExtractionSpec {
+    /// Loads a spec from JSON.
+    pub fn from_json(json: &str) -> Result<Self, SpecError> {
+        let value = serde_json::from_str(json).map_err(SpecError::Json)?;
+        Self::from_value(&value)
+    }
+
+    /// Loads a spec from TOML.
+    #[cfg(feature = "toml")]
+    pub fn from_toml(toml: &str) -> Result<Self, SpecError> {
+        let value: JsonValue = toml::from_str(toml).map_err(SpecError::Toml)?;
+        Self::from_value(&value)
+    }
+
+    /// Loads a spec from a JSON value.
+    pub fn from_value(value: &JsonValue) -> Result<Self, SpecError> {
+        let spec = object(value, "")?;
+        check_keys(spec, "", &["fields"])?;
+        let fields = spec
+            .get("fields")
+            .ok_or_else(|| SpecError::invalid("", "missing `fields`"))?;
+        Ok(Self {
+            fields: parse_fields(fields, "fields")?,
+        })
+    }
+
+    /// Extracts the fields from the document in `package`.
+    pub fn extract(&self, package: &Package) -> Result<JsonValue, ExtractError> {
+        self.extract_node(package.as_document().root())
+    }
+
+    /// Extracts the fields with `node` as the scope.
+    pub fn extract_node<'d>(&self, node: impl Into<Node<'d>>) -> Result<JsonValue, ExtractError> {
+        extract_fields(&self.fields, node.into())
+    }
+}
+
+fn object<'a>(value: &'a JsonValue, path: &str) -> Result<&'a Map<String, JsonValue>, SpecError> {
+    value
+        .as_object()
+        .ok_or_else(|| SpecError::invalid(path, "expected a table"))
+}
+
+fn check_keys(object: &Map<String, JsonValue>, path: &str, keys: &[&str]) -> Result<(), SpecError> {
+    match object.keys().find(|key| !keys.contains(&key.as_str())) {
+        Some(key) => Err(SpecError::invalid(path, format!("unknown key `{}`", key))),
+        None => Ok(()),
+    }
+}
+
+fn string(
+    object: &Map<String, JsonValue>,
+    path: &str,
+    key: &str,
+) -> Result<Option<String>, SpecError> {
+    match object.get(key) {
+        None => Ok(None),
+        Some(JsonValue::String(value)) => Ok(Some(value.clone())),
+        Some(_) => Err(SpecError::invalid(
+            path,
+            format!("`{}` must be a string", key),
+        )),
+    }
+}
+
+fn boolean(object: &Map<String, JsonValue>, path: &str, key: &str) -> Result<bool, SpecError> {
+    match object.get(key) {
+        None => Ok(false),
+        Some(JsonValue::Bool(value)) => Ok(*value),
+        Some(_) => Err(SpecError::invalid(
+            path,
+            format!("`{}` must be a boolean", key),
+        )),
+    }
+}
+
+fn parse_fields(value: &JsonValue, path: &str) -> Result<Vec<FieldSpec>, SpecError> {
+    object(value, path)?
+        .iter()
+        .map(|(name, field)| parse_field(name, field, &format!("{}.{}", path, name)))
+        .collect()
+}
+
+fn parse_field(name: &str, value: &JsonValue, path: &str) -> Result<FieldSpec, SpecError> {
+    let field = object(value, path)?;
+    check_keys(
+        field,
+        path,
+        &[
+            "xpath",
+            "css",
+            "cardinality",
+            "attribute",
+            "trim",
+            "regex",
+            "number",
+            "fields",
+        ],
+    )?;
+
+    let (expression, xpath) = match (string(field, path, "xpath")?, string(field, path, "css")?) {
+        (Some(xpath), None) => {
+            match Factory::new().build(&xpath) {
+                Ok(Some(_)) => {}
+                Ok(None) => return Err(SpecError::invalid(path, "`xpath` is empty")),
+                Err(error) => {
+                    return Err(SpecError::invalid(
+                        path,
+                        format!("invalid XPath `{}`: {}", xpath, error),
+                    ))
+                }
+            }
+            (xpath.clone(), xpath)
+        }
+        (None, Some(css)) => {
+            let xpath = crate::css_to_xpath(&css).map_err(|error| {
+                SpecError::invalid(path, format!("invalid CSS selector `{}`: {}", css, error))
+            })?;
+            (css, xpath)
+        }
+        _ => {
+            return Err(SpecError::invalid(
+                path,
+                "a field needs either `xpath` or `css`",
+            ))
+        }
+    };
+
+    let cardinality = match string(field, path, "cardinality")?.as_deref() {
+        None | Some("one") => Cardinality::One,
+        Some("optional") => Cardinality::Optional,
+        Some("many") => Cardinality::Many,
+        Some(other) => {
+            return Err(SpecError::invalid(
+                path,
+                format!(
+                    "unknown cardinality `{}`, expected `one`, `optional` or `many`",
+                    other
+                ),
+            ))
+        }
+    };
+
+    let fields = match field.get("fields") {
+        Some(fields) => {
+            let processing = ["attribute", "trim", "regex", "number"];
+            if let Some(key) = processing.iter().find(|key| field.contains_key(**key)) {
+                return Err(SpecError::invalid(
+                    path,
+                    format!("`{}` cannot be combined with `fields`", key),
+                ));
+            }
+            Some(parse_fields(fields, &format!("{}.fields", path))?)
+        }
+        None => None,
+    };
+
+    let attribute = string(field, path, "attribute")?;
+    if attribute.is_some() && !selects_nodes(&xpath) {
+        return Err(SpecError::invalid(
+            path,
+            "`attribute` needs an expression that selects nodes",
+        ));
+    }
+
+    let regex = string(field, path, "regex")?;
+    #[cfg(feature = "regex")]
+    let regex = match regex {
+        Some(pattern) => Some(regex::Regex::new(&pattern).map_err(|error| {
+            SpecError::invalid(path, format!("invalid regex `{}`: {}", pattern, error))
+        })?),
+        None => None,
+    };
+    #[cfg(not(feature = "regex"))]
+    if regex.is_some() {
+        return Err(SpecError::invalid(
+            path,
+            "`regex` requires the `regex` feature",
+        ));
+    }
+
+    Ok(FieldSpec {
+        name: name.to_string(),
+        expression,
+        xpath,
+        cardinality,
+        attribute,
+        trim: boolean(field, path, "trim")?,
+        #[cfg(feature = "regex")]
+        regex,
+        number: boolean(field, path, "number")?,
+        fields,
+    })
+}
+
+/// Returns whether `xpath` evaluates to a node set. The type of an XPath 1.0
+/// expression does not depend on the document, so it is evaluated against
+/// an empty one; an expression that fails there is given the benefit of the
+/// doubt.
+fn selects_nodes(xpath: &str) -> bool {
+    let package = Package::new();
+    match crate::evaluate(package.as_document().root(), xpath) {
+        Ok(Value::Nodeset(_)) | Err(_) => true,
+        Ok(_) => false,
+    }
+}
+
+fn extract_fields(fields: &[FieldSpec], node: Node) -> Result<JsonValue, ExtractError> {
+    let mut object = Map::new();
+    for field in fields {
+        let value = extract_field(field, node)
+            .map_err(|error| within(error, &field.name, &field.expression))?;
+        object.insert(field.name.clone(), value);
+    }
+    Ok(JsonValue::Object(object))
+}
+
+fn extract_field(field: &FieldSpec, node: Node) -> Result<JsonValue, ExtractError> {
+    let nodes = select(node, &field.xpath, false, field.attribute.as_deref())?;
+
+    match field.cardinality {
+        Cardinality::Many => {
+            let mut values = vec![];
+            for (index, selected) in nodes.into_iter().enumerate() {
+                let value = convert(field, selected)
+                    .map_err(|error| within(error, &format!("[{}]", index), &field.expression))?;
+                values.extend(value);
+            }
+            Ok(JsonValue::Array(values))
+        }
+        cardinality => {
+            for selected in nodes {
+                if let Some(value) = convert(field, selected)? {
+                    return Ok(value);
+                }
+            }
+            match cardinality {
+                Cardinality::Optional => Ok(JsonValue::Null),
+                _ => Err(ExtractError::new(ExtractErrorKind::Missing)),
+            }
+        }
+    }
+}
+
+/// Converts a matched node, returning `None` if a `regex` does not match.
+fn convert(field: &FieldSpec, selected: Selected) -> Result<Option<JsonValue>, ExtractError> {
+    if let Some(fields) = &field.fields {
+        return match selected {
+            Selected::Node(node) => extract_fields(fields, node).map(Some),
+            Selected::Value(text) => Err(ExtractError::new(ExtractErrorKind::Parse {
+                text,
+                message: "nested fields need a node".to_string(),
+            })),
+        };
+    }
+
+    let mut text = selected.string_value();
+    if field.trim {
+        text = text.trim().to_string();
+    }
+
+    #[cfg(feature = "regex")]
+    if let Some(regex) = &field.regex {
+        let captures = match regex.captures(&text) {
+            Some(captures) => captures,
+            None => return Ok(None),
+        };
+        let matched = captures.get(1).or_else(|| captures.get(0));
+        text = matched.map(|m| m.as_str().to_string()).unwrap_or_default();
+    }
+
+    if !field.number {
+        return Ok(Some(JsonValue::String(text)));
+    }
+
+    let number = text.trim().parse::<f64>().ok().and_then(|number| {
+        match number.fract() == 0.0 && number.abs() < 9.0e15 {
+            true => Some(Number::from(number as i64)),
+            false => Number::from_f64(number),
+        }
+    });
+    match number {
+        Some(number) => Ok(Some(JsonValue::Number(number))),
+        None => Err(ExtractError::new(ExtractErrorKind::Parse {
+            text,
+            message: "not a number".to_string(),
+        })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_github_trending_spec() {
+        let spec =
+            ExtractionSpec::from_json(include_str!("../examples/github_trending.json")).unwrap();
+        let package = crate::parse_html(include_str!("../examples/github_trending.html"));
+        let value = spec.extract(&package).unwrap();
+
+        assert_eq!(value["trending_repos"].as_array().map(Vec::len), Some(3));
+        assert_eq!(
+            value["trending_repos"][0],
+            json!({ "user": "rust-lang", "name": "/rust", "url": "/rust-lang/rust" })
+        );
+    }
+
+    #[test]
+    fn test_cardinality_and_post_processing() {
+        let spec = ExtractionSpec::from_json(
+            r#"{ "fields": {
+                "price": { "css": "span.price", "number": true, "trim": true },
+                "tags": { "css": "li", "cardinality": "many" },
+                "missing": { "css": "table", "cardinality": "optional" },
+                "count": { "xpath": "count(//li)", "number": true },
+                "link": { "css": "a", "attribute": "href" }
+            } }"#,
+        )
+        .unwrap();
+        let package = crate::parse_html(
+            "<span class='price'> 12 </span><li>a</li><li>b</li><a href='/x'>x</a>",
+        );
+
+        assert_eq!(
+            spec.extract(&package).unwrap().to_string(),
+            r#"{"price":12,"tags":["a","b"],"missing":null,"count":2,"link":"/x"}"#
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let error =
+            ExtractionSpec::from_json(r#"{ "fields": { "a": { "css": "p", "tirm": true } } }"#)
+                .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid spec at `fields.a`: unknown key `tirm`"
+        );
+        assert!(
+            ExtractionSpec::from_json(r#"{ "fields": { "a": { "xpath": "//p[" } } }"#).is_err()
+        );
+        assert!(ExtractionSpec::from_json(
+            r#"{ "fields": { "a": { "css": "p", "xpath": "//p" } } }"#
+        )
+        .is_err());
+        assert_eq!(
+            ExtractionSpec::from_json(
+                r#"{ "fields": { "a": { "xpath": "count(//a)", "attribute": "href" } } }"#
+            )
+            .unwrap_err()
+            .to_string(),
+            "invalid spec at `fields.a`: `attribute` needs an expression that selects nodes"
+        );
+        assert!(matches!(
+            ExtractionSpec::from_json("{").unwrap_err(),
+            SpecError::Json(_)
+        ));
+
+        let spec = ExtractionSpec::from_json(
+            r#"{ "fields": { "items": { "css": "li", "cardinality": "many", "fields": {
+                "n": { "xpath": ".", "number": true }
+            } } } }"#,
+        )
+        .unwrap();
+        let package = crate::parse_html("<li>1</li><li>x</li>");
+        let error = spec.extract(&package).unwrap_err();
+        assert_eq!(error.field(), "items[1].n");
+        assert_eq!(error.expression(), ".");
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_regex() {
+        let spec = ExtractionSpec::from_json(
+            r#"{ "fields": { "stars": { "css": "span", "regex": "([0-9,]+) stars", "cardinality": "many" } } }"#,
+        )
+        .unwrap();
+        let package = crate::parse_html("<span>1,234 stars</span><span>none</span>");
+        assert_eq!(
+            spec.extract(&package).unwrap(),
+            json!({ "stars": ["1,234"] })
+        );
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_toml_spec() {
+        let spec =
+            ExtractionSpec::from_toml(include_str!("../examples/github_trending.toml")).unwrap();
+        let json =
+            ExtractionSpec::from_json(include_str!("../examples/github_trending.json")).unwrap();
+        let package = crate::parse_html(include_str!("../examples/github_trending.html"));
+        assert_eq!(
+            spec.extract(&package).unwrap().to_string(),
+            json.extract(&package).unwrap().to_string()
+        );
+    }
+}
@@ -0,0 +1,453 @@
+#[cfg(feature = "regex")]
+use std::{cell::RefCell, collections::HashMap};
+
+use sxd_xpath::{
+    context::{Context, Evaluation},
+    function::{Args, Error, Function},
+    Value,
+};
+#[cfg(feature = "unicode-normalization")]
+use unicode_normalization::UnicodeNormalization;
+
+/// Maximum number of compiled regular expressions cached per thread.
+#[cfg(feature = "regex")]
+const MAX_CACHED_PATTERNS: usize = 256;
+
+#[cfg(feature = "regex")]
+thread_local! {
+    static PATTERNS: RefCell<HashMap<(String, String), regex::Regex>> = RefCell::new(HashMap::new());
+}
+
+/// Registers string functions modelled on XPath 2.0 on `context`.
+///
+/// * `lower-case(s)` and `upper-case(s)` convert the case of `s`.
+/// * `ends-with(s, suffix)` is true if `s` ends with `suffix`.
+/// * `string-join(nodes, separator?)` joins the string values of a node set
+///   in document order.
+///
+/// With the `unicode-normalization` feature, `normalize-unicode(s, form?)`
+/// is also registered. It normalizes `s` to `NFC` (the default), `NFD`,
+/// `NFKC` or `NFKD`.
+///
+/// With the `regex` feature, the regular expression functions are also
+/// registered. Patterns use the syntax of the [`regex`] crate, and the
+/// optional `flags` argument accepts `s`, `m`, `i` and `x`.
+///
+/// * `matches(s, pattern, flags?)` is true if `pattern` matches `s`.
+/// * `replace(s, pattern, replacement, flags?)` replaces every match;
+///   `$1` in `replacement` refers to a capture group and `\$` is a literal
+///   dollar sign.
+/// * `tokenize(s, pattern, flags?)` splits `s` at each match and returns the
+///   first token. XPath 1.0 has no string sequences, so like a node set
+///   converted to a string, only the first item is kept, as in
+///   `tokenize(@srcset, '\s*,\s*')` for the first image candidate.
+///
+/// [`regex`]: https://docs.rs/regex
+pub fn register_string_functions(context: &mut Context) {
+    context.set_function("lower-case", LowerCase);
+    context.set_function("upper-case", UpperCase);
+    context.set_function("ends-with", EndsWith);
+    context.set_function("string-join", StringJoin);
+    #[cfg(feature = "unicode-normalization")]
+    context.set_function("normalize-unicode", NormalizeUnicode);
+    #[cfg(feature = "regex")]
+    {
+        context.set_function("matches", Matches);
+        context.set_function("replace", Replace);
+        context.set_function("tokenize", Tokenize);
+    }
+}
+
+struct LowerCase;
+
+impl Function for LowerCase {
+    fn evaluate<'c, 'd>(
+        &self,
+        _context: &Evaluation<'c, 'd>,
+        args: Vec<Value<'d>>,
+    ) -> Result<Value<'d>, Error> {
+        let mut args = Args(args);
+        args.exactly(1)?;
+        Ok(Value::String(args.pop_string()?.to_lowercase()))
+    }
+}
+
+struct UpperCase;
+
+impl Function for UpperCase {
+    fn evaluate<'c, 'd>(
+        &self,
+        _context: &Evaluation<'c, 'd>,
+        args: Vec<Value<'d>>,
+    ) -> Result<Value<'d>, Error> {
+        let mut args = Args(args);
+        args.exactly(1)?;
+        Ok(Value::String(args.pop_string()?.to_uppercase()))
+    }
+}
+
+struct EndsWith;
+
+impl Function for EndsWith {
+    fn evaluate<'c, 'd>(
+        &self,
+        _context: &Evaluation<'c, 'd>,
+        args: Vec<Value<'d>>,
+    ) -> Result<Value<'d>, Error> {
+        let mut args = Args(args);
+        args.exactly(2)?;
+        let suffix = args.pop_string()?;
+        let s = args.pop_string()?;
+        Ok(Value::Boolean(s.ends_with(&suffix)))
+    }
+}
+
+struct StringJoin;
+
+impl Function for StringJoin {
+    fn evaluate<'c, 'd>(
+        &self,
+        _context: &Evaluation<'c, 'd>,
+        args: Vec<Value<'d>>,
+    ) -> Result<Value<'d>, Error> {
+        let mut args = Args(args);
+        args.at_least(1)?;
+        args.at_most(2)?;
+        let separator = if args.len() == 2 {
+            args.pop_string()?
+        } else {
+            String::new()
+        };
+
+        let values: Vec<String> = match args.0.pop() {
+            Some(Value::Nodeset(nodes)) => nodes
+                .document_order()
+                .iter()
+                .map(|node| node.string_value())
+                .collect(),
+            Some(value) => vec![value.into_string()],
+            None => vec![],
+        };
+        Ok(Value::String(values.join(&separator)))
+    }
+}
+
+#[cfg(feature = "unicode-normalization")]
+struct NormalizeUnicode;
+
+#[cfg(feature = "unicode-normalization")]
+impl Function for NormalizeUnicode {
+    fn evaluate<'c, 'd>(
+        &self,
+        _context: &Evaluation<'c, 'd>,
+        args: Vec<Value<'d>>,
+    ) -> Result<Value<'d>, Error> {
+        let mut args = Args(args);
+        args.at_least(1)?;
+        args.at_most(2)?;
+        let form = if args.len() == 2 {
+            args.pop_string()?.trim().to_ascii_uppercase()
+        } else {
+            "NFC".to_string()
+        };
+        let s = args.pop_string()?;
+
+        let normalized = match form.as_str() {
+            "NFC" => s.nfc().collect(),
+            "NFD" => s.nfd().collect(),
+            "NFKC" => s.nfkc().collect(),
+            "NFKD" => s.nfkd().collect(),
+            "" => s,
+            _ => {
+                return Err(Error::Other(format!(
+                    "unsupported normalization form `{}`",
+                    form
+                )))
+            }
+        };
+        Ok(Value::String(normalized))
+    }
+}
+
+#[cfg(feature = "regex")]
+struct Matches;
+
+#[cfg(feature = "regex")]
+impl Function for Matches {
+    fn evaluate<'c, 'd>(
+        &self,
+        _context: &Evaluation<'c, 'd>,
+        args: Vec<Value<'d>>,
+    ) -> Result<Value<'d>, Error> {
+        let mut args = Args(args);
+        args.at_least(2)?;
+        args.at_most(3)?;
+        let flags = pop_flags(&mut args, 3)?;
+        let pattern = args.pop_string()?;
+        let s = args.pop_string()?;
+
+        with_regex(&pattern, &flags, |regex| Value::Boolean(regex.is_match(&s)))
+    }
+}
+
+#[cfg(feature = "regex")]
+struct Replace;
+
+#[cfg(feature = "regex")]
+impl Function for Replace {
+    fn evaluate<'c, 'd>(
+        &self,
+        _context: &Evaluation<'c, 'd>,
+        args: Vec<Value<'d>>,
+    ) -> Result<Value<'d>, Error> {
+        let mut args = Args(args);
+        args.at_least(3)?;
+        args.at_most(4)?;
+        let flags = pop_flags(&mut args, 4)?;
+        let replacement = replacement(&args.pop_string()?)?;
+        let pattern = args.pop_string()?;
+        let s = args.pop_string()?;
+
+        with_regex(&pattern, &flags, |regex| {
+            if regex.is_match("") {
+                return Err(Error::Other(format!(
+                    "pattern `{}` matches the empty string",
+                    pattern
+                )));
+            }
+            Ok(Value::String(
+                regex.replace_all(&s, replacement.as_str()).into_owned(),
+            ))
+        })?
+    }
+}
+
+#[cfg(feature = "regex")]
+struct Tokenize;
+
+#[cfg(feature = "regex")]
+impl Function for Tokenize {
+    fn evaluate<'c, 'd>(
+        &self,
+        _context: &Evaluation<'c, 'd>,
+        args: Vec<Value<'d>>,
+    ) -> Result<Value<'d>, Error> {
+        let mut args = Args(args);
+        args.at_least(2)?;
+        args.at_most(3)?;
+        let flags = pop_flags(&mut args, 3)?;
+        let pattern = args.pop_string()?;
+        let s = args.pop_string()?;
+
+        with_regex(&pattern, &flags, |regex| {
+            if regex.is_match("") {
+                return Err(Error::Other(format!(
+                    "pattern `{}` matches the empty string",
+                    pattern
+                )));
+            }
+            let token = regex.split(&s).next().unwrap_or("");
+            Ok(Value::String(token.to_string()))
+        })?
+    }
+}
+
+/// Pops the optional `flags` argument when `args` holds `position`
+/// arguments, and checks that only XPath regular expression flags are used.
+#[cfg(feature = "regex")]
+fn pop_flags(args: &mut Args, position: usize) -> Result<String, Error> {
+    let flags = if args.len() == position {
+        args.pop_string()?
+    } else {
+        String::new()
+    };
+    match flags
+        .chars()
+        .find(|flag| !matches!(flag, 's' | 'm' | 'i' | 'x'))
+    {
+        Some(flag) => Err(Error::Other(format!(
+            "unsupported regular expression flag `{}`",
+            flag
+        ))),
+        None => Ok(flags),
+    }
+}
+
+/// Converts an XPath replacement string to the syntax of the `regex` crate.
+#[cfg(feature = "regex")]
+fn replacement(xpath_replacement: &str) -> Result<String, Error> {
+    let mut converted = String::with_capacity(xpath_replacement.len());
+    let mut chars = xpath_replacement.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('\\') => converted.push('\\'),
+                Some('$') => converted.push_str("$$"),
+                _ => {
+                    return Err(Error::Other(
+                        "`\\` in a replacement must be followed by `\\` or `$`".to_string(),
+                    ))
+                }
+            },
+            '$' => {
+                let mut group = String::new();
+                while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                    group.push(digit);
+                }
+                if group.is_empty() {
+                    return Err(Error::Other(
+                        "`$` in a replacement must be followed by a group number".to_string(),
+                    ));
+                }
+                converted.push_str(&format!("${{{}}}", group));
+            }
+            c => converted.push(c),
+        }
+    }
+    Ok(converted)
+}
+
+/// Calls `f` with the compiled regular expression for `pattern` and
+/// `flags`, compiling it unless the thread has cached it.
+#[cfg(feature = "regex")]
+fn with_regex<T>(
+    pattern: &str,
+    flags: &str,
+    f: impl FnOnce(&regex::Regex) -> T,
+) -> Result<T, Error> {
+    let key = (pattern.to_string(), flags.to_string());
+    PATTERNS.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if !cache.contains_key(&key) {
+            let source = if flags.is_empty() {
+                pattern.to_string()
+            } else {
+                format!("(?{}){}", flags, pattern)
+            };
+            let regex = regex::Regex::new(&source).map_err(|error| {
+                Error::Other(format!(
+                    "invalid regular expression `{}`: {}",
+                    pattern, error
+                ))
+            })?;
+            if cache.len() >= MAX_CACHED_PATTERNS {
+                cache.clear();
+            }
+            cache.insert(key.clone(), regex);
+        }
+        match cache.get(&key) {
+            Some(regex) => Ok(f(regex)),
+            None => Err(Error::Other(format!(
+                "invalid regular expression `{}`",
+                pattern
+            ))),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Query;
+
+    const HTML: &str = r#"<p class="Price">  1,280 YEN </p><a rel="nofollow noopener">x</a>"#;
+
+    #[test]
+    fn test_string_functions() {
+        let package = crate::parse_html(HTML);
+        let root = package.as_document().root();
+        let query = Query::with_context(crate::html_context());
+
+        assert_eq!(
+            query.select_text(root, "lower-case(//p)").unwrap(),
+            "  1,280 yen "
+        );
+        assert_eq!(
+            query.select_text(root, "upper-case(//p/@class)").unwrap(),
+            "PRICE"
+        );
+        assert_eq!(
+            query
+                .select(root, "//p[ends-with(normalize-space(), 'YEN')]")
+                .unwrap()
+                .size(),
+            1
+        );
+        assert_eq!(
+            query
+                .select_text(root, "string-join(//p | //a, '|')")
+                .unwrap(),
+            "  1,280 YEN |x"
+        );
+    }
+
+    #[cfg(feature = "unicode-normalization")]
+    #[test]
+    fn test_normalize_unicode() {
+        let package = crate::parse_html(HTML);
+        let root = package.as_document().root();
+        let query = Query::with_context(crate::html_context());
+
+        assert_eq!(
+            query
+                .select_text(root, "normalize-unicode('ｶ', 'NFKC')")
+                .unwrap(),
+            "カ"
+        );
+        assert!(query
+            .select_text(root, "normalize-unicode('a', 'XYZ')")
+            .is_err());
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_regex_functions() {
+        let package = crate::parse_html(HTML);
+        let root = package.as_document().root();
+        let query = Query::with_context(crate::html_context());
+
+        assert_eq!(
+            query
+                .select(root, "//p[matches(., '\\d+,\\d+')]")
+                .unwrap()
+                .size(),
+            1
+        );
+        assert_eq!(
+            query
+                .select(root, "//p[matches(., 'yen', 'i')]")
+                .unwrap()
+                .size(),
+            1
+        );
+        assert_eq!(
+            query
+                .select_number(
+                    root,
+                    "number(replace(normalize-space(//p), '(\\d+),(\\d+).*', '$1$2'))"
+                )
+                .unwrap(),
+            1280.0
+        );
+        assert_eq!(
+            query
+                .select_text(root, "tokenize(//a/@rel, '\\s+')")
+                .unwrap(),
+            "nofollow"
+        );
+        assert_eq!(
+            query
+                .select_text(root, "tokenize('a.jpg 1x, b.jpg 2x', '\\s*,\\s*')")
+                .unwrap(),
+            "a.jpg 1x"
+        );
+        assert_eq!(
+            query
+                .select_text(root, "string-join(tokenize('a b', ' '), ',')")
+                .unwrap(),
+            "a"
+        );
+        assert!(query.select_text(root, "tokenize('a b', '\\s*')").is_err());
+        assert!(query.select_text(root, "replace('a', '.*', 'b')").is_err());
+        assert!(query.select_text(root, "matches('a', 'a', 'q')").is_err());
+    }
+}
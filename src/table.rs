@@ -0,0 +1,409 @@
+use serde_json::{Map, Value as JsonValue};
+use sxd_document::dom::Element;
+
+use crate::util;
+
+/// Largest `colspan` honoured, as in the HTML table processing model.
+const MAX_COLSPAN: usize = 1000;
+/// Largest `rowspan` honoured, as in the HTML table processing model.
+const MAX_ROWSPAN: usize = 65534;
+
+/// A cell of a [`Table`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableCell<'d> {
+    /// The `td` or `th` element.
+    pub element: Element<'d>,
+    /// The [`rendered_text`](crate::rendered_text) of the element.
+    pub text: String,
+    /// Whether the cell is a `th` element.
+    pub header: bool,
+    /// The row of the top-left slot the cell covers.
+    pub row: usize,
+    /// The column of the top-left slot the cell covers.
+    pub column: usize,
+    /// The number of rows the cell spans.
+    pub rowspan: usize,
+    /// The number of columns the cell spans.
+    pub colspan: usize,
+}
+
+/// An HTML table laid out as a rectangular grid.
+///
+/// Cells spanning several rows or columns with `rowspan` and `colspan`
+/// occupy every slot they cover, so each row of the grid has the same
+/// number of columns. Slots no cell covers are empty.
+///
+/// Rows are ordered as they are rendered: the rows of `thead` first, then
+/// the rows of `tbody` and of the table itself, and the rows of `tfoot`
+/// last. The rows of `thead` are the header rows; without a `thead`, the
+/// leading rows consisting only of `th` cells are.
+#[derive(Debug, Clone)]
+pub struct Table<'d> {
+    element: Element<'d>,
+    cells: Vec<TableCell<'d>>,
+    grid: Vec<Vec<Option<usize>>>,
+    header_rows: usize,
+}
+
+impl<'d> Table<'d> {
+    /// Lays out the `table` element `element`, returning `None` if it is
+    /// not a `table` element.
+    ///
+    /// Cells of nested tables belong to the nested table and are not
+    /// included.
+    pub fn from_element(element: Element<'d>) -> Option<Self> {
+        if util::html_local_name(&element) != Some("table") {
+            return None;
+        }
+
+        let mut heads = vec![];
+        let mut bodies = vec![];
+        let mut foots = vec![];
+        let mut loose_rows = vec![];
+        for child in child_elements(element) {
+            let name = util::html_local_name(&child);
+            if name == Some("tr") {
+                loose_rows.push(child);
+                continue;
+            }
+            // Consecutive `tr` children of the table form an implied body.
+            if !loose_rows.is_empty() {
+                bodies.push(std::mem::take(&mut loose_rows));
+            }
+            match name {
+                Some("thead") => heads.push(rows(child)),
+                Some("tbody") => bodies.push(rows(child)),
+                Some("tfoot") => foots.push(rows(child)),
+                _ => {}
+            }
+        }
+        if !loose_rows.is_empty() {
+            bodies.push(loose_rows);
+        }
+
+        let mut table = Self {
+            element,
+            cells: vec![],
+            grid: vec![],
+            header_rows: 0,
+        };
+        for group in &heads {
+            table.add_row_group(group);
+        }
+        table.header_rows = table.grid.len();
+        for group in bodies.iter().chain(&foots) {
+            table.add_row_group(group);
+        }
+
+        let width = table.grid.iter().map(Vec::len).max().unwrap_or(0);
+        for row in &mut table.grid {
+            row.resize(width, None);
+        }
+
+        if heads.is_empty() {
+            table.header_rows = (0..table.height())
+                .take_while(|&row| {
+                    let mut cells = table.row(row).into_iter().flatten().peekable();
+                    cells.peek().is_some() && cells.all(|cell| cell.header)
+                })
+                .count();
+        }
+        Some(table)
+    }
+
+    fn add_row_group(&mut self, rows: &[Element<'d>]) {
+        let start = self.grid.len();
+        self.grid.resize(start + rows.len(), vec![]);
+
+        for (index, tr) in rows.iter().enumerate() {
+            let row = start + index;
+            let remaining_rows = rows.len() - index;
+            let mut column = 0;
+
+            for element in child_elements(*tr) {
+                let header = match util::html_local_name(&element) {
+                    Some("td") => false,
+                    Some("th") => true,
+                    _ => continue,
+                };
+                while self.grid[row].get(column).is_some_and(Option::is_some) {
+                    column += 1;
+                }
+
+                let colspan = span(element, "colspan").unwrap_or(1).clamp(1, MAX_COLSPAN);
+                let rowspan = match span(element, "rowspan") {
+                    Some(0) => remaining_rows,
+                    Some(rowspan) => rowspan.min(MAX_ROWSPAN).min(remaining_rows),
+                    None => 1,
+                };
+
+                let cell_index = self.cells.len();
+                for slots in &mut self.grid[row..row + rowspan] {
+                    if slots.len() < column + colspan {
+                        slots.resize(column + colspan, None);
+                    }
+                    for slot in &mut slots[column..column + colspan] {
+                        *slot = Some(cell_index);
+                    }
+                }
+                self.cells.push(TableCell {
+                    element,
+                    text: crate::rendered_text(element),
+                    header,
+                    row,
+                    column,
+                    rowspan,
+                    colspan,
+                });
+                column += colspan;
+            }
+        }
+    }
+
+    /// Returns the `table` element.
+    pub fn element(&self) -> Element<'d> {
+        self.element
+    }
+
+    /// Returns the number of rows.
+    pub fn height(&self) -> usize {
+        self.grid.len()
+    }
+
+    /// Returns the number of columns.
+    pub fn width(&self) -> usize {
+        self.grid.first().map(Vec::len).unwrap_or(0)
+    }
+
+    /// Returns the number of header rows.
+    pub fn header_rows(&self) -> usize {
+        self.header_rows
+    }
+
+    /// Returns every cell, in the order the cells appear in the rows.
+    pub fn cells(&self) -> &[TableCell<'d>] {
+        &self.cells
+    }
+
+    /// Returns the cell covering the slot at `row` and `column`.
+    pub fn cell(&self, row: usize, column: usize) -> Option<&TableCell<'d>> {
+        let index = (*self.grid.get(row)?.get(column)?)?;
+        self.cells.get(index)
+    }
+
+    /// Returns the cells covering the slots of `row`.
+    pub fn row(&self, row: usize) -> Vec<Option<&TableCell<'d>>> {
+        (0..self.width())
+            .map(|column| self.cell(row, column))
+            .collect()
+    }
+
+    /// Returns the text of every slot, with the text of a spanning cell
+    /// repeated in each slot it covers and empty strings for empty slots.
+    pub fn text_grid(&self) -> Vec<Vec<String>> {
+        (0..self.height())
+            .map(|row| {
+                self.row(row)
+                    .into_iter()
+                    .map(|cell| cell.map(|cell| cell.text.clone()).unwrap_or_default())
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Returns a name for each column, made from the text of the header
+    /// rows.
+    ///
+    /// The texts of several header rows are joined with ` / `, columns
+    /// without header text are named `column 1`, `column 2` and so on, and
+    /// repeated names get a ` (2)`, ` (3)` suffix so that names are unique.
+    pub fn headers(&self) -> Vec<String> {
+        let mut headers: Vec<String> = vec![];
+        for column in 0..self.width() {
+            let mut texts: Vec<&str> = vec![];
+            for row in 0..self.header_rows {
+                if let Some(cell) = self.cell(row, column) {
+                    let text = cell.text.as_str();
+                    if !text.is_empty() && texts.last() != Some(&text) {
+                        texts.push(text);
+                    }
+                }
+            }
+
+            let name = match texts.is_empty() {
+                true => format!("column {}", column + 1),
+                false => texts.join(" / "),
+            };
+            let mut unique = name.clone();
+            let mut suffix = 2;
+            while headers.contains(&unique) {
+                unique = format!("{} ({})", name, suffix);
+                suffix += 1;
+            }
+            headers.push(unique);
+        }
+        headers
+    }
+
+    /// Returns the rows after the header rows as records pairing each
+    /// column name from [`headers`](Self::headers) with the text of the
+    /// row's slot in that column.
+    pub fn records(&self) -> Vec<Vec<(String, String)>> {
+        let headers = self.headers();
+        self.text_grid()
+            .into_iter()
+            .skip(self.header_rows)
+            .map(|row| headers.iter().cloned().zip(row).collect())
+            .collect()
+    }
+
+    /// Returns the text grid as CSV, as described by RFC 4180.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        for row in self.text_grid() {
+            let fields: Vec<String> = row.iter().map(|text| csv_field(text)).collect();
+            csv.push_str(&fields.join(","));
+            csv.push_str("\r\n");
+        }
+        csv
+    }
+
+    /// Returns the [`records`](Self::records) as a JSON array of objects,
+    /// with the keys in column order as in [`to_csv`](Self::to_csv).
+    pub fn to_json(&self) -> JsonValue {
+        let records = self
+            .records()
+            .into_iter()
+            .map(|record| {
+                let object: Map<String, JsonValue> = record
+                    .into_iter()
+                    .map(|(header, text)| (header, JsonValue::String(text)))
+                    .collect();
+                JsonValue::Object(object)
+            })
+            .collect();
+        JsonValue::Array(records)
+    }
+}
+
+fn child_elements(element: Element) -> impl Iterator<Item = Element> {
+    element
+        .children()
+        .into_iter()
+        .filter_map(|child| child.element())
+}
+
+fn rows(group: Element) -> Vec<Element> {
+    child_elements(group)
+        .filter(|child| util::html_local_name(child) == Some("tr"))
+        .collect()
+}
+
+/// Parses a `rowspan` or `colspan` attribute, which must be a non-negative
+/// integer.
+fn span(element: Element, attribute: &str) -> Option<usize> {
+    element.attribute_value(attribute)?.trim().parse().ok()
+}
+
+fn csv_field(text: &str) -> String {
+    match text.contains([',', '"', '\r', '\n']) {
+        true => format!("\"{}\"", text.replace('"', "\"\"")),
+        false => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn first_table<'d>(package: &'d sxd_document::Package) -> Table<'d> {
+        let node = crate::select_one(package.as_document().root(), "//table").unwrap();
+        Table::from_element(node.element().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_spans() {
+        let package = crate::parse_html(concat!(
+            "<table><tbody>",
+            "<tr><td rowspan=2>a</td><td colspan=2>b</td></tr>",
+            "<tr><td>c</td><td rowspan=0>d</td></tr>",
+            "<tr><td>e</td></tr>",
+            "</tbody><tbody>",
+            "<tr><td colspan=4>f</td></tr>",
+            "</tbody></table>",
+        ));
+        let table = first_table(&package);
+
+        assert_eq!(table.width(), 4);
+        assert_eq!(table.height(), 4);
+        assert_eq!(
+            table.text_grid(),
+            vec![
+                vec!["a", "b", "b", ""],
+                vec!["a", "c", "d", ""],
+                vec!["e", "", "d", ""],
+                vec!["f", "f", "f", "f"],
+            ]
+        );
+        let d = table.cell(2, 2).unwrap();
+        assert_eq!((d.row, d.column, d.rowspan, d.colspan), (1, 2, 2, 1));
+        assert_eq!(table.header_rows(), 0);
+    }
+
+    #[test]
+    fn test_headers_and_records() {
+        let package = crate::parse_html(concat!(
+            "<table><caption>Cities</caption>",
+            "<tfoot><tr><td>Total</td><td>3</td><td>5</td></tr></tfoot>",
+            "<thead><tr><th rowspan=2>City</th><th colspan=2>Population</th></tr>",
+            "<tr><th>2010</th><th>2020</th></tr></thead>",
+            "<tbody><tr><td>A</td><td>1</td><td>2</td></tr>",
+            "<tr><td>B</td><td>2</td><td>3</td></tr></tbody>",
+            "</table>",
+        ));
+        let table = first_table(&package);
+
+        assert_eq!(table.header_rows(), 2);
+        assert_eq!(
+            table.headers(),
+            vec!["City", "Population / 2010", "Population / 2020"]
+        );
+        let records = table.records();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[2][0], ("City".to_string(), "Total".to_string()));
+
+        let package = crate::parse_html(
+            "<table><tr><th>x</th><th>x</th><th></th></tr><tr><td>1</td></tr></table>",
+        );
+        let table = first_table(&package);
+        assert_eq!(table.header_rows(), 1);
+        assert_eq!(table.headers(), vec!["x", "x (2)", "column 3"]);
+    }
+
+    #[test]
+    fn test_export() {
+        let package = crate::parse_html(concat!(
+            "<table><tr><th>name</th><th>note</th></tr>",
+            r#"<tr><td>a</td><td>say "hi", <table><tr><td>nested</td></tr></table></td></tr>"#,
+            "</table>",
+        ));
+        let table = first_table(&package);
+
+        assert_eq!(
+            table.to_csv(),
+            "name,note\r\na,\"say \"\"hi\"\",\nnested\"\r\n"
+        );
+        assert_eq!(
+            table.to_json(),
+            serde_json::json!([{ "name": "a", "note": "say \"hi\",\nnested" }])
+        );
+
+        let package = crate::parse_html(
+            "<table><tr><th>z</th><th>a</th></tr><tr><td>1</td><td>2</td></tr></table>",
+        );
+        assert_eq!(
+            first_table(&package).to_json().to_string(),
+            r#"[{"z":"1","a":"2"}]"#
+        );
+    }
+}
@@ -0,0 +1,532 @@
+use sxd_document::dom::{ChildOfElement, Element};
+use sxd_xpath::nodeset::Node;
+
+use crate::util;
+
+/// Elements whose contents are never rendered.
+const SKIPPED_ELEMENTS: &[&str] = &[
+    "head", "title", "meta", "link", "base", "script", "style", "template", "noscript", "datalist",
+    "param", "source", "track", "area", "iframe", "object", "embed", "rp",
+];
+
+/// Elements that keep their whitespace as written.
+const PREFORMATTED_ELEMENTS: &[&str] = &["pre", "listing", "plaintext", "textarea", "xmp"];
+
+/// Elements laid out as blocks: their contents start and end on their own line.
+pub(crate) const BLOCK_ELEMENTS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "body",
+    "caption",
+    "center",
+    "dd",
+    "details",
+    "dialog",
+    "dir",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hgroup",
+    "hr",
+    "html",
+    "legend",
+    "li",
+    "listing",
+    "main",
+    "menu",
+    "nav",
+    "ol",
+    "optgroup",
+    "option",
+    "plaintext",
+    "pre",
+    "search",
+    "section",
+    "summary",
+    "table",
+    "tbody",
+    "tfoot",
+    "thead",
+    "tr",
+    "ul",
+    "xmp",
+];
+
+/// How [`rendered_text_with`] renders `<ruby>` annotations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RubyText {
+    /// Each base text followed by its reading, as browsers report it:
+    /// `漢字かんじ`.
+    #[default]
+    Both,
+    /// Only the base text: `漢字`.
+    Base,
+    /// Only the readings: `かんじ`. Base text without a reading is kept.
+    Reading,
+    /// Each base text followed by its reading in parentheses: `漢字(かんじ)`.
+    Inline,
+}
+
+/// Options for [`rendered_text_with`].
+#[derive(Debug, Clone, Default)]
+pub struct TextOptions {
+    /// How `<ruby>` annotations are rendered.
+    pub ruby: RubyText,
+}
+
+/// A base text of a `<ruby>` element paired with its reading.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RubyAnnotation<'d> {
+    /// The `<ruby>` element holding the annotation.
+    pub ruby: Element<'d>,
+    /// The annotated text, with whitespace collapsed.
+    pub base: String,
+    /// The reading from the `<rt>` elements, with whitespace collapsed. Empty
+    /// when the base text has no reading.
+    pub reading: String,
+    /// The text nodes and elements (such as `<rb>`) forming the base text.
+    pub base_nodes: Vec<Node<'d>>,
+    /// The `<rt>` elements forming the reading.
+    pub reading_nodes: Vec<Element<'d>>,
+}
+
+/// Returns the text of `node` as a browser would render it, similar to
+/// the DOM `innerText` property.
+///
+/// Unlike the XPath `string()` function, which concatenates every
+/// descendant text node, this applies a simplified version of the
+/// `innerText` rules:
+///
+/// * block elements start and end on their own line, and `<p>` elements are
+///   separated by a blank line;
+/// * `<br>` produces a line break;
+/// * table cells in a row are separated by tabs and rows by line breaks;
+/// * whitespace is collapsed, except inside `<pre>` and `<textarea>`;
+/// * `<script>`, `<style>`, `<template>`, `<head>`, `<rp>` and elements
+///   carrying the `hidden` attribute are skipped.
+///
+/// No stylesheet is applied, so layout is derived from the element names
+/// alone. Leading and trailing whitespace is removed from the result.
+pub fn rendered_text<'d>(node: impl Into<Node<'d>>) -> String {
+    rendered_text_with(node, &TextOptions::default())
+}
+
+/// Returns the text of `node` as a browser would render it, using `options`.
+///
+/// See [`rendered_text`] for the rendering rules.
+pub fn rendered_text_with<'d>(node: impl Into<Node<'d>>, options: &TextOptions) -> String {
+    let mut builder = TextBuilder::default();
+
+    match node.into() {
+        Node::Root(root) => {
+            for child in root.children() {
+                if let Some(element) = child.element() {
+                    render_element(&mut builder, element, false, options);
+                }
+            }
+        }
+        Node::Element(element) => render_element(&mut builder, element, false, options),
+        Node::Text(text) => builder.push_text(text.text(), false),
+        Node::Attribute(attribute) => builder.push_text(attribute.value(), true),
+        Node::Comment(_) | Node::Namespace(_) | Node::ProcessingInstruction(_) => {}
+    }
+
+    builder.finish()
+}
+
+/// Returns the annotations of every `<ruby>` element in `node` and its
+/// descendants, in document order.
+///
+/// Base texts are paired with readings in order, so both
+/// `<ruby>漢<rt>かん</rt>字<rt>じ</rt></ruby>` and
+/// `<ruby><rb>漢</rb><rb>字</rb><rt>かん</rt><rt>じ</rt></ruby>` yield the
+/// pairs `(漢, かん)` and `(字, じ)`. `<rp>` fallback parentheses are ignored.
+pub fn ruby_annotations<'d>(node: impl Into<Node<'d>>) -> Vec<RubyAnnotation<'d>> {
+    let mut annotations = vec![];
+    let mut stack: Vec<Node<'d>> = vec![node.into()];
+
+    while let Some(node) = stack.pop() {
+        match node {
+            Node::Element(element) if util::html_local_name(&element) == Some("ruby") => {
+                annotations.extend(ruby_segments(element));
+            }
+            Node::Root(_) | Node::Element(_) => {
+                stack.extend(node.children().into_iter().rev());
+            }
+            _ => {}
+        }
+    }
+
+    annotations
+}
+
+/// Returns `true` if the contents of `element` are not rendered.
+pub(crate) fn is_skipped(element: &Element) -> bool {
+    if element.attribute_value("hidden").is_some() {
+        return true;
+    }
+    util::html_local_name(element).is_some_and(|name| SKIPPED_ELEMENTS.contains(&name))
+}
+
+fn render_element(
+    builder: &mut TextBuilder,
+    element: Element,
+    preformatted: bool,
+    options: &TextOptions,
+) {
+    if is_skipped(&element) {
+        return;
+    }
+
+    let name = util::html_local_name(&element).unwrap_or_default();
+    let preformatted = preformatted || PREFORMATTED_ELEMENTS.contains(&name);
+
+    match name {
+        "br" => {
+            builder.push_line_break();
+            return;
+        }
+        "ruby" if options.ruby != RubyText::Both => {
+            render_ruby(builder, element, options.ruby);
+            return;
+        }
+        "p" => builder.require_breaks(2),
+        _ if BLOCK_ELEMENTS.contains(&name) => builder.require_breaks(1),
+        _ => {}
+    }
+
+    render_children(builder, element, preformatted, options);
+
+    match name {
+        "p" => builder.require_breaks(2),
+        "td" | "th" => {
+            let has_next_cell = element
+                .following_siblings()
+                .iter()
+                .filter_map(|sibling| sibling.element())
+                .any(|sibling| matches!(util::html_local_name(&sibling), Some("td" | "th")));
+            if has_next_cell {
+                builder.push_tab();
+            }
+        }
+        _ if BLOCK_ELEMENTS.contains(&name) => builder.require_breaks(1),
+        _ => {}
+    }
+}
+
+fn render_children(
+    builder: &mut TextBuilder,
+    element: Element,
+    preformatted: bool,
+    options: &TextOptions,
+) {
+    for child in element.children() {
+        match child {
+            ChildOfElement::Element(child) => render_element(builder, child, preformatted, options),
+            ChildOfElement::Text(text) => builder.push_text(text.text(), preformatted),
+            ChildOfElement::Comment(_) | ChildOfElement::ProcessingInstruction(_) => {}
+        }
+    }
+}
+
+fn render_ruby(builder: &mut TextBuilder, ruby: Element, mode: RubyText) {
+    for annotation in ruby_segments(ruby) {
+        let text = match mode {
+            RubyText::Base => annotation.base,
+            RubyText::Reading if annotation.reading.is_empty() => annotation.base,
+            RubyText::Reading => annotation.reading,
+            RubyText::Inline if annotation.reading.is_empty() => annotation.base,
+            RubyText::Inline => format!("{}({})", annotation.base, annotation.reading),
+            RubyText::Both => format!("{}{}", annotation.base, annotation.reading),
+        };
+        builder.push_text(&text, false);
+    }
+}
+
+/// Splits a `<ruby>` element into base texts paired with their readings.
+fn ruby_segments(ruby: Element) -> Vec<RubyAnnotation> {
+    let mut annotations = vec![];
+    let mut bases: Vec<Vec<Node>> = vec![];
+    let mut readings: Vec<Element> = vec![];
+    // Whether consecutive text and inline elements extend the last base.
+    let mut in_base_run = false;
+
+    for child in ruby.children() {
+        let name = child.element().and_then(|e| util::html_local_name(&e));
+        match (child, name) {
+            (ChildOfElement::Element(rt), Some("rt")) => {
+                readings.push(rt);
+                in_base_run = false;
+            }
+            (ChildOfElement::Element(rtc), Some("rtc")) => {
+                readings.extend(
+                    rtc.children()
+                        .iter()
+                        .filter_map(|child| child.element())
+                        .filter(|child| util::html_local_name(child) == Some("rt")),
+                );
+                in_base_run = false;
+            }
+            (ChildOfElement::Element(_), Some("rp"))
+            | (ChildOfElement::Comment(_), _)
+            | (ChildOfElement::ProcessingInstruction(_), _) => {}
+            (ChildOfElement::Text(text), _) if text.text().trim().is_empty() => {}
+            (child, name) => {
+                if !readings.is_empty() {
+                    pair_ruby_segment(&mut annotations, ruby, &mut bases, &mut readings);
+                }
+                let node: Node = child.into();
+                match bases.last_mut() {
+                    Some(base) if in_base_run => base.push(node),
+                    _ => bases.push(vec![node]),
+                }
+                // Each <rb> is a base of its own.
+                in_base_run = name != Some("rb");
+            }
+        }
+    }
+    pair_ruby_segment(&mut annotations, ruby, &mut bases, &mut readings);
+
+    annotations
+}
+
+fn pair_ruby_segment<'d>(
+    annotations: &mut Vec<RubyAnnotation<'d>>,
+    ruby: Element<'d>,
+    bases: &mut Vec<Vec<Node<'d>>>,
+    readings: &mut Vec<Element<'d>>,
+) {
+    let count = bases.len().max(1);
+    let mut readings = readings.drain(..);
+    let mut bases = bases.drain(..);
+
+    for index in 0..count {
+        let base_nodes = bases.next().unwrap_or_default();
+        // Readings without a base of their own are attached to the last base.
+        let reading_nodes: Vec<Element> = if index + 1 == count {
+            readings.by_ref().collect()
+        } else {
+            readings.next().into_iter().collect()
+        };
+        if base_nodes.is_empty() && reading_nodes.is_empty() {
+            continue;
+        }
+
+        let base = collapse(base_nodes.iter().map(ruby_base_text).collect::<String>());
+        let reading = collapse(
+            reading_nodes
+                .iter()
+                .map(|rt| Node::Element(*rt).string_value())
+                .collect::<String>(),
+        );
+        annotations.push(RubyAnnotation {
+            ruby,
+            base,
+            reading,
+            base_nodes,
+            reading_nodes,
+        });
+    }
+}
+
+/// Returns the text of a ruby base node, leaving out nested annotations.
+fn ruby_base_text(node: &Node) -> String {
+    match node {
+        Node::Text(text) => text.text().to_string(),
+        Node::Element(element)
+            if matches!(util::html_local_name(element), Some("rt" | "rp" | "rtc")) =>
+        {
+            String::new()
+        }
+        Node::Element(_) => node.children().iter().map(ruby_base_text).collect(),
+        _ => String::new(),
+    }
+}
+
+fn collapse(text: String) -> String {
+    text.split_ascii_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Accumulates rendered text, collapsing whitespace and line breaks.
+#[derive(Debug, Default)]
+struct TextBuilder {
+    output: String,
+    /// Number of line breaks required before the next rendered text.
+    pending_breaks: usize,
+}
+
+impl TextBuilder {
+    fn require_breaks(&mut self, count: usize) {
+        self.pending_breaks = self.pending_breaks.max(count);
+    }
+
+    fn flush_breaks(&mut self) {
+        if self.pending_breaks == 0 {
+            return;
+        }
+        if !self.output.is_empty() {
+            self.trim_trailing_spaces();
+            let existing = self.output.len() - self.output.trim_end_matches('\n').len();
+            for _ in existing..self.pending_breaks {
+                self.output.push('\n');
+            }
+        }
+        self.pending_breaks = 0;
+    }
+
+    fn trim_trailing_spaces(&mut self) {
+        let trimmed = self.output.trim_end_matches(' ').len();
+        self.output.truncate(trimmed);
+    }
+
+    fn ends_in_whitespace(&self) -> bool {
+        self.output.is_empty() || self.output.ends_with(['\n', '\t', ' '])
+    }
+
+    fn push_text(&mut self, text: &str, preformatted: bool) {
+        if text.is_empty() {
+            return;
+        }
+
+        if preformatted {
+            self.flush_breaks();
+            self.output.push_str(text);
+            return;
+        }
+
+        let mut collapsed = String::with_capacity(text.len());
+        let mut in_whitespace = false;
+        for c in text.chars() {
+            if c.is_ascii_whitespace() {
+                in_whitespace = true;
+            } else {
+                if in_whitespace {
+                    collapsed.push(' ');
+                }
+                in_whitespace = false;
+                collapsed.push(c);
+            }
+        }
+        if in_whitespace {
+            collapsed.push(' ');
+        }
+
+        if collapsed == " " && (self.pending_breaks > 0 || self.ends_in_whitespace()) {
+            return;
+        }
+
+        self.flush_breaks();
+        let collapsed = if self.ends_in_whitespace() {
+            collapsed.trim_start_matches(' ')
+        } else {
+            &collapsed
+        };
+        self.output.push_str(collapsed);
+    }
+
+    fn push_line_break(&mut self) {
+        self.flush_breaks();
+        self.trim_trailing_spaces();
+        self.output.push('\n');
+    }
+
+    fn push_tab(&mut self) {
+        self.trim_trailing_spaces();
+        self.output.push('\t');
+    }
+
+    fn finish(mut self) -> String {
+        let trimmed = self.output.trim_end().len();
+        self.output.truncate(trimmed);
+        self.output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn body_text(html: &str) -> String {
+        let package = crate::parse_html(html);
+        let root = package.as_document().root();
+        rendered_text(root)
+    }
+
+    #[test]
+    fn test_blocks_and_line_breaks() {
+        let text =
+            body_text("<div>one<br>two</div><p>three</p><p>four</p><ul><li>a</li><li>b</li></ul>");
+        assert_eq!(text, "one\ntwo\n\nthree\n\nfour\n\na\nb");
+    }
+
+    #[test]
+    fn test_whitespace_collapse() {
+        let text = body_text("<p>  hello \n   <b>big</b>\tworld  </p><pre>  keep\n  this</pre>");
+        assert_eq!(text, "hello big world\n\n  keep\n  this");
+    }
+
+    #[test]
+    fn test_skipped_content() {
+        let text = body_text(concat!(
+            "<head><title>T</title><style>p{}</style></head>",
+            "<body>visible<script>var x;</script><template>t</template>",
+            "<span hidden>secret</span></body>",
+        ));
+        assert_eq!(text, "visible");
+    }
+
+    #[test]
+    fn test_ruby_modes() {
+        let package = crate::parse_html(concat!(
+            "<p><ruby>漢<rt>かん</rt>字<rt>じ</rt></ruby>を",
+            "<ruby>読<rp>(</rp><rt>よ</rt><rp>)</rp></ruby>む</p>",
+        ));
+        let root = package.as_document().root();
+        let with = |ruby| rendered_text_with(root, &TextOptions { ruby });
+
+        assert_eq!(rendered_text(root), "漢かん字じを読よむ");
+        assert_eq!(with(RubyText::Base), "漢字を読む");
+        assert_eq!(with(RubyText::Reading), "かんじをよむ");
+        assert_eq!(with(RubyText::Inline), "漢(かん)字(じ)を読(よ)む");
+    }
+
+    #[test]
+    fn test_ruby_annotations() {
+        let package = crate::parse_html(concat!(
+            "<ruby><rb>東</rb><rb>京</rb><rt>とう</rt><rt>きょう</rt></ruby>",
+            "<ruby>明日<rp>(</rp><rt>あした</rt><rp>)</rp></ruby>",
+        ));
+        let annotations = ruby_annotations(package.as_document().root());
+        let pairs: Vec<(&str, &str)> = annotations
+            .iter()
+            .map(|a| (a.base.as_str(), a.reading.as_str()))
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![("東", "とう"), ("京", "きょう"), ("明日", "あした")]
+        );
+        assert_eq!(annotations[0].base_nodes.len(), 1);
+        assert_eq!(annotations[2].reading_nodes.len(), 1);
+    }
+
+    #[test]
+    fn test_table_cells() {
+        let text =
+            body_text("<table><tr><th>a</th><th>b</th></tr><tr><td>1</td><td>2</td></tr></table>");
+        assert_eq!(text, "a\tb\n1\t2");
+    }
+}
@@ -1,14 +1,20 @@
-use std::convert::TryFrom;
+use std::{
+    convert::TryFrom,
+    hash::{Hash, Hasher},
+};
 
 use html5ever::{tree_builder::NodeOrText, QualName};
 use sxd_document::{
-    dom::{ChildOfElement, ChildOfRoot, Document, ParentOfChild},
+    dom::{
+        ChildOfElement, ChildOfRoot, Comment, Document, Element, ParentOfChild,
+        ProcessingInstruction, Text,
+    },
     QName,
 };
 
 use crate::Handle;
 
-const HTML_NAMESPACE: &str = "http://www.w3.org/1999/xhtml";
+pub(crate) const HTML_NAMESPACE: &str = "http://www.w3.org/1999/xhtml";
 
 pub fn qualname_as_qname<'a>(qualname: &'a QualName) -> QName<'a> {
     let namespace_uri = match qualname.ns.as_ref() {
@@ -19,6 +25,77 @@ pub fn qualname_as_qname<'a>(qualname: &'a QualName) -> QName<'a> {
     QName::with_namespace_uri(namespace_uri, qualname.local.as_ref())
 }
 
+/// Returns the local name of `element` if it is an HTML element.
+///
+/// HTML elements are stored without a namespace, so elements carrying a
+/// namespace (SVG, MathML) yield `None`.
+pub fn html_local_name<'d>(element: &Element<'d>) -> Option<&'d str> {
+    let name = element.name();
+    match name.namespace_uri() {
+        None => Some(name.local_part()),
+        Some(_) => None,
+    }
+}
+
+/// Returns the elements below `parent` in document order.
+pub fn descendant_elements<'d>(parent: ParentOfChild<'d>) -> Vec<Element<'d>> {
+    let mut stack: Vec<_> = match parent {
+        ParentOfChild::Root(root) => root
+            .children()
+            .into_iter()
+            .filter_map(|child| child.element())
+            .collect(),
+        ParentOfChild::Element(element) => element
+            .children()
+            .into_iter()
+            .filter_map(|child| child.element())
+            .collect(),
+    };
+    stack.reverse();
+
+    let mut elements = vec![];
+    while let Some(element) = stack.pop() {
+        elements.push(element);
+        stack.extend(
+            element
+                .children()
+                .into_iter()
+                .rev()
+                .filter_map(|child| child.element()),
+        );
+    }
+    elements
+}
+
+/// Identifies an element without borrowing its package.
+///
+/// `sxd_document` allocates nodes in an arena that is only freed with the
+/// package, and compares and hashes elements by their address, so the bytes
+/// an element hashes to identify it for as long as its package lives, no
+/// matter how the tree is edited.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct ElementKey(Vec<u8>);
+
+impl ElementKey {
+    pub(crate) fn new(element: Element<'_>) -> Self {
+        struct Bytes(Vec<u8>);
+
+        impl Hasher for Bytes {
+            fn finish(&self) -> u64 {
+                0
+            }
+
+            fn write(&mut self, bytes: &[u8]) {
+                self.0.extend_from_slice(bytes);
+            }
+        }
+
+        let mut bytes = Bytes(vec![]);
+        element.hash(&mut bytes);
+        Self(bytes.0)
+    }
+}
+
 pub fn node_or_text_into_child_of_root(node_or_text: NodeOrText<Handle>) -> ChildOfRoot {
     match node_or_text {
         NodeOrText::AppendNode(handle) =>
@@ -62,40 +139,192 @@ pub fn parent_of_child_append_node_or_text(poc: &ParentOfChild, noe: NodeOrText<
     }
 }
 
-// pub fn deep_clone_element<'d>(elem: &Element<'d>) -> Element<'d> {
-//     let document = elem.document();
-//     let new_elem = document.create_element(elem.name());
-
-//     for child in elem.children() {
-//         let new_child = deep_clone_child_of_element(&child);
-//         new_elem.append_child(new_child);
-//     }
-
-//     new_elem
-// }
-
-// pub fn deep_clone_text<'d>(text: &Text<'d>) -> Text<'d> {
-//     let document = text.document();
-//     document.create_text(text.text())
-// }
-
-// pub fn deep_clone_comment<'d>(comment: &Comment<'d>) -> Comment<'d> {
-//     let document = comment.document();
-//     document.create_comment(comment.text())
-// }
-
-// pub fn deep_clone_processing_instruction<'d>(
-//     pi: &ProcessingInstruction<'d>,
-// ) -> ProcessingInstruction<'d> {
-//     let document = pi.document();
-//     document.create_processing_instruction(pi.target(), pi.value())
-// }
-
-// pub fn deep_clone_child_of_element<'d>(coe: &ChildOfElement<'d>) -> ChildOfElement<'d> {
-//     match coe {
-//         ChildOfElement::Element(e) => deep_clone_element(e).into(),
-//         ChildOfElement::Text(t) => deep_clone_text(t).into(),
-//         ChildOfElement::Comment(c) => deep_clone_comment(c).into(),
-//         ChildOfElement::ProcessingInstruction(pi) => deep_clone_processing_instruction(pi).into(),
-//     }
-// }
+/// Returns a copy of `element` and its descendants, created in the same
+/// document but not attached to it.
+///
+/// The copy keeps the attributes, namespace declarations and preferred
+/// prefixes of the original, along with copies of its text, comment,
+/// processing instruction and element children.
+pub fn deep_clone_element<'d>(elem: &Element<'d>) -> Element<'d> {
+    import_element(&elem.document(), elem, None)
+}
+
+/// Returns a copy of `text`, created in the same document but not attached
+/// to it.
+pub fn deep_clone_text<'d>(text: &Text<'d>) -> Text<'d> {
+    let document = text.document();
+    document.create_text(text.text())
+}
+
+/// Returns a copy of `comment`, created in the same document but not
+/// attached to it.
+pub fn deep_clone_comment<'d>(comment: &Comment<'d>) -> Comment<'d> {
+    let document = comment.document();
+    document.create_comment(comment.text())
+}
+
+/// Returns a copy of `pi`, created in the same document but not attached to
+/// it.
+pub fn deep_clone_processing_instruction<'d>(
+    pi: &ProcessingInstruction<'d>,
+) -> ProcessingInstruction<'d> {
+    let document = pi.document();
+    document.create_processing_instruction(pi.target(), pi.value())
+}
+
+/// Returns a deep copy of `coe`, created in the same document but not
+/// attached to it. See [`deep_clone_element`].
+pub fn deep_clone_child_of_element<'d>(coe: &ChildOfElement<'d>) -> ChildOfElement<'d> {
+    match coe {
+        ChildOfElement::Element(e) => deep_clone_element(e).into(),
+        ChildOfElement::Text(t) => deep_clone_text(t).into(),
+        ChildOfElement::Comment(c) => deep_clone_comment(c).into(),
+        ChildOfElement::ProcessingInstruction(pi) => deep_clone_processing_instruction(pi).into(),
+    }
+}
+
+/// Copies `node` and its descendants into `document`, which may belong to
+/// another [`Package`](sxd_document::Package), and returns the copy without
+/// attaching it.
+///
+/// Elements are copied as by [`deep_clone_element`]. The namespace
+/// declarations in scope at `node` are repeated on the copy, so prefixed
+/// names stay resolvable wherever it is attached.
+///
+/// ```rust
+/// let page = sxd_html::parse_html("<article><h1>Title</h1><p>Text</p></article>");
+/// let article = sxd_html::select_one(page.as_document().root(), "//article").unwrap();
+///
+/// let package = sxd_html::parse_html("<main></main>");
+/// let document = package.as_document();
+/// let main = sxd_html::select_one(document.root(), "//main").unwrap().element().unwrap();
+/// main.append_child(sxd_html::import_node(&document, article.element().unwrap()));
+/// assert_eq!(
+///     sxd_html::inner_html(main),
+///     "<article><h1>Title</h1><p>Text</p></article>"
+/// );
+/// ```
+pub fn import_node<'d, 's>(
+    document: &Document<'d>,
+    node: impl Into<ChildOfElement<'s>>,
+) -> ChildOfElement<'d> {
+    import_child(document, node.into(), None)
+}
+
+fn import_child<'d>(
+    document: &Document<'d>,
+    child: ChildOfElement,
+    parent: Option<&Element>,
+) -> ChildOfElement<'d> {
+    match child {
+        ChildOfElement::Element(e) => import_element(document, &e, parent).into(),
+        ChildOfElement::Text(t) => document.create_text(t.text()).into(),
+        ChildOfElement::Comment(c) => document.create_comment(c.text()).into(),
+        ChildOfElement::ProcessingInstruction(pi) => document
+            .create_processing_instruction(pi.target(), pi.value())
+            .into(),
+    }
+}
+
+/// Copies `elem` into `document`. `parent` is the element the original
+/// child is being copied from, whose namespace declarations the copy
+/// inherits from its own copied parent; without one, everything in scope is
+/// declared on the copy.
+fn import_element<'d>(
+    document: &Document<'d>,
+    elem: &Element,
+    parent: Option<&Element>,
+) -> Element<'d> {
+    let copy = document.create_element(elem.name());
+    copy.set_preferred_prefix(elem.preferred_prefix());
+
+    let inherited = parent.map(Element::namespaces_in_scope).unwrap_or_default();
+    for namespace in elem.namespaces_in_scope() {
+        let is_inherited = inherited
+            .iter()
+            .any(|ns| ns.prefix() == namespace.prefix() && ns.uri() == namespace.uri());
+        if namespace.prefix() != "xml" && !is_inherited {
+            copy.register_prefix(namespace.prefix(), namespace.uri());
+        }
+    }
+    let default_namespace_uri = match parent {
+        Some(_) => elem.default_namespace_uri(),
+        None => elem.recursive_default_namespace_uri(),
+    };
+    copy.set_default_namespace_uri(default_namespace_uri);
+
+    for attribute in elem.attributes() {
+        let copied = copy.set_attribute_value(attribute.name(), attribute.value());
+        copied.set_preferred_prefix(attribute.preferred_prefix());
+    }
+
+    for child in elem.children() {
+        copy.append_child(import_child(document, child, Some(elem)));
+    }
+
+    copy
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{html_context, select_one, to_html, Query};
+
+    #[test]
+    fn test_deep_clone_element() {
+        let package = crate::parse_html(
+            r#"<div id="a" class="x"><p>one<!--note--><b>two</b></p><?pi data?></div>"#,
+        );
+        let root = package.as_document().root();
+        let div = select_one(root, "//div").unwrap().element().unwrap();
+
+        let copy = deep_clone_element(&div);
+        assert!(copy.parent().is_none());
+        assert_eq!(to_html(copy), to_html(div));
+
+        copy.set_attribute_value("id", "b");
+        copy.clear_children();
+        assert_eq!(div.attribute_value("id"), Some("a"));
+        assert_eq!(div.children().len(), 2);
+    }
+
+    #[test]
+    fn test_import_node_across_packages() {
+        let xml =
+            sxd_document::parser::parse(r#"<r xmlns:x="urn:x"><x:item x:id="1">text</x:item></r>"#)
+                .unwrap();
+        let item = select_one(xml.as_document().root(), "/r/*")
+            .unwrap()
+            .element()
+            .unwrap();
+        let page = crate::parse_html(r#"<svg><a xlink:href="u">link</a></svg>"#);
+        let query = Query::with_context(html_context());
+        let svg = query
+            .select_one(page.as_document().root(), "//svg:svg")
+            .unwrap()
+            .element()
+            .unwrap();
+
+        let package = crate::parse_html("<main></main>");
+        let document = package.as_document();
+        let main = select_one(document.root(), "//main")
+            .unwrap()
+            .element()
+            .unwrap();
+        main.append_child(import_node(&document, item));
+        main.append_child(import_node(&document, svg));
+        drop(xml);
+        drop(page);
+
+        let imported = main.children()[0].element().unwrap();
+        assert_eq!(imported.name().namespace_uri(), Some("urn:x"));
+        assert_eq!(imported.namespace_uri_for_prefix("x"), Some("urn:x"));
+        assert_eq!(imported.attribute_value(("urn:x", "id")), Some("1"));
+        assert_eq!(
+            query
+                .select_text(document.root(), "//main/svg:svg/svg:a/@xlink:href")
+                .unwrap(),
+            "u"
+        );
+    }
+}
@@ -0,0 +1,241 @@
+//! Derive macro for `sxd_html::FromHtml`.
+//!
+//! This crate is re-exported by `sxd_html` with the `derive` feature; see
+//! the documentation of `sxd_html::FromHtml` for the supported attributes.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse::ParseStream, parse_macro_input, spanned::Spanned, Attribute, Data, DeriveInput, Fields,
+    GenericArgument, Ident, LitStr, Path, PathArguments, Token, Type,
+};
+
+/// Implements `sxd_html::FromHtml` and `sxd_html::FromHtmlNode` for a
+/// struct with named fields, each annotated with `#[xpath("...")]` or
+/// `#[css("...")]`.
+#[proc_macro_derive(FromHtml, attributes(xpath, css))]
+pub fn derive_from_html(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+/// How many matches a field holds, given by its type.
+enum Cardinality {
+    Required,
+    Optional,
+    Repeated,
+}
+
+/// The options of an `#[xpath]` or `#[css]` attribute.
+struct FieldAttribute {
+    expression: LitStr,
+    css: bool,
+    attribute: Option<LitStr>,
+    trim: bool,
+    parse: bool,
+    parse_with: Option<Path>,
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new(
+                    input.ident.span(),
+                    "`FromHtml` can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new(
+                input.ident.span(),
+                "`FromHtml` can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut initializers = vec![];
+    for field in fields {
+        let ident = field
+            .ident
+            .as_ref()
+            .ok_or_else(|| syn::Error::new(field.span(), "field has no name"))?;
+        let attribute = field_attribute(ident, &field.attrs)?;
+        initializers.push(field_initializer(ident, &field.ty, &attribute));
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::sxd_html::FromHtml for #name #ty_generics #where_clause {
+            fn from_node<'__d>(
+                node: impl ::core::convert::Into<::sxd_html::__private::Node<'__d>>,
+            ) -> ::core::result::Result<Self, ::sxd_html::ExtractError> {
+                let node = node.into();
+                ::core::result::Result::Ok(Self {
+                    #(#initializers)*
+                })
+            }
+        }
+
+        impl #impl_generics ::sxd_html::FromHtmlNode for #name #ty_generics #where_clause {
+            fn from_html_node(
+                node: ::sxd_html::__private::Node<'_>,
+            ) -> ::core::result::Result<Self, ::sxd_html::ExtractError> {
+                <Self as ::sxd_html::FromHtml>::from_node(node)
+            }
+        }
+    })
+}
+
+fn field_attribute(ident: &Ident, attributes: &[Attribute]) -> syn::Result<FieldAttribute> {
+    let mut found = None;
+    for attribute in attributes {
+        let css = if attribute.path().is_ident("css") {
+            true
+        } else if attribute.path().is_ident("xpath") {
+            false
+        } else {
+            continue;
+        };
+        if found.is_some() {
+            return Err(syn::Error::new(
+                attribute.span(),
+                "a field can only have one `#[xpath]` or `#[css]` attribute",
+            ));
+        }
+        found = Some(attribute.parse_args_with(|input: ParseStream| parse_options(input, css))?);
+    }
+
+    let attribute = found.ok_or_else(|| {
+        syn::Error::new(
+            ident.span(),
+            format!(
+                "field `{}` needs an `#[xpath]` or `#[css]` attribute",
+                ident
+            ),
+        )
+    })?;
+
+    if !attribute.css {
+        let expression = attribute.expression.value();
+        match sxd_xpath::Factory::new().build(&expression) {
+            Ok(Some(_)) => {}
+            Ok(None) => {
+                return Err(syn::Error::new(
+                    attribute.expression.span(),
+                    "XPath expression is empty",
+                ))
+            }
+            Err(error) => {
+                return Err(syn::Error::new(
+                    attribute.expression.span(),
+                    format!("invalid XPath `{}`: {}", expression, error),
+                ))
+            }
+        }
+    }
+    Ok(attribute)
+}
+
+fn parse_options(input: ParseStream, css: bool) -> syn::Result<FieldAttribute> {
+    let mut attribute = FieldAttribute {
+        expression: input.parse()?,
+        css,
+        attribute: None,
+        trim: false,
+        parse: false,
+        parse_with: None,
+    };
+
+    while !input.is_empty() {
+        input.parse::<Token![,]>()?;
+        if input.is_empty() {
+            break;
+        }
+        let option: Ident = input.parse()?;
+        match option.to_string().as_str() {
+            "attr" => {
+                input.parse::<Token![=]>()?;
+                attribute.attribute = Some(input.parse()?);
+            }
+            "trim" => attribute.trim = true,
+            "parse" => attribute.parse = true,
+            "parse_with" => {
+                input.parse::<Token![=]>()?;
+                attribute.parse_with = Some(input.parse()?);
+            }
+            name => {
+                return Err(syn::Error::new(
+                    option.span(),
+                    format!(
+                        "unknown option `{}`, expected `attr`, `trim`, `parse` or `parse_with`",
+                        name
+                    ),
+                ))
+            }
+        }
+    }
+    Ok(attribute)
+}
+
+fn field_initializer(ident: &Ident, ty: &Type, attribute: &FieldAttribute) -> TokenStream2 {
+    let (cardinality, value_type) = cardinality(ty);
+    let cardinality = match cardinality {
+        Cardinality::Required => quote!(required),
+        Cardinality::Optional => quote!(optional),
+        Cardinality::Repeated => quote!(repeated),
+    };
+
+    let trim = attribute.trim;
+    let convert = match (&attribute.parse_with, attribute.parse || attribute.trim) {
+        (Some(path), _) => quote! {
+            |node| ::sxd_html::__private::parse_with(node, #trim, #path)
+        },
+        (None, true) => quote! {
+            |node| ::sxd_html::__private::parse::<#value_type>(node, #trim)
+        },
+        (None, false) => quote!(::sxd_html::__private::convert::<#value_type>),
+    };
+
+    let expression = &attribute.expression;
+    let css = attribute.css;
+    let attribute = match &attribute.attribute {
+        Some(name) => quote!(::core::option::Option::Some(#name)),
+        None => quote!(::core::option::Option::None),
+    };
+    let field = ident.to_string();
+
+    quote! {
+        #ident: ::sxd_html::__private::select(node, #expression, #css, #attribute)
+            .and_then(|nodes| ::sxd_html::__private::#cardinality(nodes, #convert))
+            .map_err(|error| ::sxd_html::__private::within(error, #field, #expression))?,
+    }
+}
+
+/// Returns the cardinality of a field of type `ty` and the type of its
+/// values, recognizing `Option<T>` and `Vec<T>` by name.
+fn cardinality(ty: &Type) -> (Cardinality, &Type) {
+    if let Type::Path(path) = ty {
+        if let Some(segment) = path.path.segments.last() {
+            let cardinality = match segment.ident.to_string().as_str() {
+                "Option" => Some(Cardinality::Optional),
+                "Vec" => Some(Cardinality::Repeated),
+                _ => None,
+            };
+            if let (Some(cardinality), PathArguments::AngleBracketed(arguments)) =
+                (cardinality, &segment.arguments)
+            {
+                if let Some(GenericArgument::Type(inner)) = arguments.args.first() {
+                    return (cardinality, inner);
+                }
+            }
+        }
+    }
+    (Cardinality::Required, ty)
+}
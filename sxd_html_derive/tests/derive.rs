@@ -0,0 +1,116 @@
+use sxd_html::{ExtractErrorKind, FromHtml};
+
+const HTML: &str = r#"
+<article>
+  <h1><a href="/rust-lang/rust"><span>rust-lang</span> / rust</a></h1>
+  <span class="stars"> 1,234 </span>
+  <a class="topic">compiler</a><a class="topic">language</a>
+  <div class="owner"><b>rust-lang</b><i>org</i></div>
+</article>
+"#;
+
+#[derive(FromHtml, Debug, PartialEq)]
+struct Owner {
+    #[xpath("./b")]
+    name: String,
+    #[css("i", trim)]
+    kind: String,
+}
+
+#[derive(FromHtml, Debug, PartialEq)]
+struct Repo {
+    #[xpath("//h1/a/@href")]
+    url: String,
+    #[css("h1 > a", attr = "href")]
+    href: String,
+    #[xpath("normalize-space(//h1/a)")]
+    title: String,
+    #[css("span.stars", parse_with = parse_count)]
+    stars: u32,
+    #[css("a.topic")]
+    topics: Vec<String>,
+    #[css(".license")]
+    license: Option<String>,
+    #[xpath("count(//a[@class='topic'])")]
+    topic_count: usize,
+    #[css("div.owner")]
+    owner: Owner,
+}
+
+fn parse_count(text: &str) -> Result<u32, std::num::ParseIntError> {
+    text.trim().replace(',', "").parse()
+}
+
+#[test]
+fn test_derive_from_html() {
+    let repo = Repo::from_html(HTML).unwrap();
+    assert_eq!(
+        repo,
+        Repo {
+            url: "/rust-lang/rust".to_string(),
+            href: "/rust-lang/rust".to_string(),
+            title: "rust-lang / rust".to_string(),
+            stars: 1234,
+            topics: vec!["compiler".to_string(), "language".to_string()],
+            license: None,
+            topic_count: 2,
+            owner: Owner {
+                name: "rust-lang".to_string(),
+                kind: "org".to_string(),
+            },
+        }
+    );
+}
+
+#[test]
+fn test_from_node() {
+    #[derive(FromHtml)]
+    struct Topic {
+        #[xpath(".")]
+        name: String,
+    }
+
+    let package = sxd_html::parse_html(HTML);
+    let topics = sxd_html::select_css(package.as_document().root(), "a.topic").unwrap();
+    let names: Vec<String> = topics
+        .document_order()
+        .into_iter()
+        .map(|node| Topic::from_node(node).unwrap().name)
+        .collect();
+    assert_eq!(names, vec!["compiler", "language"]);
+}
+
+#[test]
+fn test_errors_name_the_field() {
+    #[derive(FromHtml, Debug)]
+    struct Missing {
+        #[css("span.forks")]
+        _forks: u32,
+    }
+
+    #[derive(FromHtml, Debug)]
+    struct Invalid {
+        #[css(".stars", trim)]
+        _stars: u32,
+    }
+
+    #[derive(FromHtml, Debug)]
+    struct NestedInvalid {
+        #[css("article")]
+        _inner: Invalid,
+    }
+
+    let error = Missing::from_html(HTML).unwrap_err();
+    assert_eq!(error.field(), "_forks");
+    assert_eq!(error.expression(), "span.forks");
+    assert_eq!(error.kind(), &ExtractErrorKind::Missing);
+
+    let error = NestedInvalid::from_html(HTML).unwrap_err();
+    assert_eq!(error.field(), "_inner._stars");
+    assert_eq!(error.expression(), ".stars");
+    assert!(matches!(error.kind(), ExtractErrorKind::Parse { text, .. } if text == "1,234"));
+    assert_eq!(
+        error.to_string(),
+        "unable to extract `_inner._stars` with `.stars`: unable to parse \"1,234\": invalid digit found in string"
+    );
+}